@@ -1,6 +1,5 @@
 use activitypub_federation::{
-    activity_queue::queue_activity, config::Data, protocol::context::WithContext,
-    traits::ActivityHandler,
+    config::Data, protocol::context::WithContext, traits::ActivityHandler,
 };
 use serde::{Deserialize, Serialize};
 use ulid::Ulid;
@@ -8,15 +7,19 @@ use url::Url;
 
 use crate::{
     config::CONFIG,
+    delivery::queue_activity,
     error::{Context, Error},
     state::State,
 };
 
 pub mod announce;
+pub mod block;
+pub mod collection;
 pub mod delete;
 pub mod flag;
 pub mod follow;
 pub mod like;
+pub mod move_account;
 pub mod note;
 pub mod other_activity;
 pub mod person;
@@ -35,44 +38,62 @@ pub fn generate_object_id() -> Result<Url, Error> {
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum NoteOrAnnounce {
-    Note(self::note::Note),
+    Note(Box<self::note::Note>),
     Announce(self::announce::Announce),
 }
 
 impl NoteOrAnnounce {
+    pub fn into_activity(self) -> Result<CreateNoteOrAnnounce, Error> {
+        match self {
+            Self::Note(note) => Ok(CreateNoteOrAnnounce::CreateNote(Box::new(
+                self::note::CreateNote::new(*note)?,
+            ))),
+            Self::Announce(announce) => Ok(CreateNoteOrAnnounce::Announce(announce)),
+        }
+    }
+
     #[tracing::instrument(skip(data))]
     pub async fn send(self, data: &Data<State>, inboxes: Vec<Url>) -> Result<(), Error> {
-        let me = self::person::LocalPerson::get(&*data.db).await?;
-        match self {
-            Self::Note(note) => {
-                let create_note = self::note::CreateNote::new(note)?;
+        match self.into_activity()? {
+            CreateNoteOrAnnounce::CreateNote(create_note) => {
                 let with_context = WithContext::new_default(create_note);
-                queue_activity(&with_context, &me, inboxes, data).await?;
+                queue_activity(&with_context, inboxes, data).await?;
                 Ok(())
             }
-            Self::Announce(announce) => {
+            CreateNoteOrAnnounce::Announce(announce) => {
                 let with_context = WithContext::new_default(announce);
-                queue_activity(&with_context, &me, inboxes, data).await?;
+                queue_activity(&with_context, inboxes, data).await?;
                 Ok(())
             }
         }
     }
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum CreateNoteOrAnnounce {
+    CreateNote(Box<self::note::CreateNote>),
+    Announce(self::announce::Announce),
+}
+
 #[derive(Deserialize, Serialize)]
 #[serde(untagged)]
 #[enum_delegate::implement(ActivityHandler)]
 pub enum Activity {
     AcceptFollow(self::follow::FollowAccept),
     Announce(self::announce::Announce),
+    Block(self::block::Block),
     CreateFollow(self::follow::Follow),
-    CreateNote(self::note::CreateNote),
+    CreateNote(Box<self::note::CreateNote>),
     Delete(self::delete::Delete),
     Flag(self::flag::Flag),
     Like(self::like::Like),
+    Move(self::move_account::Move),
     RejectFollow(self::follow::FollowReject),
+    UndoBlock(self::undo::Undo<self::block::Block>),
     UndoFollow(self::undo::Undo<self::follow::Follow>),
     UndoLike(self::undo::Undo<self::like::Like>),
+    UpdateNote(Box<self::note::UpdateNote>),
     UpdatePerson(Box<self::person::PersonUpdate>),
     /// Fallback
     Other(self::other_activity::OtherActivity),
@@ -0,0 +1,145 @@
+use std::sync::Arc;
+
+use activitypub_federation::{
+    config::{Data, UrlVerifier},
+    kinds::activity::BlockType,
+    protocol::{context::WithContext, verification::verify_domains_match},
+    traits::ActivityHandler,
+};
+use async_trait::async_trait;
+use derivative::Derivative;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QuerySelect, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+use url::Url;
+
+use crate::{
+    delivery::queue_activity,
+    entity::{block, domain_block, follow, follower, user},
+    error::{Context, Error},
+    state::State,
+};
+
+use super::person::LocalPerson;
+
+#[derive(Clone, Derivative, Deserialize, Serialize)]
+#[derivative(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Block {
+    #[serde(rename = "type")]
+    pub ty: BlockType,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub id: Url,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub actor: Url,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub object: Url,
+}
+
+impl Block {
+    pub fn new(to_id: Ulid, object: Url) -> Result<Self, Error> {
+        Ok(Self {
+            ty: Default::default(),
+            id: block::Model::ap_id_from_id(to_id)?,
+            actor: LocalPerson::id(),
+            object,
+        })
+    }
+
+    #[tracing::instrument(skip(data))]
+    pub async fn send(self, data: &Data<State>, inbox: Url) -> Result<(), Error> {
+        let with_context = WithContext::new_default(self);
+        queue_activity(&with_context, vec![inbox], data).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ActivityHandler for Block {
+    type DataType = State;
+    type Error = Error;
+
+    fn id(&self) -> &Url {
+        &self.id
+    }
+
+    fn actor(&self) -> &Url {
+        &self.actor
+    }
+
+    #[tracing::instrument(skip(_data))]
+    async fn verify(&self, _data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        verify_domains_match(&self.id, &self.actor).context_bad_request("failed to verify domain")
+    }
+
+    #[tracing::instrument(skip(data))]
+    async fn receive(self, data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        if self.object != LocalPerson::id() {
+            return Ok(());
+        }
+
+        // Blocked by a remote user, so drop follow relationships in both directions
+        let tx = data
+            .db
+            .begin()
+            .await
+            .context_internal_server_error("failed to begin database transaction")?;
+
+        let user_id = user::Entity::find()
+            .filter(user::Column::Uri.eq(self.actor.as_str()))
+            .select_only()
+            .column(user::Column::Id)
+            .into_tuple::<uuid::Uuid>()
+            .one(&tx)
+            .await
+            .context_internal_server_error("failed to query database")?;
+
+        if let Some(user_id) = user_id {
+            follow::Entity::delete_by_id(user_id)
+                .exec(&tx)
+                .await
+                .context_internal_server_error("failed to delete from database")?;
+            follower::Entity::delete_by_id(user_id)
+                .exec(&tx)
+                .await
+                .context_internal_server_error("failed to delete from database")?;
+        }
+
+        tx.commit()
+            .await
+            .context_internal_server_error("failed to commit database transaction")?;
+
+        Ok(())
+    }
+}
+
+/// Rejects activities from, fetches from and deliveries to blocked domains.
+#[derive(Clone)]
+pub struct DomainBlockVerifier {
+    db: Arc<DatabaseConnection>,
+}
+
+impl DomainBlockVerifier {
+    pub fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl UrlVerifier for DomainBlockVerifier {
+    async fn verify(&self, url: &Url) -> Result<(), activitypub_federation::error::Error> {
+        match domain_block::Model::is_blocked(url, &*self.db).await {
+            Ok(false) => Ok(()),
+            Ok(true) => Err(activitypub_federation::error::Error::Other(format!(
+                "domain of {} is blocked",
+                url
+            ))),
+            Err(error) => Err(activitypub_federation::error::Error::Other(format!(
+                "failed to check domain block\n{:?}",
+                error.inner
+            ))),
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use activitypub_federation::kinds::collection::{OrderedCollectionPageType, OrderedCollectionType};
+use derivative::Derivative;
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+use url::Url;
+
+pub const COLLECTION_PAGE_SIZE: u64 = 20;
+
+#[derive(Clone, Derivative, Deserialize, Serialize)]
+#[derivative(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderedCollection<T> {
+    #[serde(rename = "type")]
+    pub ty: OrderedCollectionType,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub id: Url,
+    pub total_items: u64,
+    #[derivative(Debug(format_with = "crate::fmt::debug_format_option_display"))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first: Option<Url>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ordered_items: Option<Vec<T>>,
+}
+
+#[derive(Clone, Derivative, Deserialize, Serialize)]
+#[derivative(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderedCollectionPage<T> {
+    #[serde(rename = "type")]
+    pub ty: OrderedCollectionPageType,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub id: Url,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub part_of: Url,
+    pub total_items: u64,
+    #[derivative(Debug(format_with = "crate::fmt::debug_format_option_display"))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next: Option<Url>,
+    pub ordered_items: Vec<T>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum OrderedCollectionOrPage<T> {
+    Collection(OrderedCollection<T>),
+    Page(OrderedCollectionPage<T>),
+}
+
+/// Either a link to an object or the object itself embedded.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum IdOrObject<T> {
+    Id(Url),
+    Object(T),
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ObjectWithId {
+    pub id: Url,
+}
+
+impl IdOrObject<ObjectWithId> {
+    pub fn id(&self) -> &Url {
+        match self {
+            Self::Id(id) => id,
+            Self::Object(object) => &object.id,
+        }
+    }
+}
+
+/// Remote collection or collection page, either ordered or not.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteCollection {
+    #[serde(default)]
+    pub first: Option<IdOrObject<Box<RemoteCollection>>>,
+    #[serde(default)]
+    pub next: Option<Url>,
+    #[serde(default)]
+    pub items: Vec<IdOrObject<ObjectWithId>>,
+    #[serde(default)]
+    pub ordered_items: Vec<IdOrObject<ObjectWithId>>,
+}
+
+/// Constructs URL of a collection page, e.g. `https://example.com/person/outbox?page=true&after=...`
+pub fn collection_page_url(collection_id: &Url, after: Option<Ulid>) -> Url {
+    let mut url = collection_id.clone();
+    url.query_pairs_mut().append_pair("page", "true");
+    if let Some(after) = after {
+        url.query_pairs_mut()
+            .append_pair("after", &after.to_string());
+    }
+    url
+}
@@ -1,5 +1,4 @@
 use activitypub_federation::{
-    activity_queue::queue_activity,
     config::Data,
     kinds::{activity::DeleteType, object::TombstoneType},
     protocol::{context::WithContext, verification::verify_domains_match},
@@ -12,6 +11,7 @@ use serde::{Deserialize, Serialize};
 use url::Url;
 
 use crate::{
+    delivery::queue_activity,
     entity::{post, user},
     error::{Context, Error},
     format_err,
@@ -59,9 +59,8 @@ impl Delete {
 
     #[tracing::instrument(skip(data))]
     pub async fn send(self, data: &Data<State>, inboxes: Vec<Url>) -> Result<(), Error> {
-        let me = LocalPerson::get(&*data.db).await?;
         let with_context = WithContext::new_default(self);
-        queue_activity(&with_context, &me, inboxes, data).await?;
+        queue_activity(&with_context, inboxes, data).await?;
         Ok(())
     }
 }
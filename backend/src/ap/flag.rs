@@ -1,5 +1,4 @@
 use activitypub_federation::{
-    activity_queue::queue_activity,
     config::Data,
     fetch::object_id::ObjectId,
     kinds::activity::FlagType,
@@ -15,6 +14,7 @@ use url::Url;
 
 use crate::{
     ap::person::LocalPerson,
+    delivery::queue_activity,
     entity::{report, user},
     error::{Context, Error},
     queue::{Event, Notification, NotificationType},
@@ -50,9 +50,8 @@ impl Flag {
     }
 
     pub async fn send(self, data: &Data<State>, inbox: Url) -> Result<(), Error> {
-        let me = LocalPerson::get(&*data.db).await?;
         let with_context = WithContext::new_default(self);
-        queue_activity(&with_context, &me, vec![inbox], data).await?;
+        queue_activity(&with_context, vec![inbox], data).await?;
         Ok(())
     }
 }
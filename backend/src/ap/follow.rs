@@ -1,5 +1,4 @@
 use activitypub_federation::{
-    activity_queue::queue_activity,
     config::Data,
     fetch::object_id::ObjectId,
     kinds::activity::{AcceptType, FollowType, RejectType},
@@ -7,18 +6,18 @@ use activitypub_federation::{
     traits::{ActivityHandler, Object},
 };
 use async_trait::async_trait;
+use chrono::Utc;
 use derivative::Derivative;
 use sea_orm::{
-    ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, QueryFilter, QuerySelect,
-    TransactionTrait,
+    sea_query::OnConflict, ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, PaginatorTrait,
+    QueryFilter, QuerySelect, TransactionTrait,
 };
 use serde::{Deserialize, Serialize};
-use ulid::Ulid;
 use url::Url;
 
 use crate::{
-    config::CONFIG,
-    entity::{follow, follower, user},
+    delivery::queue_activity,
+    entity::{follow, follow_request, follower, setting, user},
     error::{Context, Error},
     format_err,
     queue::{Event, Notification, NotificationType},
@@ -43,12 +42,11 @@ pub struct Follow {
 
 impl Follow {
     pub async fn send(self, data: &Data<State>) -> Result<(), Error> {
-        let me = LocalPerson::get(&*data.db).await?;
         let object: ObjectId<user::Model> = self.object.clone().into();
         let inbox = object.dereference(data).await?.inbox;
         let inbox = Url::parse(&inbox).context_internal_server_error("malformed user inbox URL")?;
         let with_context = WithContext::new_default(self);
-        queue_activity(&with_context, &me, vec![inbox], data).await?;
+        queue_activity(&with_context, vec![inbox], data).await?;
         Ok(())
     }
 }
@@ -73,14 +71,44 @@ impl ActivityHandler for Follow {
 
     #[tracing::instrument(skip(data))]
     async fn receive(self, data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        let setting = setting::Model::get(&*data.db).await?;
+        if setting.user_manually_approves_followers {
+            let actor: ObjectId<user::Model> = self.actor.clone().into();
+            let from_user = actor.dereference(data).await?;
+
+            let existing_follower_count = follower::Entity::find_by_id(from_user.id)
+                .count(&*data.db)
+                .await
+                .context_internal_server_error("failed to query database")?;
+            if existing_follower_count == 0 {
+                let follow_request_activemodel = follow_request::ActiveModel {
+                    from_id: ActiveValue::Set(from_user.id),
+                    uri: ActiveValue::Set(self.id().to_string()),
+                    created_at: ActiveValue::Set(Utc::now().fixed_offset()),
+                };
+                follow_request::Entity::insert(follow_request_activemodel)
+                    .on_conflict(
+                        OnConflict::column(follow_request::Column::FromId)
+                            .update_column(follow_request::Column::Uri)
+                            .to_owned(),
+                    )
+                    .exec(&*data.db)
+                    .await
+                    .context_internal_server_error("failed to insert to database")?;
+
+                let event =
+                    Event::Notification(Notification::new(NotificationType::FollowRequested {
+                        user_id: from_user.id.into(),
+                    }));
+                event.send(&*data.db).await?;
+
+                return Ok(());
+            }
+        }
+
         let follower = follower::Model::from_json(self.clone(), data).await?;
 
-        let accept = FollowAccept {
-            ty: Default::default(),
-            id: generate_object_id()?,
-            actor: LocalPerson::id(),
-            object: self,
-        };
+        let accept = FollowAccept::new(self)?;
         accept.send(data).await?;
 
         let event = Event::Notification(Notification::new(NotificationType::CreateFollower {
@@ -106,14 +134,22 @@ pub struct FollowAccept {
 }
 
 impl FollowAccept {
+    pub fn new(follow: Follow) -> Result<Self, Error> {
+        Ok(Self {
+            ty: Default::default(),
+            id: generate_object_id()?,
+            actor: LocalPerson::id(),
+            object: follow,
+        })
+    }
+
     #[tracing::instrument(skip(data))]
     pub async fn send(self, data: &Data<State>) -> Result<(), Error> {
-        let me = LocalPerson::get(&*data.db).await?;
         let actor: ObjectId<user::Model> = self.object.actor.clone().into();
         let inbox = actor.dereference(data).await?.inbox;
         let inbox = Url::parse(&inbox).context_internal_server_error("malformed user inbox URL")?;
         let with_context = WithContext::new_default(self);
-        queue_activity(&with_context, &me, vec![inbox], data).await?;
+        queue_activity(&with_context, vec![inbox], data).await?;
         Ok(())
     }
 }
@@ -171,30 +207,18 @@ pub struct FollowReject {
 }
 
 impl FollowReject {
-    pub fn new(user_id: Ulid, user_uri: Url) -> Result<Self, Error> {
+    pub fn new(follow: Follow) -> Result<Self, Error> {
         Ok(Self {
             ty: Default::default(),
             id: generate_object_id()?,
             actor: LocalPerson::id(),
-            object: Follow {
-                ty: Default::default(),
-                id: Some(
-                    Url::parse(&format!(
-                        "https://{}/follower/{}",
-                        CONFIG.public_domain, user_id
-                    ))
-                    .context_internal_server_error("failed to construct URL")?,
-                ),
-                actor: user_uri,
-                object: LocalPerson::id(),
-            },
+            object: follow,
         })
     }
 
     pub async fn send(self, data: &Data<State>, inbox: Url) -> Result<(), Error> {
-        let me = LocalPerson::get(&*data.db).await?;
         let with_context = WithContext::new_default(self);
-        queue_activity(&with_context, &me, vec![inbox], data).await?;
+        queue_activity(&with_context, vec![inbox], data).await?;
         Ok(())
     }
 }
@@ -1,5 +1,4 @@
 use activitypub_federation::{
-    activity_queue::queue_activity,
     config::Data,
     fetch::object_id::ObjectId,
     kinds::activity::LikeType,
@@ -13,13 +12,14 @@ use serde::{Deserialize, Serialize};
 use url::Url;
 
 use crate::{
+    delivery::queue_activity,
     entity::{post, reaction, user},
     error::{Context, Error},
     queue::{Event, Notification, NotificationType, Update},
     state::State,
 };
 
-use super::{person::LocalPerson, tag::Tag};
+use super::tag::Tag;
 
 #[derive(Derivative, Deserialize, Serialize)]
 #[derivative(Debug)]
@@ -42,7 +42,6 @@ pub struct Like {
 impl Like {
     #[tracing::instrument(skip(data))]
     pub async fn send(self, data: &Data<State>) -> Result<(), Error> {
-        let me = LocalPerson::get(&*data.db).await?;
         let post = self.object.dereference(data).await?;
         let user = post
             .find_related(user::Entity)
@@ -53,7 +52,7 @@ impl Like {
         let inbox =
             Url::parse(&user.inbox).context_internal_server_error("malformed user inbox URL")?;
         let with_context = WithContext::new_default(self);
-        queue_activity(&with_context, &me, vec![inbox], data).await?;
+        queue_activity(&with_context, vec![inbox], data).await?;
         Ok(())
     }
 }
@@ -0,0 +1,178 @@
+use activitypub_federation::{
+    config::Data,
+    fetch::object_id::ObjectId,
+    kinds::activity::MoveType,
+    protocol::{context::WithContext, verification::verify_domains_match},
+    traits::{ActivityHandler, Object},
+};
+use async_trait::async_trait;
+use derivative::Derivative;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, EntityTrait, ModelTrait, PaginatorTrait, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{
+    delivery::queue_activity,
+    entity::{follow, user},
+    error::{Context, Error},
+    format_err,
+    queue::{Event, Notification, NotificationType},
+    state::State,
+    util::get_follower_inboxes,
+};
+
+use super::{follow::Follow, generate_object_id, person::LocalPerson, undo::Undo};
+
+/// `Move` of an account to `target`, which must list `object` in its `alsoKnownAs`
+#[derive(Derivative, Deserialize, Serialize)]
+#[derivative(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Move {
+    #[serde(rename = "type")]
+    pub ty: MoveType,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub id: Url,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub actor: Url,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub object: Url,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub target: Url,
+    #[derivative(Debug(format_with = "crate::fmt::debug_format_vec_display"))]
+    #[serde(default)]
+    pub to: Vec<Url>,
+}
+
+impl Move {
+    pub fn new_self(target: Url) -> Result<Self, Error> {
+        Ok(Self {
+            ty: Default::default(),
+            id: generate_object_id()?,
+            actor: LocalPerson::id(),
+            object: LocalPerson::id(),
+            target,
+            to: vec![LocalPerson::followers()?],
+        })
+    }
+
+    #[tracing::instrument(skip(data))]
+    pub async fn send(self, data: &Data<State>) -> Result<(), Error> {
+        let inboxes = get_follower_inboxes(&*data.db).await?;
+        let with_context = WithContext::new_default(self);
+        queue_activity(&with_context, inboxes, data).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ActivityHandler for Move {
+    type DataType = State;
+    type Error = Error;
+
+    fn id(&self) -> &Url {
+        &self.id
+    }
+
+    fn actor(&self) -> &Url {
+        &self.actor
+    }
+
+    #[tracing::instrument(skip(_data))]
+    async fn verify(&self, _data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        verify_domains_match(&self.id, &self.actor)
+            .context_bad_request("failed to verify domain")?;
+        if self.object != self.actor {
+            return Err(format_err!(BAD_REQUEST, "actor can only move itself"));
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(data))]
+    async fn receive(self, data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        let from_uri: ObjectId<user::Model> = self.object.clone().into();
+        let from_user = from_uri.dereference(data).await?;
+
+        // Always refetch the target, as it has to have been updated to list the alias just now
+        let target_uri: ObjectId<user::Model> = self.target.clone().into();
+        let target_user = target_uri.dereference_forced(data).await?;
+        if !target_user
+            .also_known_as
+            .iter()
+            .any(|alias| alias == &from_user.uri)
+        {
+            return Err(format_err!(
+                BAD_REQUEST,
+                "target does not list actor as an alias"
+            ));
+        }
+
+        let from_user_activemodel = user::ActiveModel {
+            id: ActiveValue::Unchanged(from_user.id),
+            moved_to: ActiveValue::Set(Some(target_user.uri.clone())),
+            ..Default::default()
+        };
+        from_user_activemodel
+            .update(&*data.db)
+            .await
+            .context_internal_server_error("failed to update database")?;
+
+        // Only follows are carried over to the target
+        let tx = data
+            .db
+            .begin()
+            .await
+            .context_internal_server_error("failed to begin database transaction")?;
+
+        let Some(existing) = follow::Entity::find_by_id(from_user.id)
+            .one(&tx)
+            .await
+            .context_internal_server_error("failed to query database")?
+        else {
+            return Ok(());
+        };
+        let undo = Undo::<Follow>::new(existing.clone().into_json(data).await?)?;
+        ModelTrait::delete(existing, &tx)
+            .await
+            .context_internal_server_error("failed to delete from database")?;
+
+        let target_existing_count = follow::Entity::find_by_id(target_user.id)
+            .count(&tx)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        let follow = if target_existing_count == 0 {
+            let follow_activemodel = follow::ActiveModel {
+                to_id: ActiveValue::Set(target_user.id),
+                accepted: ActiveValue::Set(false),
+            };
+            let follow = follow_activemodel
+                .insert(&tx)
+                .await
+                .context_internal_server_error("failed to insert to database")?;
+            Some(follow)
+        } else {
+            None
+        };
+
+        tx.commit()
+            .await
+            .context_internal_server_error("failed to commit database transaction")?;
+
+        let inbox = Url::parse(&from_user.inbox)
+            .context_internal_server_error("malformed user inbox URL")?;
+        undo.send(data, vec![inbox]).await?;
+        if let Some(follow) = follow {
+            let follow = follow.into_json(data).await?;
+            follow.send(data).await?;
+        }
+
+        let event = Event::Notification(Notification::new(NotificationType::Moved {
+            user_id: from_user.id.into(),
+            target_user_id: target_user.id.into(),
+        }));
+        event.send(&*data.db).await?;
+
+        Ok(())
+    }
+}
@@ -2,10 +2,11 @@ use activitypub_federation::{
     config::Data,
     fetch::object_id::ObjectId,
     kinds::{
-        activity::CreateType,
-        object::{DocumentType, NoteType},
+        activity::{CreateType, UpdateType},
+        collection::CollectionType,
+        object::DocumentType,
     },
-    protocol::verification::verify_domains_match,
+    protocol::{context::WithContext, verification::verify_domains_match},
     traits::{ActivityHandler, Object},
 };
 use async_trait::async_trait;
@@ -17,8 +18,10 @@ use serde::{Deserialize, Serialize};
 use url::Url;
 
 use crate::{
-    entity::{mention, post},
+    delivery::queue_activity,
+    entity::{mention, poll, post, user},
     error::{Context, Error},
+    format_err,
     queue::{Event, Notification, NotificationType, Update},
     state::State,
 };
@@ -39,6 +42,48 @@ pub struct Attachment {
     pub name: Option<String>,
 }
 
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Deserialize, Serialize)]
+pub enum NoteType {
+    #[default]
+    Note,
+    Question,
+}
+
+impl std::fmt::Display for NoteType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Note => write!(f, "Note"),
+            Self::Question => write!(f, "Question"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuestionOptionReplies {
+    #[serde(rename = "type")]
+    pub ty: CollectionType,
+    pub total_items: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuestionOption {
+    #[serde(rename = "type")]
+    pub ty: NoteType,
+    pub name: String,
+    #[serde(default)]
+    pub replies: Option<QuestionOptionReplies>,
+}
+
+/// `closed` of a `Question`, which is either the time it was closed or a flag
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum QuestionClosed {
+    At(DateTime<FixedOffset>),
+    Flag(bool),
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Source {
@@ -60,6 +105,8 @@ pub struct Note {
     #[serde(default)]
     pub quote_url: Option<ObjectId<post::Model>>,
     pub published: DateTime<FixedOffset>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated: Option<DateTime<FixedOffset>>,
     #[derivative(Debug(format_with = "crate::fmt::debug_format_vec_display"))]
     #[serde(default)]
     pub to: Vec<Url>,
@@ -68,6 +115,10 @@ pub struct Note {
     pub cc: Vec<Url>,
     #[serde(default)]
     pub summary: Option<String>,
+    /// Chosen option when this note is a vote on a `Question`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default)]
     pub content: String,
     #[serde(default)]
     pub source: Option<Source>,
@@ -79,6 +130,16 @@ pub struct Note {
     pub sensitive: bool,
     #[serde(default)]
     pub tag: Vec<Tag>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub one_of: Option<Vec<QuestionOption>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub any_of: Option<Vec<QuestionOption>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_time: Option<DateTime<FixedOffset>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub closed: Option<QuestionClosed>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub voters_count: Option<u64>,
 }
 
 #[derive(Derivative, Deserialize, Serialize)]
@@ -128,25 +189,61 @@ impl ActivityHandler for CreateNote {
 
     #[tracing::instrument(skip(_data))]
     async fn verify(&self, _data: &Data<Self::DataType>) -> Result<(), Self::Error> {
-        verify_domains_match(&self.id, &self.actor).context_bad_request("failed to verify domain")
+        verify_domains_match(&self.id, &self.actor)
+            .context_bad_request("failed to verify domain")?;
+        verify_domains_match(self.object.id.inner(), &self.actor)
+            .context_bad_request("failed to verify domain")?;
+        verify_domains_match(&self.object.attributed_to, &self.actor)
+            .context_bad_request("failed to verify domain")
     }
 
     #[tracing::instrument(skip(data))]
     async fn receive(self, data: &Data<Self::DataType>) -> Result<(), Self::Error> {
-        let post = post::Model::from_json(NoteOrAnnounce::Note(self.object), data).await?;
+        // Votes on our polls are notes with `name` replying to them, and are not stored as posts
+        if let (Some(name), Some(in_reply_to)) = (&self.object.name, &self.object.in_reply_to) {
+            let poll = poll::Entity::find()
+                .inner_join(post::Entity)
+                .filter(post::Column::Uri.eq(in_reply_to.inner().as_str()))
+                .filter(post::Column::UserId.is_null())
+                .one(&*data.db)
+                .await
+                .context_internal_server_error("failed to query database")?;
+            if let Some(poll) = poll {
+                let voter_uri: ObjectId<user::Model> = self.actor.into();
+                let voter = voter_uri.dereference(data).await?;
+                return poll.receive_vote(&voter, name, data).await;
+            }
+        }
+
+        let post =
+            post::Model::from_json(NoteOrAnnounce::Note(Box::new(self.object)), data).await?;
 
         let event = Event::Update(Update::CreatePost {
             post_id: post.id.into(),
         });
         event.send(&*data.db).await?;
 
+        let local_person_replied_count = if let Some(reply_id) = post.reply_id {
+            post::Entity::find_by_id(reply_id)
+                .filter(post::Column::UserId.is_null())
+                .count(&*data.db)
+                .await
+                .context_internal_server_error("failed to query database")?
+        } else {
+            0
+        };
         let local_person_mentioned_count = post
             .find_related(mention::Entity)
             .filter(mention::Column::UserUri.eq(LocalPerson::id().as_str()))
             .count(&*data.db)
             .await
             .context_internal_server_error("failed to query database")?;
-        if local_person_mentioned_count > 0 {
+        if local_person_replied_count > 0 {
+            let event = Event::Notification(Notification::new(NotificationType::Replied {
+                post_id: post.id.into(),
+            }));
+            event.send(&*data.db).await?;
+        } else if local_person_mentioned_count > 0 {
             let event = Event::Notification(Notification::new(NotificationType::Mentioned {
                 post_id: post.id.into(),
             }));
@@ -170,3 +267,95 @@ impl ActivityHandler for CreateNote {
         Ok(())
     }
 }
+
+#[derive(Derivative, Deserialize, Serialize)]
+#[derivative(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateNote {
+    #[serde(rename = "type")]
+    pub ty: UpdateType,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub id: Url,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub actor: Url,
+    #[derivative(Debug(format_with = "crate::fmt::debug_format_vec_display"))]
+    #[serde(default)]
+    pub to: Vec<Url>,
+    #[derivative(Debug(format_with = "crate::fmt::debug_format_vec_display"))]
+    #[serde(default)]
+    pub cc: Vec<Url>,
+    pub object: Note,
+}
+
+impl UpdateNote {
+    pub fn new(note: Note) -> Result<Self, Error> {
+        Ok(Self {
+            ty: Default::default(),
+            id: generate_object_id()?,
+            actor: note.attributed_to.clone(),
+            to: note.to.clone(),
+            cc: note.cc.clone(),
+            object: note,
+        })
+    }
+
+    #[tracing::instrument(skip(data))]
+    pub async fn send(self, data: &Data<State>, inboxes: Vec<Url>) -> Result<(), Error> {
+        let with_context = WithContext::new_default(self);
+        queue_activity(&with_context, inboxes, data).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ActivityHandler for UpdateNote {
+    type DataType = State;
+    type Error = Error;
+
+    fn id(&self) -> &Url {
+        &self.id
+    }
+
+    fn actor(&self) -> &Url {
+        &self.actor
+    }
+
+    #[tracing::instrument(skip(_data))]
+    async fn verify(&self, _data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        verify_domains_match(&self.id, &self.actor)
+            .context_bad_request("failed to verify domain")?;
+        verify_domains_match(self.object.id.inner(), &self.actor)
+            .context_bad_request("failed to verify domain")?;
+        // the existing post is checked to be by the same author in `post::Model::from_json`
+        if self.object.attributed_to != self.actor {
+            return Err(format_err!(
+                FORBIDDEN,
+                "actor is not the author of the note"
+            ));
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(data))]
+    async fn receive(self, data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        // Ignore updates of posts we have never seen
+        let existing_count = post::Entity::find()
+            .filter(post::Column::Uri.eq(self.object.id.inner().as_str()))
+            .count(&*data.db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        if existing_count == 0 {
+            return Ok(());
+        }
+
+        let post =
+            post::Model::from_json(NoteOrAnnounce::Note(Box::new(self.object)), data).await?;
+
+        let event = Event::Update(Update::UpdatePost {
+            post_id: post.id.into(),
+        });
+        event.send(&*data.db).await?;
+
+        Ok(())
+    }
+}
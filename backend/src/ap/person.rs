@@ -1,5 +1,4 @@
 use activitypub_federation::{
-    activity_queue::queue_activity,
     config::Data,
     fetch::object_id::ObjectId,
     kinds::{activity::UpdateType, object::ImageType, public},
@@ -15,6 +14,7 @@ use url::Url;
 
 use crate::{
     config::CONFIG,
+    delivery::queue_activity,
     entity::{local_file, setting, user},
     error::{Context, Error},
     format_err,
@@ -74,8 +74,26 @@ pub struct Person {
     #[derivative(Debug(format_with = "crate::fmt::debug_format_option_display"))]
     #[serde(default)]
     pub shared_inbox: Option<Url>,
+    #[derivative(Debug(format_with = "crate::fmt::debug_format_option_display"))]
+    #[serde(default)]
+    pub outbox: Option<Url>,
+    #[derivative(Debug(format_with = "crate::fmt::debug_format_option_display"))]
+    #[serde(default)]
+    pub followers: Option<Url>,
+    #[derivative(Debug(format_with = "crate::fmt::debug_format_option_display"))]
+    #[serde(default)]
+    pub following: Option<Url>,
+    #[derivative(Debug(format_with = "crate::fmt::debug_format_option_display"))]
+    #[serde(default)]
+    pub featured: Option<Url>,
     #[serde(default)]
     pub manually_approves_followers: bool,
+    #[derivative(Debug(format_with = "crate::fmt::debug_format_vec_display"))]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub also_known_as: Vec<Url>,
+    #[derivative(Debug(format_with = "crate::fmt::debug_format_option_display"))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub moved_to: Option<Url>,
     pub public_key: PublicKey,
 }
 
@@ -136,6 +154,21 @@ impl LocalPerson {
             .context_internal_server_error("failed to construct followers URL")
     }
 
+    pub fn following() -> Result<Url, Error> {
+        Url::parse(&format!("{}/following", Self::id()))
+            .context_internal_server_error("failed to construct following URL")
+    }
+
+    pub fn featured() -> Result<Url, Error> {
+        Url::parse(&format!("{}/featured", Self::id()))
+            .context_internal_server_error("failed to construct featured URL")
+    }
+
+    pub fn outbox() -> Result<Url, Error> {
+        Url::parse(&format!("{}/outbox", Self::id()))
+            .context_internal_server_error("failed to construct outbox URL")
+    }
+
     pub fn id() -> Url {
         static ID: Lazy<Url> = Lazy::new(|| {
             Url::parse(&format!("https://{}/person", CONFIG.public_domain))
@@ -196,12 +229,27 @@ impl Object for LocalPerson {
                 }),
             inbox: self.inbox(),
             shared_inbox: Some(self.inbox()),
+            outbox: Some(Self::outbox()?),
+            followers: Some(Self::followers()?),
+            following: Some(Self::following()?),
+            featured: Some(Self::featured()?),
             public_key: PublicKey {
                 id: format!("{}#main-key", id),
                 owner: id,
                 public_key_pem: self.public_key_pem().to_string(),
             },
-            manually_approves_followers: false,
+            manually_approves_followers: self.0.user_manually_approves_followers,
+            also_known_as: self
+                .0
+                .user_also_known_as
+                .iter()
+                .filter_map(|uri| Url::parse(uri).ok())
+                .collect(),
+            moved_to: self
+                .0
+                .user_moved_to
+                .as_deref()
+                .and_then(|uri| Url::parse(uri).ok()),
             name: self.0.user_name,
             summary: self.0.user_description,
         })
@@ -274,10 +322,9 @@ impl PersonUpdate {
     }
 
     pub async fn send(self, data: &Data<State>) -> Result<(), Error> {
-        let me = LocalPerson::get(&*data.db).await?;
         let inboxes = get_follower_inboxes(&*data.db).await?;
         let with_context = WithContext::new_default(self);
-        queue_activity(&with_context, &me, inboxes, data).await?;
+        queue_activity(&with_context, inboxes, data).await?;
         Ok(())
     }
 }
@@ -15,8 +15,9 @@ pub struct Mention {
     pub name: String,
 }
 
-#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Deserialize, Serialize)]
 pub enum HashtagType {
+    #[default]
     Hashtag,
 }
 
@@ -26,12 +27,6 @@ impl std::fmt::Display for HashtagType {
     }
 }
 
-impl Default for HashtagType {
-    fn default() -> Self {
-        Self::Hashtag
-    }
-}
-
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Hashtag {
@@ -40,8 +35,9 @@ pub struct Hashtag {
     pub name: String,
 }
 
-#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Deserialize, Serialize)]
 pub enum EmojiType {
+    #[default]
     Emoji,
 }
 
@@ -51,12 +47,6 @@ impl std::fmt::Display for EmojiType {
     }
 }
 
-impl Default for EmojiType {
-    fn default() -> Self {
-        Self::Emoji
-    }
-}
-
 #[derive(Clone, Derivative, Deserialize, Serialize)]
 #[derivative(Debug)]
 #[serde(rename_all = "camelCase")]
@@ -1,5 +1,4 @@
 use activitypub_federation::{
-    activity_queue::queue_activity,
     config::Data,
     kinds::activity::UndoType,
     protocol::{context::WithContext, verification::verify_domains_match},
@@ -14,14 +13,15 @@ use serde::{Deserialize, Serialize};
 use url::Url;
 
 use crate::{
-    entity::{follower, user},
+    delivery::queue_activity,
+    entity::{follow_request, follower, user},
     error::{Context, Error},
     format_err,
     queue::{Event, Notification, NotificationType},
     state::State,
 };
 
-use super::{follow::Follow, generate_object_id, like::Like, person::LocalPerson};
+use super::{block::Block, follow::Follow, generate_object_id, like::Like, person::LocalPerson};
 
 #[derive(Derivative, Deserialize, Serialize)]
 #[derivative(Debug)]
@@ -54,9 +54,34 @@ where
 {
     #[tracing::instrument(skip(data))]
     pub async fn send(self, data: &Data<State>, inboxes: Vec<Url>) -> Result<(), Error> {
-        let me = LocalPerson::get(&*data.db).await?;
         let with_context = WithContext::new_default(self);
-        queue_activity(&with_context, &me, inboxes, data).await?;
+        queue_activity(&with_context, inboxes, data).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ActivityHandler for Undo<Block> {
+    type DataType = State;
+    type Error = Error;
+
+    fn id(&self) -> &Url {
+        &self.id
+    }
+
+    fn actor(&self) -> &Url {
+        &self.actor
+    }
+
+    #[tracing::instrument(skip(_data))]
+    async fn verify(&self, _data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        verify_domains_match(self.object.id(), &self.id)
+            .context_bad_request("failed to verify domain")
+    }
+
+    #[tracing::instrument(skip(_data))]
+    async fn receive(self, _data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        // Relationships dropped by the block are not restored
         Ok(())
     }
 }
@@ -98,6 +123,17 @@ impl ActivityHandler for Undo<Follow> {
             .context_internal_server_error("failed to query database")?
             .context_not_found("follower not found")?;
 
+        let follow_request_deleted = follow_request::Entity::delete_by_id(follower_id)
+            .exec(&tx)
+            .await
+            .context_internal_server_error("failed to delete from database")?;
+        if follow_request_deleted.rows_affected > 0 {
+            tx.commit()
+                .await
+                .context_internal_server_error("failed to commit database transaction")?;
+            return Ok(());
+        }
+
         let existing_count = follower::Entity::find_by_id(follower_id)
             .count(&tx)
             .await
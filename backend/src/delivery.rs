@@ -0,0 +1,384 @@
+use std::{fmt::Debug, time::Duration};
+
+use activitypub_federation::{
+    activity_sending::SendActivityTask,
+    config::{Data, FederationConfig},
+    traits::ActivityHandler,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset, Utc};
+use futures_util::{stream::FuturesUnordered, StreamExt};
+use sea_orm::{
+    sea_query::OnConflict, ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, DbBackend,
+    EntityTrait, QueryFilter, QueryOrder, QuerySelect, Statement,
+};
+use serde::Serialize;
+use sqlx_postgres::PgListener;
+use ulid::Ulid;
+use url::Url;
+
+use crate::{
+    ap::person::LocalPerson,
+    config::CONFIG,
+    entity::{delivery, delivery_inbox, domain_block},
+    error::{Context, Error},
+    format_err,
+    state::State,
+};
+
+const DELIVERY_CHANNEL_NAME: &str = "delivery";
+const DELIVERY_BATCH_SIZE: u64 = 32;
+const DELIVERY_POLL_INTERVAL: Duration = Duration::from_secs(30);
+const DELIVERY_BACKOFF_BASE_SECS: i64 = 60;
+const LISTEN_RETRY_BASE: Duration = Duration::from_secs(1);
+
+/// Stores an activity to be delivered to each of `inboxes` by the delivery worker.
+/// Local, suspended and blocked inboxes are skipped.
+#[tracing::instrument(skip(data))]
+pub async fn queue_activity<Activity>(
+    activity: &Activity,
+    inboxes: Vec<Url>,
+    data: &Data<State>,
+) -> Result<(), Error>
+where
+    Activity: ActivityHandler + Serialize + Debug,
+{
+    let mut inboxes = inboxes
+        .into_iter()
+        .filter(|inbox| inbox.domain() != Some(CONFIG.public_domain.as_str()))
+        .collect::<Vec<_>>();
+    inboxes.sort();
+    inboxes.dedup();
+    domain_block::Model::retain_unblocked(&mut inboxes, &*data.db).await?;
+    if inboxes.is_empty() {
+        return Ok(());
+    }
+
+    let suspended_inboxes = delivery_inbox::Entity::find()
+        .filter(delivery_inbox::Column::Inbox.is_in(inboxes.iter().map(Url::as_str)))
+        .filter(delivery_inbox::Column::SuspendedAt.is_not_null())
+        .select_only()
+        .column(delivery_inbox::Column::Inbox)
+        .into_tuple::<String>()
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+
+    let activity_json = serde_json::to_value(activity)
+        .context_internal_server_error("failed to serialize activity")?;
+    let now = Utc::now().fixed_offset();
+    let deliveries = inboxes
+        .into_iter()
+        .filter(|inbox| {
+            !suspended_inboxes
+                .iter()
+                .any(|suspended| suspended == inbox.as_str())
+        })
+        .map(|inbox| delivery::ActiveModel {
+            id: ActiveValue::Set(Ulid::new().into()),
+            activity_id: ActiveValue::Set(activity.id().to_string()),
+            activity: ActiveValue::Set(activity_json.clone()),
+            inbox: ActiveValue::Set(inbox.to_string()),
+            attempts: ActiveValue::Set(0),
+            created_at: ActiveValue::Set(now),
+            next_attempt_at: ActiveValue::Set(now),
+            failed_at: ActiveValue::Set(None),
+            last_error: ActiveValue::Set(None),
+        })
+        .collect::<Vec<_>>();
+    if deliveries.is_empty() {
+        return Ok(());
+    }
+
+    delivery::Entity::insert_many(deliveries)
+        .exec(&*data.db)
+        .await
+        .context_internal_server_error("failed to insert to database")?;
+
+    notify_worker(&*data.db).await
+}
+
+/// Wakes up the delivery worker so that due deliveries are sent immediately.
+pub async fn notify_worker(db: &impl ConnectionTrait) -> Result<(), Error> {
+    let statement = Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "SELECT pg_notify($1, '')",
+        [DELIVERY_CHANNEL_NAME.into()],
+    );
+    db.execute(statement)
+        .await
+        .context_internal_server_error("failed to notify to Postgres channel")?;
+    Ok(())
+}
+
+/// Sends stored deliveries until `State::stopper` is stopped.
+pub async fn run_worker(federation_config: FederationConfig<State>) -> Result<(), Error> {
+    let data = federation_config.to_request_data();
+
+    let mut retry_delay = LISTEN_RETRY_BASE;
+    let mut pg_listener = loop {
+        match listen(&data).await {
+            Ok(pg_listener) => break pg_listener,
+            Err(error) => {
+                tracing::error!(
+                    "failed to listen Postgres channel, retrying in {:?}\n{:?}",
+                    retry_delay,
+                    error.inner
+                );
+                data.stopper
+                    .stop_future(tokio::time::sleep(retry_delay))
+                    .await;
+                if data.stopper.is_stopped() {
+                    return Ok(());
+                }
+                retry_delay = (retry_delay * 2).min(DELIVERY_POLL_INTERVAL);
+            }
+        }
+    };
+
+    tracing::info!("starting delivery worker...");
+    while !data.stopper.is_stopped() {
+        if let Err(error) = deliver_due(&data).await {
+            tracing::error!("failed to deliver activities\n{:?}", error.inner);
+        }
+
+        let wait = async {
+            tokio::select! {
+                res = pg_listener.recv() => {
+                    if let Err(error) = res {
+                        tracing::error!("failed to listen from Postgres channel\n{:?}", error);
+                        tokio::time::sleep(DELIVERY_POLL_INTERVAL).await;
+                    }
+                },
+                _ = tokio::time::sleep(DELIVERY_POLL_INTERVAL) => {},
+            }
+        };
+        data.stopper.stop_future(wait).await;
+    }
+    tracing::info!("delivery worker stopped");
+
+    Ok(())
+}
+
+async fn listen(data: &Data<State>) -> Result<PgListener, Error> {
+    let mut pg_listener = data.pg_listener().await?;
+    pg_listener
+        .listen(DELIVERY_CHANNEL_NAME)
+        .await
+        .context_internal_server_error("failed to listen Postgres channel")?;
+    Ok(pg_listener)
+}
+
+#[tracing::instrument(skip(data))]
+async fn deliver_due(data: &Data<State>) -> Result<(), Error> {
+    loop {
+        let deliveries = delivery::Entity::find()
+            .filter(delivery::Column::FailedAt.is_null())
+            .filter(delivery::Column::NextAttemptAt.lte(Utc::now()))
+            .order_by_asc(delivery::Column::NextAttemptAt)
+            .limit(DELIVERY_BATCH_SIZE)
+            .all(&*data.db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        if deliveries.is_empty() {
+            return Ok(());
+        }
+
+        let me = LocalPerson::get(&*data.db).await?;
+        let mut results = deliveries
+            .into_iter()
+            .map(|delivery| async {
+                let res = send(&delivery, &me, data).await;
+                (delivery, res)
+            })
+            .collect::<FuturesUnordered<_>>();
+        while let Some((delivery, res)) = results.next().await {
+            if let Err(error) = handle_result(delivery, res, data).await {
+                tracing::error!("failed to record delivery result\n{:?}", error.inner);
+            }
+        }
+
+        if data.stopper.is_stopped() {
+            return Ok(());
+        }
+    }
+}
+
+async fn send(
+    delivery: &delivery::Model,
+    me: &LocalPerson,
+    data: &Data<State>,
+) -> Result<(), Error> {
+    let activity = StoredActivity {
+        id: delivery
+            .activity_id
+            .parse()
+            .context_internal_server_error("malformed activity ID")?,
+        actor: LocalPerson::id(),
+        activity: delivery.activity.clone(),
+    };
+    let inbox = delivery
+        .inbox
+        .parse()
+        .context_internal_server_error("malformed inbox URL")?;
+    let tasks = SendActivityTask::prepare(&activity, me, vec![inbox], data).await?;
+    for task in tasks {
+        task.sign_and_send(data).await?;
+    }
+    Ok(())
+}
+
+async fn handle_result(
+    delivery: delivery::Model,
+    res: Result<(), Error>,
+    data: &Data<State>,
+) -> Result<(), Error> {
+    let now = Utc::now().fixed_offset();
+    match res {
+        Ok(()) => {
+            delivery::Entity::delete_by_id(delivery.id)
+                .exec(&*data.db)
+                .await
+                .context_internal_server_error("failed to delete from database")?;
+            delivery_inbox::Entity::delete_by_id(delivery.inbox)
+                .exec(&*data.db)
+                .await
+                .context_internal_server_error("failed to delete from database")?;
+        }
+        Err(error) => {
+            tracing::warn!(
+                "failed to deliver {} to {}\n{:?}",
+                delivery.activity_id,
+                delivery.inbox,
+                error.inner
+            );
+
+            let attempts = delivery.attempts + 1;
+            let gave_up = attempts as u32 >= CONFIG.delivery_max_attempts;
+            let delivery_activemodel = delivery::ActiveModel {
+                id: ActiveValue::Unchanged(delivery.id),
+                attempts: ActiveValue::Set(attempts),
+                next_attempt_at: ActiveValue::Set(now + backoff(attempts)),
+                failed_at: ActiveValue::Set(gave_up.then_some(now)),
+                last_error: ActiveValue::Set(Some(format!("{:#}", error.inner))),
+                ..Default::default()
+            };
+            delivery_activemodel
+                .update(&*data.db)
+                .await
+                .context_internal_server_error("failed to update database")?;
+
+            record_inbox_failure(&delivery.inbox, now, data).await?;
+        }
+    }
+    Ok(())
+}
+
+fn backoff(attempts: i32) -> chrono::Duration {
+    let exponent = attempts.clamp(0, 16) as u32;
+    chrono::Duration::seconds(DELIVERY_BACKOFF_BASE_SECS * 2i64.pow(exponent))
+}
+
+/// Suspends an inbox which has been failing for longer than `CONFIG.delivery_suspend_after_days`,
+/// marking all of its pending deliveries as failed.
+async fn record_inbox_failure(
+    inbox: &str,
+    now: DateTime<FixedOffset>,
+    data: &Data<State>,
+) -> Result<(), Error> {
+    let delivery_inbox_activemodel = delivery_inbox::ActiveModel {
+        inbox: ActiveValue::Set(inbox.to_string()),
+        first_failed_at: ActiveValue::Set(now),
+        suspended_at: ActiveValue::Set(None),
+    };
+    delivery_inbox::Entity::insert(delivery_inbox_activemodel)
+        .on_conflict(
+            OnConflict::column(delivery_inbox::Column::Inbox)
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(&*data.db)
+        .await
+        .context_internal_server_error("failed to insert to database")?;
+
+    let delivery_inbox = delivery_inbox::Entity::find_by_id(inbox)
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_internal_server_error("inbox not found")?;
+    let suspend_after = chrono::Duration::days(CONFIG.delivery_suspend_after_days as i64);
+    if delivery_inbox.suspended_at.is_some() || now - delivery_inbox.first_failed_at < suspend_after
+    {
+        return Ok(());
+    }
+
+    tracing::warn!("suspending deliveries to {}", inbox);
+    let delivery_inbox_activemodel = delivery_inbox::ActiveModel {
+        inbox: ActiveValue::Unchanged(delivery_inbox.inbox),
+        suspended_at: ActiveValue::Set(Some(now)),
+        ..Default::default()
+    };
+    delivery_inbox_activemodel
+        .update(&*data.db)
+        .await
+        .context_internal_server_error("failed to update database")?;
+
+    delivery::Entity::update_many()
+        .filter(delivery::Column::Inbox.eq(inbox))
+        .filter(delivery::Column::FailedAt.is_null())
+        .col_expr(delivery::Column::FailedAt, now.into())
+        .col_expr(
+            delivery::Column::LastError,
+            Some("inbox suspended".to_string()).into(),
+        )
+        .exec(&*data.db)
+        .await
+        .context_internal_server_error("failed to update database")?;
+
+    Ok(())
+}
+
+/// Already serialized activity loaded from the delivery table.
+#[derive(Debug)]
+struct StoredActivity {
+    id: Url,
+    actor: Url,
+    activity: serde_json::Value,
+}
+
+impl Serialize for StoredActivity {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.activity.serialize(serializer)
+    }
+}
+
+#[async_trait]
+impl ActivityHandler for StoredActivity {
+    type DataType = State;
+    type Error = Error;
+
+    fn id(&self) -> &Url {
+        &self.id
+    }
+
+    fn actor(&self) -> &Url {
+        &self.actor
+    }
+
+    async fn verify(&self, _data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        Err(format_err!(
+            INTERNAL_SERVER_ERROR,
+            "stored activity cannot be received"
+        ))
+    }
+
+    async fn receive(self, _data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        Err(format_err!(
+            INTERNAL_SERVER_ERROR,
+            "stored activity cannot be received"
+        ))
+    }
+}
@@ -4,7 +4,8 @@ use chrono::{DateTime, FixedOffset};
 use derivative::Derivative;
 use mime::Mime;
 use sea_orm::{
-    ColumnTrait, ConnectionTrait, EntityTrait, ModelTrait, QueryFilter, QueryOrder, QuerySelect,
+    ColumnTrait, ConnectionTrait, EntityTrait, ModelTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect,
 };
 use serde::{Deserialize, Serialize};
 use ulid::Ulid;
@@ -13,12 +14,16 @@ use utoipa::{IntoParams, ToSchema};
 
 use crate::{
     entity::{
-        emoji, follow, hashtag, local_file, mention, post, post_emoji, reaction, remote_file,
-        report, sea_orm_active_enums, setting, user,
+        access_key, bookmark, delivery, domain_block, draft, emoji, follow, follow_request,
+        hashtag, import, local_file, login_lockout, mention, mute, passkey, pin, poll, poll_option,
+        poll_vote, post, post_edit, post_emoji, reaction, remote_file, report, scheduled_post,
+        sea_orm_active_enums, setting, user,
     },
     error::{Context, Result},
 };
 
+pub mod mastodon;
+
 fn default_size() -> u64 {
     10
 }
@@ -75,6 +80,9 @@ pub struct User {
     pub banner_url: Option<Url>,
     pub manually_approves_followers: bool,
     pub is_bot: bool,
+    #[derivative(Debug(format_with = "crate::fmt::debug_format_option_display"))]
+    #[schema(value_type = Option<String>, format = "url")]
+    pub moved_to: Option<Url>,
 }
 
 impl User {
@@ -93,6 +101,7 @@ impl User {
             banner_url: user.banner_url.and_then(|url| url.parse().ok()),
             manually_approves_followers: user.manually_approves_followers,
             is_bot: user.is_bot,
+            moved_to: user.moved_to.and_then(|url| url.parse().ok()),
         })
     }
 }
@@ -244,6 +253,7 @@ pub struct Post {
     pub title: Option<String>,
     pub source_content: Option<String>,
     pub source_media_type: Option<String>,
+    pub updated_at: Option<DateTime<FixedOffset>>,
     pub user: Option<User>,
     pub visibility: Visibility,
     pub is_sensitive: bool,
@@ -255,6 +265,9 @@ pub struct Post {
     pub mentions: Vec<Mention>,
     pub emojis: Vec<Emoji>,
     pub hashtags: Vec<String>,
+    pub poll: Option<Poll>,
+    pub is_pinned: bool,
+    pub is_bookmarked: bool,
 }
 
 impl Post {
@@ -367,6 +380,28 @@ impl Post {
             .await
             .context_internal_server_error("failed to query database")?;
 
+        let poll = post
+            .find_related(poll::Entity)
+            .one(db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        let poll = if let Some(poll) = poll {
+            Some(Poll::from_model(poll, db).await?)
+        } else {
+            None
+        };
+
+        let pin_count = post
+            .find_related(pin::Entity)
+            .count(db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        let bookmark_count = post
+            .find_related(bookmark::Entity)
+            .count(db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+
         Ok(Self {
             id: post.id.into(),
             created_at: post.created_at,
@@ -377,6 +412,7 @@ impl Post {
             title: post.title,
             source_content: post.source_content,
             source_media_type: post.source_media_type,
+            updated_at: post.updated_at,
             user,
             visibility: match post.visibility {
                 sea_orm_active_enums::Visibility::Public => Visibility::Public,
@@ -394,12 +430,145 @@ impl Post {
             mentions,
             emojis,
             hashtags,
+            poll,
+            is_pinned: pin_count > 0,
+            is_bookmarked: bookmark_count > 0,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Bookmark {
+    pub created_at: DateTime<FixedOffset>,
+    pub post: Post,
+}
+
+impl Bookmark {
+    pub async fn from_model(
+        bookmark: bookmark::Model,
+        post: post::Model,
+        db: &impl ConnectionTrait,
+    ) -> Result<Self> {
+        Ok(Self {
+            created_at: bookmark.created_at,
+            post: Post::from_model(post, db).await?,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PollOption {
+    pub name: String,
+    pub votes_count: u32,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Poll {
+    pub ends_at: Option<DateTime<FixedOffset>>,
+    pub is_multiple: bool,
+    pub is_ended: bool,
+    pub voters_count: Option<u32>,
+    pub options: Vec<PollOption>,
+    /// Indices of options we have voted for
+    pub voted: Vec<u32>,
+}
+
+impl Poll {
+    pub async fn from_model(poll: poll::Model, db: &impl ConnectionTrait) -> Result<Self> {
+        let options = poll
+            .find_related(poll_option::Entity)
+            .order_by_asc(poll_option::Column::Order)
+            .all(db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        let options = options
+            .into_iter()
+            .map(|option| PollOption {
+                name: option.name,
+                votes_count: option.votes_count as u32,
+            })
+            .collect::<Vec<_>>();
+
+        let voted = poll
+            .find_related(poll_vote::Entity)
+            .filter(poll_vote::Column::UserId.is_null())
+            .select_only()
+            .column(poll_vote::Column::Order)
+            .order_by_asc(poll_vote::Column::Order)
+            .into_tuple::<i16>()
+            .all(db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        let voted = voted.into_iter().map(|order| order as u32).collect();
+
+        Ok(Self {
+            is_ended: poll.has_ended(),
+            ends_at: poll.ends_at,
+            is_multiple: poll.is_multiple,
+            voters_count: poll.voters_count.map(|count| count as u32),
+            options,
+            voted,
         })
     }
 }
 
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatePoll {
+    pub options: Vec<String>,
+    #[serde(default)]
+    pub ends_at: Option<DateTime<FixedOffset>>,
+    #[serde(default)]
+    pub is_multiple: bool,
+}
+
 #[derive(Debug, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
+pub struct CreateVote {
+    /// Indices of chosen options
+    pub choices: Vec<u32>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum SearchType {
+    Post,
+    User,
+    Hashtag,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+    pub posts: Vec<Post>,
+    pub users: Vec<User>,
+    /// Matching hashtag names, most used first
+    pub hashtags: Vec<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PostContext {
+    /// From the thread root down to the direct parent
+    pub ancestors: Vec<Post>,
+    /// Replies in depth-first order
+    pub descendants: Vec<Post>,
+}
+
+/// Source format of post text to be rendered by the server
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum PostFormat {
+    Plain,
+    Markdown,
+    Mfm,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct CreatePost {
     #[schema(value_type = Option<String>, format = "ulid")]
     #[serde(default)]
@@ -407,7 +576,11 @@ pub struct CreatePost {
     #[schema(value_type = Option<String>, format = "ulid")]
     #[serde(default)]
     pub repost_id: Option<Ulid>,
+    /// HTML unless `format` is given, in which case it is parsed and rendered by the server
     pub text: String,
+    /// Source format of `text`. Mentions, hashtags and emojis found in it are added to the given ones
+    #[serde(default)]
+    pub format: Option<PostFormat>,
     #[serde(default)]
     pub title: Option<String>,
     pub visibility: Visibility,
@@ -422,6 +595,163 @@ pub struct CreatePost {
     pub emojis: Vec<String>,
     #[serde(default)]
     pub hashtags: Vec<String>,
+    #[serde(default)]
+    pub poll: Option<CreatePoll>,
+    /// Time to publish the post at instead of now. The returned ID is of the scheduled post
+    #[serde(default)]
+    pub scheduled_at: Option<DateTime<FixedOffset>>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Draft {
+    #[schema(value_type = String, format = "ulid")]
+    pub id: Ulid,
+    pub created_at: DateTime<FixedOffset>,
+    pub updated_at: DateTime<FixedOffset>,
+    pub post: CreatePost,
+}
+
+impl Draft {
+    pub fn from_model(draft: draft::Model) -> Result<Self> {
+        Ok(Self {
+            id: draft.id.into(),
+            created_at: draft.created_at,
+            updated_at: draft.updated_at,
+            post: serde_json::from_value(draft.request)
+                .context_internal_server_error("malformed draft")?,
+        })
+    }
+}
+
+/// Kind of Mastodon CSV export, named after its file
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportType {
+    Following,
+    Blocks,
+    Mutes,
+    Bookmarks,
+}
+
+impl From<ImportType> for sea_orm_active_enums::ImportType {
+    fn from(value: ImportType) -> Self {
+        match value {
+            ImportType::Following => Self::Following,
+            ImportType::Blocks => Self::Blocks,
+            ImportType::Mutes => Self::Mutes,
+            ImportType::Bookmarks => Self::Bookmarks,
+        }
+    }
+}
+
+impl From<sea_orm_active_enums::ImportType> for ImportType {
+    fn from(value: sea_orm_active_enums::ImportType) -> Self {
+        match value {
+            sea_orm_active_enums::ImportType::Following => Self::Following,
+            sea_orm_active_enums::ImportType::Blocks => Self::Blocks,
+            sea_orm_active_enums::ImportType::Mutes => Self::Mutes,
+            sea_orm_active_enums::ImportType::Bookmarks => Self::Bookmarks,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Import {
+    #[schema(value_type = String, format = "ulid")]
+    pub id: Ulid,
+    #[serde(rename = "type")]
+    pub ty: ImportType,
+    pub total_count: u32,
+    pub processed_count: u32,
+    pub failed_count: u32,
+    pub created_at: DateTime<FixedOffset>,
+    pub finished_at: Option<DateTime<FixedOffset>>,
+}
+
+impl Import {
+    pub fn from_model(import: import::Model) -> Self {
+        Self {
+            id: import.id.into(),
+            ty: import.r#type.into(),
+            total_count: import.entries.len() as u32,
+            processed_count: import.processed_count as u32,
+            failed_count: import.failed_count as u32,
+            created_at: import.created_at,
+            finished_at: import.finished_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledPost {
+    #[schema(value_type = String, format = "ulid")]
+    pub id: Ulid,
+    pub created_at: DateTime<FixedOffset>,
+    pub scheduled_at: DateTime<FixedOffset>,
+    pub post: CreatePost,
+    /// Set if publishing has failed, in which case the post is not retried until edited
+    pub failed_at: Option<DateTime<FixedOffset>>,
+    pub last_error: Option<String>,
+}
+
+impl ScheduledPost {
+    pub fn from_model(scheduled_post: scheduled_post::Model) -> Result<Self> {
+        Ok(Self {
+            id: scheduled_post.id.into(),
+            created_at: scheduled_post.created_at,
+            scheduled_at: scheduled_post.scheduled_at,
+            post: serde_json::from_value(scheduled_post.request)
+                .context_internal_server_error("malformed scheduled post")?,
+            failed_at: scheduled_post.failed_at,
+            last_error: scheduled_post.last_error,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdatePost {
+    /// HTML unless `format` is given, in which case it is parsed and rendered by the server
+    pub text: String,
+    /// Source format of `text`. Mentions, hashtags and emojis found in it are added to the given ones
+    #[serde(default)]
+    pub format: Option<PostFormat>,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub is_sensitive: bool,
+    #[schema(value_type = Vec<String>, format = "ulid")]
+    #[serde(default)]
+    pub files: Vec<Ulid>,
+    #[serde(default)]
+    pub mentions: Vec<Mention>,
+    #[serde(default)]
+    pub emojis: Vec<String>,
+    #[serde(default)]
+    pub hashtags: Vec<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PostEdit {
+    pub created_at: DateTime<FixedOffset>,
+    pub text: String,
+    pub title: Option<String>,
+    pub is_sensitive: bool,
+}
+
+impl PostEdit {
+    pub fn from_model(post_edit: post_edit::Model) -> Self {
+        Self {
+            created_at: post_edit.created_at,
+            text: post_edit.text,
+            title: post_edit.title,
+            is_sensitive: post_edit.is_sensitive,
+        }
+    }
 }
 
 #[derive(Derivative, Serialize, ToSchema)]
@@ -526,6 +856,95 @@ impl Follow {
     }
 }
 
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FollowRequest {
+    #[serde(flatten)]
+    pub user: User,
+    pub created_at: DateTime<FixedOffset>,
+}
+
+impl FollowRequest {
+    pub fn from_model(follow_request: follow_request::Model, user: user::Model) -> Result<Self> {
+        Ok(Self {
+            user: User::from_model(user)?,
+            created_at: follow_request.created_at,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateBlock {
+    #[schema(value_type = String, format = "ulid")]
+    pub to_id: Ulid,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DomainBlock {
+    pub domain: String,
+    pub created_at: DateTime<FixedOffset>,
+}
+
+impl DomainBlock {
+    pub fn from_model(domain_block: domain_block::Model) -> Self {
+        Self {
+            domain: domain_block.domain,
+            created_at: domain_block.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateDomainBlock {
+    pub domain: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Mute {
+    #[schema(value_type = String, format = "ulid")]
+    pub id: Ulid,
+    pub user: Option<User>,
+    pub domain: Option<String>,
+    #[schema(value_type = Option<String>, format = "ulid")]
+    pub thread_id: Option<Ulid>,
+    pub created_at: DateTime<FixedOffset>,
+    pub expires_at: Option<DateTime<FixedOffset>>,
+}
+
+impl Mute {
+    pub fn from_model(mute: mute::Model, user: Option<user::Model>) -> Result<Self> {
+        Ok(Self {
+            id: mute.id.into(),
+            user: user.map(User::from_model).transpose()?,
+            domain: mute.domain,
+            thread_id: mute.thread_id.map(Into::into),
+            created_at: mute.created_at,
+            expires_at: mute.expires_at,
+        })
+    }
+}
+
+/// Exactly one of `userId`, `domain` and `postId` must be given.
+/// Muting a post mutes the whole thread it belongs to.
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateMute {
+    #[schema(value_type = Option<String>, format = "ulid")]
+    #[serde(default)]
+    pub user_id: Option<Ulid>,
+    #[serde(default)]
+    pub domain: Option<String>,
+    #[schema(value_type = Option<String>, format = "ulid")]
+    #[serde(default)]
+    pub post_id: Option<Ulid>,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<FixedOffset>>,
+}
+
 #[derive(Debug, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateFollow {
@@ -553,6 +972,12 @@ pub struct Setting {
     #[schema(value_type = Option<String>, format = "url")]
     pub object_store_s3_public_url_base: Option<String>,
     pub object_store_local_file_system_base_path: Option<String>,
+    pub hide_follow_collections: bool,
+    pub user_manually_approves_followers: bool,
+    #[schema(value_type = Vec<String>, format = "url")]
+    pub user_also_known_as: Vec<String>,
+    #[schema(value_type = Option<String>, format = "url")]
+    pub user_moved_to: Option<String>,
 }
 
 impl Setting {
@@ -573,6 +998,10 @@ impl Setting {
             object_store_s3_public_url_base: setting.object_store_s3_public_url_base,
             object_store_local_file_system_base_path: setting
                 .object_store_local_file_system_base_path,
+            hide_follow_collections: setting.hide_follow_collections,
+            user_manually_approves_followers: setting.user_manually_approves_followers,
+            user_also_known_as: setting.user_also_known_as,
+            user_moved_to: setting.user_moved_to,
         }
     }
 }
@@ -607,3 +1036,204 @@ pub struct CreateReport {
     pub user_id: Ulid,
     pub content: String,
 }
+
+#[derive(Derivative, Serialize, ToSchema)]
+#[derivative(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Delivery {
+    #[schema(value_type = String, format = "ulid")]
+    pub id: Ulid,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    #[schema(value_type = String, format = "url")]
+    pub activity_id: Url,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    #[schema(value_type = String, format = "url")]
+    pub inbox: Url,
+    pub attempts: i32,
+    pub created_at: DateTime<FixedOffset>,
+    pub next_attempt_at: DateTime<FixedOffset>,
+    pub failed_at: Option<DateTime<FixedOffset>>,
+    pub last_error: Option<String>,
+}
+
+impl Delivery {
+    pub fn from_model(delivery: delivery::Model) -> Result<Self> {
+        Ok(Self {
+            id: delivery.id.into(),
+            activity_id: delivery
+                .activity_id
+                .parse()
+                .context_internal_server_error("malformed activity ID")?,
+            inbox: delivery
+                .inbox
+                .parse()
+                .context_internal_server_error("malformed inbox URL")?,
+            attempts: delivery.attempts,
+            created_at: delivery.created_at,
+            next_attempt_at: delivery.next_attempt_at,
+            failed_at: delivery.failed_at,
+            last_error: delivery.last_error,
+        })
+    }
+}
+
+/// How an access key was issued
+#[derive(Clone, Copy, Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum AccessKeyType {
+    /// Password login
+    Session,
+    /// OAuth authorization of a third-party application
+    Application,
+    /// Long-lived token created for scripts
+    Personal,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessKey {
+    #[schema(value_type = String, format = "ulid")]
+    pub id: Ulid,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: AccessKeyType,
+    pub scopes: Vec<String>,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub created_at: DateTime<FixedOffset>,
+    pub last_used_at: Option<DateTime<FixedOffset>>,
+    pub expires_at: Option<DateTime<FixedOffset>>,
+    /// Whether this is the key the request was made with
+    pub current: bool,
+}
+
+impl AccessKey {
+    pub fn from_model(access_key: access_key::Model, current_id: Ulid) -> Self {
+        let ty = if access_key.application_id.is_some() {
+            AccessKeyType::Application
+        } else if access_key.personal {
+            AccessKeyType::Personal
+        } else {
+            AccessKeyType::Session
+        };
+        let id = Ulid::from(access_key.id);
+        Self {
+            id,
+            name: access_key.name,
+            ty,
+            scopes: access_key.scopes,
+            user_agent: access_key.user_agent,
+            ip: access_key.ip,
+            created_at: access_key.created_at,
+            last_used_at: access_key.last_used_at,
+            expires_at: access_key.expires_at,
+            current: id == current_id,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateAccessKey {
+    pub name: String,
+    /// Defaults to every scope
+    #[serde(default)]
+    pub scopes: Option<Vec<String>>,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<FixedOffset>>,
+}
+
+/// WebAuthn assertion from `navigator.credentials.get()`, with binary fields in base64url
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PasskeyAssertion {
+    pub credential_id: String,
+    pub client_data_json: String,
+    pub authenticator_data: String,
+    pub signature: String,
+}
+
+/// One of the second factors, required once any is enabled
+#[derive(Debug, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SecondFactor {
+    #[serde(default)]
+    pub totp: Option<String>,
+    #[serde(default)]
+    pub recovery_code: Option<String>,
+    #[serde(default)]
+    pub passkey: Option<PasskeyAssertion>,
+}
+
+impl SecondFactor {
+    pub fn is_empty(&self) -> bool {
+        self.totp.is_none() && self.recovery_code.is_none() && self.passkey.is_none()
+    }
+}
+
+/// Proof of the password and a second factor if enabled, for changes weakening the account
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Reauthentication {
+    pub password: String,
+    #[serde(flatten)]
+    pub second_factor: SecondFactor,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Passkey {
+    #[schema(value_type = String, format = "ulid")]
+    pub id: Ulid,
+    pub name: String,
+    pub credential_id: String,
+    pub created_at: DateTime<FixedOffset>,
+    pub last_used_at: Option<DateTime<FixedOffset>>,
+}
+
+impl Passkey {
+    pub fn from_model(passkey: passkey::Model) -> Self {
+        Self {
+            id: passkey.id.into(),
+            name: passkey.name,
+            credential_id: passkey.credential_id,
+            created_at: passkey.created_at,
+            last_used_at: passkey.last_used_at,
+        }
+    }
+}
+
+/// WebAuthn attestation from `navigator.credentials.create()`, with binary fields in base64url
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatePasskey {
+    pub name: String,
+    pub client_data_json: String,
+    pub authenticator_data: String,
+    /// DER `SubjectPublicKeyInfo` from `getPublicKey()`
+    pub public_key: String,
+    /// COSE algorithm from `getPublicKeyAlgorithm()`, either -7 (ES256) or -8 (EdDSA)
+    pub public_key_algorithm: i32,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginLockout {
+    #[schema(value_type = String, format = "ulid")]
+    pub id: Ulid,
+    /// Locked out client IP, or `*` for every client
+    pub ip: String,
+    pub created_at: DateTime<FixedOffset>,
+    pub locked_until: DateTime<FixedOffset>,
+}
+
+impl LoginLockout {
+    pub fn from_model(lockout: login_lockout::Model) -> Self {
+        Self {
+            id: lockout.id.into(),
+            ip: lockout.ip,
+            created_at: lockout.created_at,
+            locked_until: lockout.locked_until,
+        }
+    }
+}
@@ -0,0 +1,916 @@
+//! Entities of the Mastodon client API, built from our own DTOs.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    sea_query::Condition, ColumnTrait, ConnectionTrait, EntityTrait, PaginatorTrait, QueryFilter,
+    QuerySelect,
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use ulid::Ulid;
+
+use crate::{
+    ap::person::LocalPerson,
+    entity::{follow, follower, oauth_application, post, user},
+    error::{Context, Result},
+    util::hashtag_url,
+};
+
+/// `Ulid` exposed as a decimal string, which sorts like the snowflake IDs of Mastodon.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MastodonId(pub Ulid);
+
+impl fmt::Display for MastodonId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", u128::from(self.0))
+    }
+}
+
+impl std::str::FromStr for MastodonId {
+    type Err = ulid::DecodeError;
+
+    /// Also accepts the ULID form used by our own API.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.parse::<u128>() {
+            Ok(id) => Ok(Self(Ulid::from(id))),
+            Err(_) => Ulid::from_string(s).map(Self),
+        }
+    }
+}
+
+impl From<uuid::Uuid> for MastodonId {
+    fn from(value: uuid::Uuid) -> Self {
+        Self(value.into())
+    }
+}
+
+impl Serialize for MastodonId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for MastodonId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+fn ulid_datetime(id: Ulid) -> DateTime<Utc> {
+    id.datetime().into()
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Field {
+    pub name: String,
+    pub value: String,
+    pub verified_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CustomEmoji {
+    pub shortcode: String,
+    pub url: String,
+    pub static_url: String,
+    pub visible_in_picker: bool,
+}
+
+impl CustomEmoji {
+    pub fn from_dto(emoji: super::Emoji) -> Self {
+        Self {
+            shortcode: emoji.name.trim_matches(':').to_string(),
+            url: emoji.image_url.to_string(),
+            static_url: emoji.image_url.to_string(),
+            visible_in_picker: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Account {
+    pub id: MastodonId,
+    pub username: String,
+    pub acct: String,
+    pub display_name: String,
+    pub locked: bool,
+    pub bot: bool,
+    pub group: bool,
+    pub discoverable: bool,
+    pub created_at: DateTime<Utc>,
+    pub note: String,
+    pub url: String,
+    pub uri: String,
+    pub avatar: String,
+    pub avatar_static: String,
+    pub header: String,
+    pub header_static: String,
+    pub followers_count: u64,
+    pub following_count: u64,
+    pub statuses_count: u64,
+    pub last_status_at: Option<String>,
+    pub emojis: Vec<CustomEmoji>,
+    pub fields: Vec<Field>,
+}
+
+impl Account {
+    /// Account of a remote user, whose counts are unknown to us.
+    pub fn from_user(user: user::Model) -> Self {
+        let avatar = user.avatar_url.unwrap_or_default();
+        let header = user.banner_url.unwrap_or_default();
+        Self {
+            id: user.id.into(),
+            acct: format!("{}@{}", user.handle, user.host),
+            display_name: user.name.unwrap_or_default(),
+            username: user.handle,
+            locked: user.manually_approves_followers,
+            bot: user.is_bot,
+            group: false,
+            discoverable: false,
+            created_at: ulid_datetime(user.id.into()),
+            note: user.description.unwrap_or_default(),
+            url: user.uri.clone(),
+            uri: user.uri,
+            avatar_static: avatar.clone(),
+            avatar,
+            header_static: header.clone(),
+            header,
+            followers_count: 0,
+            following_count: 0,
+            statuses_count: 0,
+            last_status_at: None,
+            emojis: Vec::new(),
+            fields: Vec::new(),
+        }
+    }
+
+    /// Account of ours, identified by the ID of the setting.
+    pub async fn local(db: &impl ConnectionTrait) -> Result<Self> {
+        let person = LocalPerson::get(db).await?;
+        let avatar = person
+            .get_avatar_url(db)
+            .await?
+            .map(|url| url.to_string())
+            .unwrap_or_default();
+        let header = person
+            .get_banner_url(db)
+            .await?
+            .map(|url| url.to_string())
+            .unwrap_or_default();
+
+        let followers_count = follower::Entity::find()
+            .count(db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        let following_count = follow::Entity::find()
+            .filter(follow::Column::Accepted.eq(true))
+            .count(db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        let statuses_count = post::Entity::find()
+            .filter(post::Column::UserId.is_null())
+            .count(db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+
+        let display_name = person.display_name().to_string();
+        let setting = person.0;
+        Ok(Self {
+            id: setting.id.into(),
+            username: setting.user_handle.clone(),
+            acct: setting.user_handle,
+            display_name,
+            locked: setting.user_manually_approves_followers,
+            bot: false,
+            group: false,
+            discoverable: true,
+            created_at: ulid_datetime(setting.id.into()),
+            note: setting.user_description.unwrap_or_default(),
+            url: LocalPerson::id().to_string(),
+            uri: LocalPerson::id().to_string(),
+            avatar_static: avatar.clone(),
+            avatar,
+            header_static: header.clone(),
+            header,
+            followers_count,
+            following_count,
+            statuses_count,
+            last_status_at: None,
+            emojis: Vec::new(),
+            fields: Vec::new(),
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AccountSource {
+    pub privacy: &'static str,
+    pub sensitive: bool,
+    pub language: String,
+    pub note: String,
+    pub fields: Vec<Field>,
+    pub follow_requests_count: u64,
+}
+
+/// Account of ours with its settings, returned when verifying credentials.
+#[derive(Clone, Debug, Serialize)]
+pub struct CredentialAccount {
+    #[serde(flatten)]
+    pub account: Account,
+    pub source: AccountSource,
+}
+
+/// Registered client application, with its credentials only when just created.
+#[derive(Clone, Debug, Serialize)]
+pub struct Application {
+    pub id: MastodonId,
+    pub name: String,
+    pub website: Option<String>,
+    pub redirect_uri: String,
+    pub redirect_uris: Vec<String>,
+    pub scopes: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+}
+
+impl Application {
+    pub fn from_model(application: oauth_application::Model, with_credentials: bool) -> Self {
+        let (client_id, client_secret) = if with_credentials {
+            (Some(application.client_id), Some(application.client_secret))
+        } else {
+            (None, None)
+        };
+        Self {
+            id: application.id.into(),
+            name: application.name,
+            website: application.website,
+            redirect_uri: application.redirect_uris.join("\n"),
+            redirect_uris: application.redirect_uris,
+            scopes: application.scopes,
+            client_id,
+            client_secret,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Relationship {
+    pub id: MastodonId,
+    pub following: bool,
+    pub showing_reblogs: bool,
+    pub notifying: bool,
+    pub followed_by: bool,
+    pub blocking: bool,
+    pub blocked_by: bool,
+    pub muting: bool,
+    pub muting_notifications: bool,
+    pub requested: bool,
+    pub domain_blocking: bool,
+    pub endorsed: bool,
+    pub note: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MediaAttachment {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub ty: &'static str,
+    pub url: String,
+    pub preview_url: String,
+    pub remote_url: Option<String>,
+    pub description: Option<String>,
+    pub blurhash: Option<String>,
+}
+
+impl MediaAttachment {
+    pub fn new(id: String, media_type: &mime::Mime, url: String, alt: Option<String>) -> Self {
+        let ty = match media_type.type_() {
+            mime::IMAGE => "image",
+            mime::VIDEO => "video",
+            mime::AUDIO => "audio",
+            _ => "unknown",
+        };
+        Self {
+            id,
+            ty,
+            preview_url: url.clone(),
+            url,
+            remote_url: None,
+            description: alt,
+            blurhash: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Mention {
+    pub id: MastodonId,
+    pub username: String,
+    pub url: String,
+    pub acct: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Tag {
+    pub name: String,
+    pub url: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PollOption {
+    pub title: String,
+    pub votes_count: Option<u32>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Poll {
+    pub id: MastodonId,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub expired: bool,
+    pub multiple: bool,
+    pub votes_count: u32,
+    pub voters_count: Option<u32>,
+    pub options: Vec<PollOption>,
+    pub emojis: Vec<CustomEmoji>,
+    pub voted: bool,
+    pub own_votes: Vec<u32>,
+}
+
+impl Poll {
+    pub fn from_dto(id: MastodonId, poll: super::Poll) -> Self {
+        Self {
+            id,
+            expires_at: poll.ends_at.map(Into::into),
+            expired: poll.is_ended,
+            multiple: poll.is_multiple,
+            votes_count: poll.options.iter().map(|option| option.votes_count).sum(),
+            voters_count: poll.voters_count,
+            options: poll
+                .options
+                .into_iter()
+                .map(|option| PollOption {
+                    title: option.name,
+                    votes_count: Some(option.votes_count),
+                })
+                .collect(),
+            emojis: Vec::new(),
+            voted: !poll.voted.is_empty(),
+            own_votes: poll.voted,
+        }
+    }
+}
+
+pub fn visibility_to_str(visibility: &super::Visibility) -> &'static str {
+    match visibility {
+        super::Visibility::Public => "public",
+        super::Visibility::Home => "unlisted",
+        super::Visibility::Followers => "private",
+        super::Visibility::DirectMessage => "direct",
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatusVisibility {
+    Public,
+    Unlisted,
+    Private,
+    Direct,
+}
+
+impl From<StatusVisibility> for super::Visibility {
+    fn from(value: StatusVisibility) -> Self {
+        match value {
+            StatusVisibility::Public => Self::Public,
+            StatusVisibility::Unlisted => Self::Home,
+            StatusVisibility::Private => Self::Followers,
+            StatusVisibility::Direct => Self::DirectMessage,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Status {
+    pub id: MastodonId,
+    pub uri: String,
+    pub url: String,
+    pub created_at: DateTime<Utc>,
+    pub edited_at: Option<DateTime<Utc>>,
+    pub account: Account,
+    pub content: String,
+    pub visibility: &'static str,
+    pub sensitive: bool,
+    pub spoiler_text: String,
+    pub media_attachments: Vec<MediaAttachment>,
+    pub mentions: Vec<Mention>,
+    pub tags: Vec<Tag>,
+    pub emojis: Vec<CustomEmoji>,
+    pub reblogs_count: u64,
+    pub favourites_count: u64,
+    pub replies_count: u64,
+    pub in_reply_to_id: Option<MastodonId>,
+    pub in_reply_to_account_id: Option<MastodonId>,
+    pub reblog: Option<Box<Status>>,
+    pub poll: Option<Poll>,
+    pub card: Option<()>,
+    pub language: Option<String>,
+    pub favourited: bool,
+    pub reblogged: bool,
+    pub muted: bool,
+    pub bookmarked: bool,
+    pub pinned: bool,
+}
+
+impl Status {
+    /// Converts `post`, following it to the reposted one if it is a plain repost.
+    pub async fn from_post(
+        post: super::Post,
+        me: &Account,
+        db: &impl ConnectionTrait,
+    ) -> Result<Self> {
+        Self::from_posts(vec![post], me, db)
+            .await?
+            .pop()
+            .context_internal_server_error("status not converted")
+    }
+
+    /// [`Status::from_post`] for many posts, querying what they refer to at once.
+    pub async fn from_posts(
+        posts: Vec<super::Post>,
+        me: &Account,
+        db: &impl ConnectionTrait,
+    ) -> Result<Vec<Self>> {
+        let reposted_ids = posts
+            .iter()
+            .filter(|post| post.text.is_empty())
+            .filter_map(|post| post.repost_id)
+            .map(uuid::Uuid::from)
+            .collect::<HashSet<_>>();
+        let reposted = if reposted_ids.is_empty() {
+            Vec::new()
+        } else {
+            post::Entity::find()
+                .filter(post::Column::Id.is_in(reposted_ids))
+                .all(db)
+                .await
+                .context_internal_server_error("failed to query database")?
+        };
+        let mut reposted_posts = Vec::with_capacity(reposted.len());
+        for reposted in reposted {
+            reposted_posts.push(super::Post::from_model(reposted, db).await?);
+        }
+
+        let lookups = StatusLookups::load(posts.iter().chain(&reposted_posts), db).await?;
+        let reblogs = reposted_posts
+            .into_iter()
+            .map(|reposted| {
+                let id = uuid::Uuid::from(reposted.id);
+                Ok((id, Self::from_post_only(reposted, me, &lookups)?))
+            })
+            .collect::<Result<HashMap<_, _>>>()?;
+
+        posts
+            .into_iter()
+            .map(|post| {
+                let reblog = match post.repost_id {
+                    Some(repost_id) if post.text.is_empty() => reblogs
+                        .get(&uuid::Uuid::from(repost_id))
+                        .cloned()
+                        .map(Box::new),
+                    _ => None,
+                };
+                let mut this = Self::from_post_only(post, me, &lookups)?;
+                this.reblog = reblog;
+                Ok(this)
+            })
+            .collect()
+    }
+
+    fn from_post_only(post: super::Post, me: &Account, lookups: &StatusLookups) -> Result<Self> {
+        let id = MastodonId(post.id);
+
+        let in_reply_to_account_id = post.reply_id.and_then(|reply_id| {
+            lookups
+                .reply_user_ids
+                .get(&uuid::Uuid::from(reply_id))
+                .map(|user_id| user_id.map(MastodonId::from).unwrap_or(me.id))
+        });
+
+        let reblogs_count = lookups
+            .reblogs_counts
+            .get(&uuid::Uuid::from(post.id))
+            .copied()
+            .unwrap_or(0);
+        let reblogged = lookups.reblogged_ids.contains(&uuid::Uuid::from(post.id));
+
+        let mut mentions = Vec::with_capacity(post.mentions.len());
+        for mention in &post.mentions {
+            if mention.user_uri == LocalPerson::id() {
+                mentions.push(Mention {
+                    id: me.id,
+                    username: me.username.clone(),
+                    url: me.url.clone(),
+                    acct: me.acct.clone(),
+                });
+                continue;
+            }
+            if let Some(user) = lookups.users_by_uri.get(mention.user_uri.as_str()) {
+                mentions.push(Mention {
+                    id: user.id.into(),
+                    acct: format!("{}@{}", user.handle, user.host),
+                    username: user.handle.clone(),
+                    url: user.uri.clone(),
+                });
+            }
+        }
+
+        let account = match post.user {
+            Some(user) => {
+                let user = lookups
+                    .users
+                    .get(&uuid::Uuid::from(user.id))
+                    .cloned()
+                    .context_internal_server_error("user not found")?;
+                Account::from_user(user)
+            }
+            None => me.clone(),
+        };
+
+        Ok(Self {
+            id,
+            uri: post.uri.to_string(),
+            url: post.uri.to_string(),
+            created_at: post.created_at.into(),
+            edited_at: post.updated_at.map(Into::into),
+            account,
+            content: post.text,
+            visibility: visibility_to_str(&post.visibility),
+            sensitive: post.is_sensitive,
+            spoiler_text: post.title.unwrap_or_default(),
+            media_attachments: post
+                .files
+                .into_iter()
+                .enumerate()
+                .map(|(idx, file)| {
+                    MediaAttachment::new(
+                        format!("{}{:02}", id, idx),
+                        &file.media_type,
+                        file.url.to_string(),
+                        file.alt,
+                    )
+                })
+                .collect(),
+            mentions,
+            tags: post
+                .hashtags
+                .into_iter()
+                .map(|name| {
+                    Ok(Tag {
+                        url: hashtag_url(&name)?.to_string(),
+                        name,
+                    })
+                })
+                .collect::<Result<_>>()?,
+            emojis: post.emojis.into_iter().map(CustomEmoji::from_dto).collect(),
+            reblogs_count,
+            favourites_count: post.reactions.len() as u64,
+            replies_count: post.replies_id.len() as u64,
+            in_reply_to_id: post.reply_id.map(MastodonId),
+            in_reply_to_account_id,
+            reblog: None,
+            poll: post.poll.map(|poll| Poll::from_dto(id, poll)),
+            card: None,
+            language: None,
+            favourited: post
+                .reactions
+                .iter()
+                .any(|reaction| reaction.user.is_none()),
+            reblogged,
+            muted: false,
+            bookmarked: post.is_bookmarked,
+            pinned: post.is_pinned,
+        })
+    }
+
+    pub async fn from_model(
+        post: post::Model,
+        me: &Account,
+        db: &impl ConnectionTrait,
+    ) -> Result<Self> {
+        let post = super::Post::from_model(post, db).await?;
+        Self::from_post(post, me, db).await
+    }
+}
+
+/// Rows referred to by posts being converted by [`Status::from_posts`].
+#[derive(Default)]
+struct StatusLookups {
+    /// Authors of replied posts, `None` for our own
+    reply_user_ids: HashMap<uuid::Uuid, Option<uuid::Uuid>>,
+    reblogs_counts: HashMap<uuid::Uuid, u64>,
+    /// Posts we have reposted
+    reblogged_ids: HashSet<uuid::Uuid>,
+    users: HashMap<uuid::Uuid, user::Model>,
+    users_by_uri: HashMap<String, user::Model>,
+}
+
+impl StatusLookups {
+    async fn load<'a>(
+        posts: impl Iterator<Item = &'a super::Post>,
+        db: &impl ConnectionTrait,
+    ) -> Result<Self> {
+        let mut post_ids = Vec::new();
+        let mut reply_ids = Vec::new();
+        let mut user_ids = Vec::new();
+        let mut user_uris = Vec::new();
+        for post in posts {
+            post_ids.push(uuid::Uuid::from(post.id));
+            reply_ids.extend(post.reply_id.map(uuid::Uuid::from));
+            user_ids.extend(post.user.as_ref().map(|user| uuid::Uuid::from(user.id)));
+            user_uris.extend(
+                post.mentions
+                    .iter()
+                    .map(|mention| mention.user_uri.to_string()),
+            );
+        }
+        let mut this = Self::default();
+        if post_ids.is_empty() {
+            return Ok(this);
+        }
+
+        if !reply_ids.is_empty() {
+            this.reply_user_ids = post::Entity::find()
+                .filter(post::Column::Id.is_in(reply_ids))
+                .select_only()
+                .column(post::Column::Id)
+                .column(post::Column::UserId)
+                .into_tuple::<(uuid::Uuid, Option<uuid::Uuid>)>()
+                .all(db)
+                .await
+                .context_internal_server_error("failed to query database")?
+                .into_iter()
+                .collect();
+        }
+
+        this.reblogs_counts = post::Entity::find()
+            .filter(post::Column::RepostId.is_in(post_ids.clone()))
+            .select_only()
+            .column(post::Column::RepostId)
+            .column_as(post::Column::Id.count(), "count")
+            .group_by(post::Column::RepostId)
+            .into_tuple::<(uuid::Uuid, i64)>()
+            .all(db)
+            .await
+            .context_internal_server_error("failed to query database")?
+            .into_iter()
+            .map(|(id, count)| (id, count as u64))
+            .collect();
+        this.reblogged_ids = post::Entity::find()
+            .filter(post::Column::RepostId.is_in(post_ids))
+            .filter(post::Column::UserId.is_null())
+            .select_only()
+            .column(post::Column::RepostId)
+            .distinct()
+            .into_tuple::<uuid::Uuid>()
+            .all(db)
+            .await
+            .context_internal_server_error("failed to query database")?
+            .into_iter()
+            .collect();
+
+        if !user_ids.is_empty() || !user_uris.is_empty() {
+            let users = user::Entity::find()
+                .filter(
+                    Condition::any()
+                        .add(user::Column::Id.is_in(user_ids))
+                        .add(user::Column::Uri.is_in(user_uris)),
+                )
+                .all(db)
+                .await
+                .context_internal_server_error("failed to query database")?;
+            for user in users {
+                this.users_by_uri.insert(user.uri.clone(), user.clone());
+                this.users.insert(user.id, user);
+            }
+        }
+
+        Ok(this)
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct StatusContext {
+    pub ancestors: Vec<Status>,
+    pub descendants: Vec<Status>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ScheduledStatusParams {
+    pub text: String,
+    pub visibility: &'static str,
+    pub sensitive: bool,
+    pub spoiler_text: Option<String>,
+    pub media_ids: Vec<MastodonId>,
+    pub in_reply_to_id: Option<MastodonId>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ScheduledStatus {
+    pub id: MastodonId,
+    pub scheduled_at: DateTime<Utc>,
+    pub params: ScheduledStatusParams,
+    pub media_attachments: Vec<MediaAttachment>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Notification {
+    pub id: MastodonId,
+    #[serde(rename = "type")]
+    pub ty: &'static str,
+    pub created_at: DateTime<Utc>,
+    pub account: Account,
+    pub status: Option<Status>,
+}
+
+impl Notification {
+    pub fn new(id: Ulid, ty: &'static str, account: Account, status: Option<Status>) -> Self {
+        Self {
+            id: MastodonId(id),
+            ty,
+            created_at: ulid_datetime(id),
+            account,
+            status,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct InstanceStats {
+    pub user_count: u64,
+    pub status_count: u64,
+    pub domain_count: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct InstanceUrls {
+    pub streaming_api: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct StatusesConfiguration {
+    pub max_characters: u32,
+    pub max_media_attachments: u32,
+    pub characters_reserved_per_url: u32,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MediaAttachmentsConfiguration {
+    pub supported_mime_types: Vec<&'static str>,
+    pub image_size_limit: u64,
+    pub image_matrix_limit: u64,
+    pub video_size_limit: u64,
+    pub video_frame_rate_limit: u32,
+    pub video_matrix_limit: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PollsConfiguration {
+    pub max_options: u32,
+    pub max_characters_per_option: u32,
+    pub min_expiration: u64,
+    pub max_expiration: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct InstanceConfiguration {
+    pub statuses: StatusesConfiguration,
+    pub media_attachments: MediaAttachmentsConfiguration,
+    pub polls: PollsConfiguration,
+}
+
+impl InstanceConfiguration {
+    pub fn new() -> Self {
+        Self {
+            statuses: StatusesConfiguration {
+                max_characters: 5000,
+                max_media_attachments: 16,
+                characters_reserved_per_url: 23,
+            },
+            media_attachments: MediaAttachmentsConfiguration {
+                supported_mime_types: vec![
+                    "image/jpeg",
+                    "image/png",
+                    "image/gif",
+                    "image/webp",
+                    "image/avif",
+                    "video/mp4",
+                    "video/webm",
+                    "audio/mpeg",
+                    "audio/ogg",
+                ],
+                image_size_limit: 16 * 1024 * 1024,
+                image_matrix_limit: 8192 * 8192,
+                video_size_limit: 100 * 1024 * 1024,
+                video_frame_rate_limit: 60,
+                video_matrix_limit: 4096 * 4096,
+            },
+            polls: PollsConfiguration {
+                max_options: 16,
+                max_characters_per_option: 200,
+                min_expiration: 300,
+                max_expiration: 60 * 60 * 24 * 365,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct InstanceV1 {
+    pub uri: String,
+    pub title: String,
+    pub short_description: String,
+    pub description: String,
+    pub email: String,
+    pub version: String,
+    pub urls: InstanceUrls,
+    pub stats: InstanceStats,
+    pub thumbnail: Option<String>,
+    pub languages: Vec<String>,
+    pub registrations: bool,
+    pub approval_required: bool,
+    pub invites_enabled: bool,
+    pub configuration: InstanceConfiguration,
+    pub contact_account: Account,
+    pub rules: Vec<()>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct InstanceV2Usage {
+    pub users: InstanceV2UsageUsers,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct InstanceV2UsageUsers {
+    pub active_month: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct InstanceV2Thumbnail {
+    pub url: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct InstanceV2ConfigurationUrls {
+    pub streaming: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct InstanceV2Configuration {
+    pub urls: InstanceV2ConfigurationUrls,
+    #[serde(flatten)]
+    pub inner: InstanceConfiguration,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct InstanceV2Registrations {
+    pub enabled: bool,
+    pub approval_required: bool,
+    pub message: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct InstanceV2Contact {
+    pub email: String,
+    pub account: Account,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct InstanceV2 {
+    pub domain: String,
+    pub title: String,
+    pub version: String,
+    pub source_url: String,
+    pub description: String,
+    pub usage: InstanceV2Usage,
+    pub thumbnail: InstanceV2Thumbnail,
+    pub languages: Vec<String>,
+    pub configuration: InstanceV2Configuration,
+    pub registrations: InstanceV2Registrations,
+    pub contact: InstanceV2Contact,
+    pub rules: Vec<()>,
+}
+
+/// Version string which clients parse to detect supported features.
+pub fn instance_version() -> String {
+    format!(
+        "4.0.0 (compatible; {} {})",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    )
+}
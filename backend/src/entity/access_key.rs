@@ -9,9 +9,33 @@ pub struct Model {
     pub id: Uuid,
     pub name: String,
     pub last_used_at: Option<DateTimeWithTimeZone>,
+    pub application_id: Option<Uuid>,
+    pub scopes: Vec<String>,
+    pub created_at: DateTimeWithTimeZone,
+    pub expires_at: Option<DateTimeWithTimeZone>,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub personal: bool,
+    #[sea_orm(unique)]
+    pub secret_hash: String,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::oauth_application::Entity",
+        from = "Column::ApplicationId",
+        to = "super::oauth_application::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    OauthApplication,
+}
+
+impl Related<super::oauth_application::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::OauthApplication.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {}
@@ -16,10 +16,19 @@ pub struct Model {
     pub alt: Option<String>,
     pub emoji_name: Option<String>,
     pub object_store_type: ObjectStoreType,
+    pub draft_id: Option<Uuid>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::draft::Entity",
+        from = "Column::DraftId",
+        to = "super::draft::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    Draft,
     #[sea_orm(
         belongs_to = "super::emoji::Entity",
         from = "Column::EmojiName",
@@ -38,6 +47,12 @@ pub enum Relation {
     Post,
 }
 
+impl Related<super::draft::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Draft.def()
+    }
+}
+
 impl Related<super::emoji::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Emoji.def()
@@ -1,18 +1,38 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.15
 
 pub mod access_key;
+pub mod block;
+pub mod bookmark;
+pub mod delivery;
+pub mod delivery_inbox;
+pub mod domain_block;
+pub mod draft;
 pub mod emoji;
 pub mod follow;
+pub mod follow_request;
 pub mod follower;
 pub mod hashtag;
+pub mod import;
 pub mod local_file;
+pub mod login_lockout;
 pub mod mention;
+pub mod mute;
 pub mod notification;
+pub mod oauth_application;
+pub mod oauth_authorization;
+pub mod passkey;
+pub mod passkey_challenge;
+pub mod pin;
+pub mod poll;
+pub mod poll_option;
+pub mod poll_vote;
 pub mod post;
+pub mod post_edit;
 pub mod post_emoji;
 pub mod reaction;
 pub mod remote_file;
 pub mod report;
+pub mod scheduled_post;
 pub mod sea_orm_active_enums;
 pub mod setting;
 pub mod user;
@@ -9,6 +9,8 @@ pub struct Model {
     pub id: Uuid,
     #[sea_orm(column_type = "JsonBinary")]
     pub payload: Json,
+    pub user_ids: Vec<Uuid>,
+    pub post_id: Option<Uuid>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -20,10 +20,13 @@ pub struct Model {
     pub repost_id: Option<Uuid>,
     pub source_content: Option<String>,
     pub source_media_type: Option<String>,
+    pub updated_at: Option<DateTimeWithTimeZone>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_one = "super::bookmark::Entity")]
+    Bookmark,
     #[sea_orm(has_many = "super::hashtag::Entity")]
     Hashtag,
     #[sea_orm(has_many = "super::local_file::Entity")]
@@ -46,6 +49,14 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     SelfRef1,
+    #[sea_orm(has_many = "super::mute::Entity")]
+    Mute,
+    #[sea_orm(has_one = "super::pin::Entity")]
+    Pin,
+    #[sea_orm(has_one = "super::poll::Entity")]
+    Poll,
+    #[sea_orm(has_many = "super::post_edit::Entity")]
+    PostEdit,
     #[sea_orm(has_many = "super::post_emoji::Entity")]
     PostEmoji,
     #[sea_orm(has_many = "super::reaction::Entity")]
@@ -62,6 +73,12 @@ pub enum Relation {
     User,
 }
 
+impl Related<super::bookmark::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Bookmark.def()
+    }
+}
+
 impl Related<super::hashtag::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Hashtag.def()
@@ -80,6 +97,30 @@ impl Related<super::mention::Entity> for Entity {
     }
 }
 
+impl Related<super::mute::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Mute.def()
+    }
+}
+
+impl Related<super::pin::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Pin.def()
+    }
+}
+
+impl Related<super::poll::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Poll.def()
+    }
+}
+
+impl Related<super::post_edit::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PostEdit.def()
+    }
+}
+
 impl Related<super::post_emoji::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::PostEmoji.def()
@@ -2,6 +2,18 @@
 
 use sea_orm::entity::prelude::*;
 
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "import_type")]
+pub enum ImportType {
+    #[sea_orm(string_value = "blocks")]
+    Blocks,
+    #[sea_orm(string_value = "bookmarks")]
+    Bookmarks,
+    #[sea_orm(string_value = "following")]
+    Following,
+    #[sea_orm(string_value = "mutes")]
+    Mutes,
+}
 #[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
 #[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "object_store_type")]
 pub enum ObjectStoreType {
@@ -25,6 +25,15 @@ pub struct Model {
     pub object_store_s3_bucket: Option<String>,
     pub object_store_s3_public_url_base: Option<String>,
     pub object_store_local_file_system_base_path: Option<String>,
+    pub hide_follow_collections: bool,
+    pub user_manually_approves_followers: bool,
+    pub user_also_known_as: Vec<String>,
+    pub user_moved_to: Option<String>,
+    pub user_totp_secret: Option<String>,
+    pub user_totp_enabled: bool,
+    pub user_recovery_code_hashes: Vec<String>,
+    pub html_sanitized: bool,
+    pub user_totp_last_step: Option<i64>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
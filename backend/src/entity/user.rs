@@ -21,14 +21,24 @@ pub struct Model {
     pub manually_approves_followers: bool,
     pub is_bot: bool,
     pub description: Option<String>,
+    pub also_known_as: Vec<String>,
+    pub moved_to: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::block::Entity")]
+    Block,
     #[sea_orm(has_many = "super::follow::Entity")]
     Follow,
+    #[sea_orm(has_many = "super::follow_request::Entity")]
+    FollowRequest,
     #[sea_orm(has_many = "super::follower::Entity")]
     Follower,
+    #[sea_orm(has_many = "super::mute::Entity")]
+    Mute,
+    #[sea_orm(has_many = "super::poll_vote::Entity")]
+    PollVote,
     #[sea_orm(has_many = "super::post::Entity")]
     Post,
     #[sea_orm(has_many = "super::reaction::Entity")]
@@ -37,18 +47,42 @@ pub enum Relation {
     Report,
 }
 
+impl Related<super::block::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Block.def()
+    }
+}
+
 impl Related<super::follow::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Follow.def()
     }
 }
 
+impl Related<super::follow_request::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::FollowRequest.def()
+    }
+}
+
 impl Related<super::follower::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Follower.def()
     }
 }
 
+impl Related<super::mute::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Mute.def()
+    }
+}
+
+impl Related<super::poll_vote::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PollVote.def()
+    }
+}
+
 impl Related<super::post::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Post.def()
@@ -1,9 +1,13 @@
+mod block;
+mod domain_block;
 mod emoji;
 mod enums;
 mod follow;
 mod follower;
 mod local_file;
+mod poll;
 mod post;
 mod reaction;
+mod scheduled_post;
 mod setting;
 mod user;
@@ -0,0 +1,31 @@
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, PaginatorTrait, QueryFilter};
+use ulid::Ulid;
+use url::Url;
+
+use crate::{
+    config::CONFIG,
+    entity::{block, user},
+    error::{Context, Error},
+};
+
+impl block::Model {
+    pub fn ap_id(&self) -> Result<Url, Error> {
+        Self::ap_id_from_id(self.to_id.into())
+    }
+
+    pub fn ap_id_from_id(id: Ulid) -> Result<Url, Error> {
+        Url::parse(&format!("https://{}/block/{}", CONFIG.public_domain, id))
+            .context_internal_server_error("failed to construct block URL ID")
+    }
+
+    #[tracing::instrument(skip(db))]
+    pub async fn is_blocked(user_uri: &Url, db: &impl ConnectionTrait) -> Result<bool, Error> {
+        let count = block::Entity::find()
+            .inner_join(user::Entity)
+            .filter(user::Column::Uri.eq(user_uri.as_str()))
+            .count(db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        Ok(count > 0)
+    }
+}
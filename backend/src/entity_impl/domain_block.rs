@@ -0,0 +1,77 @@
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, EntityTrait, PaginatorTrait, QueryFilter, QuerySelect,
+};
+use url::Url;
+
+use crate::{
+    entity::domain_block,
+    error::{Context, Error},
+};
+
+impl domain_block::Model {
+    /// Checks whether the domain of `url` or any of its parent domains is blocked.
+    #[tracing::instrument(skip(db))]
+    pub async fn is_blocked(url: &Url, db: &impl ConnectionTrait) -> Result<bool, Error> {
+        let Some(domain) = url.domain() else {
+            return Ok(false);
+        };
+        let domain = domain.to_lowercase();
+        let candidates = domain
+            .char_indices()
+            .filter(|(_, c)| *c == '.')
+            .map(|(idx, _)| domain[idx + 1..].to_string())
+            .chain(std::iter::once(domain.clone()))
+            .collect::<Vec<_>>();
+        let count = domain_block::Entity::find()
+            .filter(domain_block::Column::Domain.is_in(candidates))
+            .count(db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        Ok(count > 0)
+    }
+
+    /// Removes the URLs whose domain or any of its parent domains is blocked, with a single query
+    /// however many URLs there are.
+    #[tracing::instrument(skip(urls, db))]
+    pub async fn retain_unblocked(
+        urls: &mut Vec<Url>,
+        db: &impl ConnectionTrait,
+    ) -> Result<(), Error> {
+        let blocked_domains = domain_block::Entity::find()
+            .select_only()
+            .column(domain_block::Column::Domain)
+            .into_tuple::<String>()
+            .all(db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        urls.retain(|url| {
+            !url.domain().is_some_and(|domain| {
+                blocked_domains
+                    .iter()
+                    .any(|blocked| is_same_or_subdomain(domain, blocked))
+            })
+        });
+        Ok(())
+    }
+}
+
+fn is_same_or_subdomain(domain: &str, parent: &str) -> bool {
+    let domain = domain.to_lowercase();
+    domain == parent
+        || domain
+            .strip_suffix(parent)
+            .is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_parent_domains() {
+        assert!(is_same_or_subdomain("example.com", "example.com"));
+        assert!(is_same_or_subdomain("Social.Example.com", "example.com"));
+        assert!(!is_same_or_subdomain("notexample.com", "example.com"));
+        assert!(!is_same_or_subdomain("example.com", "social.example.com"));
+    }
+}
@@ -30,6 +30,7 @@ impl local_file::Model {
             post_id: ActiveValue::Set(None),
             emoji_name: ActiveValue::Set(None),
             order: ActiveValue::Set(None),
+            draft_id: ActiveValue::Set(None),
             object_store_key: ActiveValue::Set(object_store_key),
             object_store_type: ActiveValue::Set(object_store_type),
             media_type: ActiveValue::Set(media_type.to_string()),
@@ -64,6 +65,21 @@ impl local_file::Model {
         Ok(())
     }
 
+    /// Marks this file as used by a draft, so that it is not deleted while the draft exists.
+    #[tracing::instrument(skip(db))]
+    pub async fn attach_to_draft(&self, draft_id: Ulid, db: &impl ConnectionTrait) -> Result<()> {
+        let this_activemodel = local_file::ActiveModel {
+            id: ActiveValue::Unchanged(self.id),
+            draft_id: ActiveValue::Set(Some(draft_id.into())),
+            ..Default::default()
+        };
+        this_activemodel
+            .update(db)
+            .await
+            .context_internal_server_error("failed to update database")?;
+        Ok(())
+    }
+
     #[tracing::instrument(skip(db))]
     pub async fn attach_to_emoji(
         &self,
@@ -0,0 +1,327 @@
+use activitypub_federation::{config::Data, traits::Object};
+use chrono::Utc;
+use sea_orm::{
+    sea_query::{Expr, OnConflict},
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, ModelTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, TransactionTrait,
+};
+use ulid::Ulid;
+use url::Url;
+
+use crate::{
+    ap::{
+        note::{Note, NoteType, QuestionClosed, QuestionOption, QuestionOptionReplies, UpdateNote},
+        NoteOrAnnounce,
+    },
+    entity::{mention, poll, poll_option, poll_vote, post, sea_orm_active_enums::Visibility, user},
+    error::{Context, Error},
+    format_err,
+    queue::{Event, Update},
+    state::State,
+    util::get_follower_inboxes,
+};
+
+impl poll::Model {
+    pub fn has_ended(&self) -> bool {
+        self.is_ended
+            || self
+                .ends_at
+                .map(|ends_at| ends_at <= Utc::now())
+                .unwrap_or(false)
+    }
+
+    /// Turns `note` into a `Question` with options and tallies of this poll.
+    #[tracing::instrument(skip(db))]
+    pub async fn fill_question(
+        &self,
+        note: &mut Note,
+        db: &impl ConnectionTrait,
+    ) -> Result<(), Error> {
+        let options = self
+            .find_related(poll_option::Entity)
+            .order_by_asc(poll_option::Column::Order)
+            .all(db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        let options = options
+            .into_iter()
+            .map(|option| QuestionOption {
+                ty: NoteType::Note,
+                name: option.name,
+                replies: Some(QuestionOptionReplies {
+                    ty: Default::default(),
+                    total_items: option.votes_count as u64,
+                }),
+            })
+            .collect::<Vec<_>>();
+
+        note.ty = NoteType::Question;
+        if self.is_multiple {
+            note.any_of = Some(options);
+        } else {
+            note.one_of = Some(options);
+        }
+        note.end_time = self.ends_at;
+        note.closed = if self.is_ended {
+            Some(QuestionClosed::At(
+                self.ends_at.unwrap_or_else(|| Utc::now().fixed_offset()),
+            ))
+        } else {
+            None
+        };
+        note.voters_count = self.voters_count.map(|count| count as u64);
+
+        Ok(())
+    }
+
+    /// Stores poll of a remote `Question`, updating tallies of already known one.
+    #[tracing::instrument(skip(db))]
+    pub async fn save_from_json(
+        post_id: uuid::Uuid,
+        json: &Note,
+        db: &impl ConnectionTrait,
+    ) -> Result<(), Error> {
+        let (options, is_multiple) = match (&json.one_of, &json.any_of) {
+            (Some(options), _) => (options, false),
+            (None, Some(options)) => (options, true),
+            (None, None) => return Ok(()),
+        };
+        let ends_at = match (&json.end_time, &json.closed) {
+            (Some(end_time), _) => Some(*end_time),
+            (None, Some(QuestionClosed::At(closed))) => Some(*closed),
+            (None, Some(QuestionClosed::Flag(true))) => Some(Utc::now().fixed_offset()),
+            (None, Some(QuestionClosed::Flag(false)) | None) => None,
+        };
+
+        let poll_activemodel = poll::ActiveModel {
+            post_id: ActiveValue::Set(post_id),
+            ends_at: ActiveValue::Set(ends_at),
+            is_multiple: ActiveValue::Set(is_multiple),
+            voters_count: ActiveValue::Set(json.voters_count.map(|count| count as i32)),
+            is_ended: ActiveValue::Set(false),
+        };
+        poll::Entity::insert(poll_activemodel)
+            .on_conflict(
+                OnConflict::column(poll::Column::PostId)
+                    .update_columns([
+                        poll::Column::EndsAt,
+                        poll::Column::IsMultiple,
+                        poll::Column::VotersCount,
+                    ])
+                    .to_owned(),
+            )
+            .exec_without_returning(db)
+            .await
+            .context_internal_server_error("failed to insert to database")?;
+
+        poll_option::Entity::delete_many()
+            .filter(poll_option::Column::PostId.eq(post_id))
+            .filter(poll_option::Column::Order.gte(options.len() as i16))
+            .exec(db)
+            .await
+            .context_internal_server_error("failed to delete from database")?;
+        let options = options
+            .iter()
+            .enumerate()
+            .map(|(idx, option)| poll_option::ActiveModel {
+                post_id: ActiveValue::Set(post_id),
+                order: ActiveValue::Set(idx as i16),
+                name: ActiveValue::Set(option.name.clone()),
+                votes_count: ActiveValue::Set(
+                    option
+                        .replies
+                        .as_ref()
+                        .map(|replies| replies.total_items as i32)
+                        .unwrap_or(0),
+                ),
+            })
+            .collect::<Vec<_>>();
+        if !options.is_empty() {
+            poll_option::Entity::insert_many(options)
+                .on_conflict(
+                    OnConflict::columns([poll_option::Column::PostId, poll_option::Column::Order])
+                        .update_columns([
+                            poll_option::Column::Name,
+                            poll_option::Column::VotesCount,
+                        ])
+                        .to_owned(),
+                )
+                .exec_without_returning(db)
+                .await
+                .context_internal_server_error("failed to insert to database")?;
+        }
+
+        Ok(())
+    }
+
+    /// Records votes of a user (or of ours if `user_id` is `None`) on options at `orders`.
+    #[tracing::instrument(skip(db))]
+    pub async fn save_votes(
+        &self,
+        user_id: Option<uuid::Uuid>,
+        orders: &[i16],
+        db: &impl ConnectionTrait,
+    ) -> Result<(), Error> {
+        let existing_count = poll_vote::Entity::find()
+            .filter(poll_vote::Column::PostId.eq(self.post_id))
+            .filter(match user_id {
+                Some(user_id) => poll_vote::Column::UserId.eq(user_id),
+                None => poll_vote::Column::UserId.is_null(),
+            })
+            .count(db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+
+        let now = Utc::now().fixed_offset();
+        for order in orders {
+            let poll_vote_activemodel = poll_vote::ActiveModel {
+                id: ActiveValue::Set(Ulid::new().into()),
+                post_id: ActiveValue::Set(self.post_id),
+                user_id: ActiveValue::Set(user_id),
+                order: ActiveValue::Set(*order),
+                created_at: ActiveValue::Set(now),
+            };
+            poll_vote_activemodel
+                .insert(db)
+                .await
+                .context_internal_server_error("failed to insert to database")?;
+        }
+
+        poll_option::Entity::update_many()
+            .filter(poll_option::Column::PostId.eq(self.post_id))
+            .filter(poll_option::Column::Order.is_in(orders.iter().copied()))
+            .col_expr(
+                poll_option::Column::VotesCount,
+                Expr::col(poll_option::Column::VotesCount).add(1),
+            )
+            .exec(db)
+            .await
+            .context_internal_server_error("failed to update database")?;
+        if existing_count == 0 {
+            poll::Entity::update_many()
+                .filter(poll::Column::PostId.eq(self.post_id))
+                .col_expr(
+                    poll::Column::VotersCount,
+                    Expr::cust(r#"COALESCE("voters_count", 0) + 1"#),
+                )
+                .exec(db)
+                .await
+                .context_internal_server_error("failed to update database")?;
+        }
+
+        Ok(())
+    }
+
+    /// Handles a vote from a remote user on our poll, which is a note replying to it with `name`.
+    #[tracing::instrument(skip(data))]
+    pub async fn receive_vote(
+        &self,
+        voter: &user::Model,
+        name: &str,
+        data: &Data<State>,
+    ) -> Result<(), Error> {
+        if self.has_ended() {
+            return Err(format_err!(BAD_REQUEST, "poll has ended"));
+        }
+
+        let option = self
+            .find_related(poll_option::Entity)
+            .filter(poll_option::Column::Name.eq(name))
+            .one(&*data.db)
+            .await
+            .context_internal_server_error("failed to query database")?
+            .context_bad_request("poll option not found")?;
+
+        let tx = data
+            .db
+            .begin()
+            .await
+            .context_internal_server_error("failed to begin database transaction")?;
+        // lock the poll so that concurrent votes see each other
+        poll::Entity::find_by_id(self.post_id)
+            .lock_exclusive()
+            .one(&tx)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        let existing_orders = self
+            .find_related(poll_vote::Entity)
+            .filter(poll_vote::Column::UserId.eq(voter.id))
+            .select_only()
+            .column(poll_vote::Column::Order)
+            .into_tuple::<i16>()
+            .all(&tx)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        if existing_orders.contains(&option.order)
+            || (!self.is_multiple && !existing_orders.is_empty())
+        {
+            return Ok(());
+        }
+
+        self.save_votes(Some(voter.id), &[option.order], &tx)
+            .await?;
+        tx.commit()
+            .await
+            .context_internal_server_error("failed to commit database transaction")?;
+
+        let event = Event::Update(Update::UpdatePost {
+            post_id: self.post_id.into(),
+        });
+        event.send(&*data.db).await?;
+
+        self.send_update(data).await
+    }
+
+    /// Sends current tallies of our poll to voters, and to followers or mentioned users depending
+    /// on the visibility of the post like [`send_post`](crate::handler::api::post::send_post).
+    #[tracing::instrument(skip(data))]
+    pub async fn send_update(&self, data: &Data<State>) -> Result<(), Error> {
+        let post = self
+            .find_related(post::Entity)
+            .one(&*data.db)
+            .await
+            .context_internal_server_error("failed to query database")?
+            .context_internal_server_error("post not found")?;
+        if post.user_id.is_some() {
+            return Ok(());
+        }
+
+        let voter_inboxes = poll_vote::Entity::find()
+            .filter(poll_vote::Column::PostId.eq(self.post_id))
+            .inner_join(user::Entity)
+            .select_only()
+            .column(user::Column::Inbox)
+            .distinct()
+            .into_tuple::<String>()
+            .all(&*data.db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        let mut inboxes = match post.visibility {
+            Visibility::Public | Visibility::Home | Visibility::Followers => {
+                get_follower_inboxes(&*data.db).await?
+            }
+            Visibility::DirectMessage => post
+                .find_related(mention::Entity)
+                .all(&*data.db)
+                .await
+                .context_internal_server_error("failed to query database")?
+                .into_iter()
+                .filter_map(|mention| Url::parse(&mention.user_uri).ok())
+                .collect(),
+        };
+        inboxes.extend(
+            voter_inboxes
+                .into_iter()
+                .filter_map(|inbox| Url::parse(&inbox).ok()),
+        );
+
+        let NoteOrAnnounce::Note(note) = post.into_json(data).await? else {
+            return Err(format_err!(
+                INTERNAL_SERVER_ERROR,
+                "poll post is not a note"
+            ));
+        };
+        let update = UpdateNote::new(*note)?;
+        update.send(data, inboxes).await
+    }
+}
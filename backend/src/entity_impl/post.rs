@@ -1,18 +1,26 @@
+use std::collections::HashSet;
+
 use activitypub_federation::{
-    config::Data, fetch::object_id::ObjectId, kinds::public,
-    protocol::verification::verify_domains_match, traits::Object,
+    config::Data,
+    fetch::{fetch_object_http, object_id::ObjectId},
+    kinds::public,
+    protocol::verification::verify_domains_match,
+    traits::Object,
 };
 use async_trait::async_trait;
 use sea_orm::{
-    sea_query::OnConflict, ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, ModelTrait,
+    sea_query::{Condition, OnConflict, Query},
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, ModelTrait,
     QueryFilter, QueryOrder, QuerySelect, TransactionTrait,
 };
+use serde::Deserialize;
 use ulid::Ulid;
 use url::Url;
 
 use crate::{
     ap::{
         announce::Announce,
+        collection::{IdOrObject, RemoteCollection},
         note::{Attachment, Note, Source},
         person::LocalPerson,
         tag::{Emoji, EmojiIcon, Hashtag, Mention, Tag},
@@ -20,13 +28,53 @@ use crate::{
     },
     config::CONFIG,
     entity::{
-        hashtag, local_file, mention, post, post_emoji, remote_file, sea_orm_active_enums, user,
+        follow, hashtag, local_file, mention, poll, post, post_edit, post_emoji, remote_file,
+        sea_orm_active_enums, user,
     },
     error::{Context, Error},
+    format_err,
     queue::{Event, Update},
+    sanitize::{sanitize_html, sanitize_text},
     state::State,
 };
 
+/// Maximum number of `replies` collection pages to fetch after the first one.
+const REMOTE_REPLIES_PAGE_LIMIT: usize = 4;
+
+/// Maximum number of replies to fetch from a `replies` collection.
+const REMOTE_REPLIES_ITEM_LIMIT: usize = 40;
+
+tokio::task_local! {
+    static REPLY_FETCH_DEPTH: u32;
+}
+
+/// Resolves reply target post, fetching missing ancestors up to `CONFIG.reply_fetch_depth`.
+/// Returns `None` if the target cannot be resolved, so that the reply itself is still stored.
+async fn resolve_reply_id(
+    in_reply_to: ObjectId<post::Model>,
+    data: &Data<State>,
+) -> Option<uuid::Uuid> {
+    let depth = REPLY_FETCH_DEPTH.try_with(|depth| *depth).unwrap_or(0);
+    let res = if depth < CONFIG.reply_fetch_depth {
+        REPLY_FETCH_DEPTH
+            .scope(depth + 1, in_reply_to.dereference(data))
+            .await
+    } else {
+        in_reply_to.dereference_local(data).await
+    };
+    match res {
+        Ok(post) => Some(post.id),
+        Err(error) => {
+            tracing::warn!(
+                "failed to resolve reply target post {}\n{:?}",
+                in_reply_to.inner(),
+                error.inner
+            );
+            None
+        }
+    }
+}
+
 fn calculate_visibility(to: &[Url], cc: &[Url]) -> sea_orm_active_enums::Visibility {
     if to.contains(&public()) {
         sea_orm_active_enums::Visibility::Public
@@ -48,6 +96,146 @@ impl post::Model {
     pub fn ap_id(&self) -> Result<Url, Error> {
         Self::ap_id_from_id(self.id.into())
     }
+
+    /// Fetches `replies` collection of this remote post to store replies never delivered to us.
+    #[tracing::instrument(skip(data))]
+    pub async fn fetch_remote_replies(&self, data: &Data<State>) -> Result<(), Error> {
+        #[derive(Deserialize)]
+        struct NoteReplies {
+            #[serde(default)]
+            replies: Option<IdOrObject<RemoteCollection>>,
+        }
+
+        if self.user_id.is_none() {
+            return Ok(());
+        }
+
+        let uri = Url::parse(&self.uri).context_internal_server_error("malformed post URI")?;
+        let note = fetch_object_http::<_, NoteReplies>(&uri, data)
+            .await?
+            .object;
+        let collection = match note.replies {
+            Some(IdOrObject::Object(collection)) => collection,
+            Some(IdOrObject::Id(url)) => {
+                fetch_object_http::<_, RemoteCollection>(&url, data)
+                    .await?
+                    .object
+            }
+            None => return Ok(()),
+        };
+
+        let mut reply_ids = collection
+            .items
+            .iter()
+            .chain(collection.ordered_items.iter())
+            .map(|item| item.id().clone())
+            .collect::<Vec<_>>();
+        let mut next = match collection.first {
+            Some(IdOrObject::Object(page)) => {
+                reply_ids.extend(
+                    page.items
+                        .iter()
+                        .chain(page.ordered_items.iter())
+                        .map(|item| item.id().clone()),
+                );
+                page.next
+            }
+            Some(IdOrObject::Id(url)) => Some(url),
+            None => None,
+        };
+        for _ in 0..REMOTE_REPLIES_PAGE_LIMIT {
+            if reply_ids.len() >= REMOTE_REPLIES_ITEM_LIMIT {
+                break;
+            }
+            let Some(url) = next else {
+                break;
+            };
+            let page = fetch_object_http::<_, RemoteCollection>(&url, data)
+                .await?
+                .object;
+            reply_ids.extend(
+                page.items
+                    .iter()
+                    .chain(page.ordered_items.iter())
+                    .map(|item| item.id().clone()),
+            );
+            next = page.next;
+        }
+        let mut seen = HashSet::new();
+        reply_ids.retain(|reply_id| seen.insert(reply_id.clone()));
+        reply_ids.truncate(REMOTE_REPLIES_ITEM_LIMIT);
+
+        for reply_id in reply_ids {
+            let reply_id: ObjectId<post::Model> = reply_id.into();
+            if let Err(error) = reply_id.dereference(data).await {
+                tracing::warn!(
+                    "failed to fetch reply {}\n{:?}",
+                    reply_id.inner(),
+                    error.inner
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Condition matching posts we are allowed to see: our own posts, public and unlisted posts,
+    /// followers-only posts of accepted follows and direct messages mentioning us.
+    pub fn visible_condition() -> Condition {
+        Condition::any()
+            .add(post::Column::UserId.is_null())
+            .add(post::Column::Visibility.is_in([
+                sea_orm_active_enums::Visibility::Public,
+                sea_orm_active_enums::Visibility::Home,
+            ]))
+            .add(
+                Condition::all()
+                    .add(post::Column::Visibility.eq(sea_orm_active_enums::Visibility::Followers))
+                    .add(
+                        post::Column::UserId.in_subquery(
+                            Query::select()
+                                .column(follow::Column::ToId)
+                                .from(follow::Entity)
+                                .and_where(follow::Column::Accepted.eq(true))
+                                .to_owned(),
+                        ),
+                    ),
+            )
+            .add(
+                Condition::all()
+                    .add(
+                        post::Column::Visibility
+                            .eq(sea_orm_active_enums::Visibility::DirectMessage),
+                    )
+                    .add(
+                        post::Column::Id.in_subquery(
+                            Query::select()
+                                .column(mention::Column::PostId)
+                                .from(mention::Entity)
+                                .and_where(mention::Column::UserUri.eq(LocalPerson::id().as_str()))
+                                .to_owned(),
+                        ),
+                    ),
+            )
+    }
+
+    /// Records current content of this post into edit history before it gets overwritten.
+    #[tracing::instrument(skip(db))]
+    pub async fn save_edit(&self, db: &impl ConnectionTrait) -> Result<(), Error> {
+        let post_edit_activemodel = post_edit::ActiveModel {
+            id: ActiveValue::Set(Ulid::new().into()),
+            post_id: ActiveValue::Set(self.id),
+            created_at: ActiveValue::Set(self.updated_at.unwrap_or(self.created_at)),
+            text: ActiveValue::Set(self.text.clone()),
+            title: ActiveValue::Set(self.title.clone()),
+            is_sensitive: ActiveValue::Set(self.is_sensitive),
+        };
+        post_edit_activemodel
+            .insert(db)
+            .await
+            .context_internal_server_error("failed to insert to database")?;
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -266,15 +454,23 @@ impl Object for post::Model {
             }))
             .collect::<Vec<_>>();
 
-        Ok(NoteOrAnnounce::Note(Note {
+        let poll = self
+            .find_related(poll::Entity)
+            .one(&*data.db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+
+        let mut note = Note {
             ty: Default::default(),
             id: uri.into(),
             attributed_to: user_uri,
             quote_url: quote_uri.map(Into::into),
             published: self.created_at,
+            updated: self.updated_at,
             to,
             cc,
             summary: self.title,
+            name: None,
             content: self.text,
             source: Some(Source {
                 content: self.source_content,
@@ -284,7 +480,17 @@ impl Object for post::Model {
             attachment,
             sensitive: self.is_sensitive,
             tag,
-        }))
+            one_of: None,
+            any_of: None,
+            end_time: None,
+            closed: None,
+            voters_count: None,
+        };
+        if let Some(poll) = poll {
+            poll.fill_question(&mut note, &*data.db).await?;
+        }
+
+        Ok(NoteOrAnnounce::Note(Box::new(note)))
     }
 
     #[tracing::instrument(skip(_data))]
@@ -307,25 +513,31 @@ impl Object for post::Model {
     async fn from_json(json: Self::Kind, data: &Data<Self::DataType>) -> Result<Self, Self::Error> {
         match json {
             NoteOrAnnounce::Note(json) => {
-                let user_uri: ObjectId<user::Model> = json.attributed_to.into();
+                let user_uri: ObjectId<user::Model> = json.attributed_to.clone().into();
                 let user = user_uri.dereference(data).await?;
 
-                let repost_id = if let Some(repost_uri) = json.quote_url {
+                let repost_id = if let Some(repost_uri) = json.quote_url.clone() {
                     let repost_post = repost_uri.dereference(data).await?;
                     Some(repost_post.id)
                 } else {
                     None
                 };
 
+                let reply_id = if let Some(in_reply_to) = json.in_reply_to.clone() {
+                    resolve_reply_id(in_reply_to, data).await
+                } else {
+                    None
+                };
+
                 let visibility = calculate_visibility(&json.to, &json.cc);
 
                 let mut this_activemodel = post::ActiveModel {
                     id: ActiveValue::Set(Ulid::new().into()),
                     created_at: ActiveValue::Set(json.published),
-                    reply_id: ActiveValue::Set(None),
+                    reply_id: ActiveValue::Set(reply_id),
                     repost_id: ActiveValue::Set(repost_id),
-                    text: ActiveValue::Set(json.content),
-                    title: ActiveValue::Set(json.summary),
+                    text: ActiveValue::Set(sanitize_html(&json.content)),
+                    title: ActiveValue::Set(json.summary.as_deref().map(sanitize_text)),
                     user_id: ActiveValue::Set(Some(user.id)),
                     visibility: ActiveValue::Set(visibility),
                     is_sensitive: ActiveValue::Set(json.sensitive),
@@ -340,6 +552,7 @@ impl Object for post::Model {
                             .as_ref()
                             .and_then(|source| source.media_type.clone()),
                     ),
+                    updated_at: ActiveValue::Set(json.updated),
                 };
 
                 let tx = data
@@ -348,21 +561,55 @@ impl Object for post::Model {
                     .await
                     .context_internal_server_error("failed to begin database transaction")?;
 
-                let existing_id = post::Entity::find()
+                let existing = post::Entity::find()
                     .filter(post::Column::Uri.eq(json.id.inner().to_string()))
-                    .select_only()
-                    .column(post::Column::Id)
-                    .into_tuple::<uuid::Uuid>()
                     .one(&tx)
                     .await
                     .context_internal_server_error("failed to query database")?;
 
-                let this = if let Some(id) = existing_id {
-                    this_activemodel.id = ActiveValue::Unchanged(id);
-                    this_activemodel
+                let this = if let Some(existing) = existing {
+                    if existing.user_id != Some(user.id) {
+                        return Err(format_err!(
+                            FORBIDDEN,
+                            "note is not by the author of the existing post"
+                        ));
+                    }
+                    this_activemodel.id = ActiveValue::Unchanged(existing.id);
+                    let this = this_activemodel
                         .update(&tx)
                         .await
-                        .context_internal_server_error("failed to update database")?
+                        .context_internal_server_error("failed to update database")?;
+
+                    if existing.text != this.text
+                        || existing.title != this.title
+                        || existing.is_sensitive != this.is_sensitive
+                    {
+                        existing.save_edit(&tx).await?;
+                    }
+
+                    // Tags and attachments may have been changed by an edit, so replace them
+                    remote_file::Entity::delete_many()
+                        .filter(remote_file::Column::PostId.eq(this.id))
+                        .exec(&tx)
+                        .await
+                        .context_internal_server_error("failed to delete from database")?;
+                    mention::Entity::delete_many()
+                        .filter(mention::Column::PostId.eq(this.id))
+                        .exec(&tx)
+                        .await
+                        .context_internal_server_error("failed to delete from database")?;
+                    post_emoji::Entity::delete_many()
+                        .filter(post_emoji::Column::PostId.eq(this.id))
+                        .exec(&tx)
+                        .await
+                        .context_internal_server_error("failed to delete from database")?;
+                    hashtag::Entity::delete_many()
+                        .filter(hashtag::Column::PostId.eq(this.id))
+                        .exec(&tx)
+                        .await
+                        .context_internal_server_error("failed to delete from database")?;
+
+                    this
                 } else {
                     this_activemodel
                         .insert(&tx)
@@ -370,6 +617,8 @@ impl Object for post::Model {
                         .context_internal_server_error("failed to insert to database")?
                 };
 
+                poll::Model::save_from_json(this.id, &json, &tx).await?;
+
                 let remote_files = json
                     .attachment
                     .into_iter()
@@ -502,6 +751,7 @@ impl Object for post::Model {
                     uri: ActiveValue::Set(json.id.inner().to_string()),
                     source_content: ActiveValue::Set(None),
                     source_media_type: ActiveValue::Set(None),
+                    updated_at: ActiveValue::Set(None),
                 };
 
                 let tx = data
@@ -0,0 +1,39 @@
+use chrono::{DateTime, FixedOffset, Utc};
+use sea_orm::{ActiveModelTrait, ActiveValue, ConnectionTrait};
+use ulid::Ulid;
+
+use crate::{
+    dto::CreatePost,
+    entity::scheduled_post,
+    error::{Context, Error},
+};
+
+impl scheduled_post::Model {
+    /// Stores `req` to be published at `scheduled_at` by the scheduled post worker.
+    #[tracing::instrument(skip(db))]
+    pub async fn schedule(
+        scheduled_at: DateTime<FixedOffset>,
+        mut req: CreatePost,
+        db: &impl ConnectionTrait,
+    ) -> Result<Ulid, Error> {
+        req.scheduled_at = None;
+        let request = serde_json::to_value(&req)
+            .context_internal_server_error("failed to serialize scheduled post")?;
+
+        let id = Ulid::new();
+        let scheduled_post_activemodel = scheduled_post::ActiveModel {
+            id: ActiveValue::Set(id.into()),
+            request: ActiveValue::Set(request),
+            created_at: ActiveValue::Set(Utc::now().fixed_offset()),
+            scheduled_at: ActiveValue::Set(scheduled_at),
+            failed_at: ActiveValue::Set(None),
+            last_error: ActiveValue::Set(None),
+        };
+        scheduled_post_activemodel
+            .insert(db)
+            .await
+            .context_internal_server_error("failed to insert to database")?;
+
+        Ok(id)
+    }
+}
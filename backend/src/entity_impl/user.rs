@@ -1,11 +1,14 @@
 use activitypub_federation::{
     config::Data,
-    protocol::{public_key::PublicKey, verification::verify_domains_match},
+    fetch::webfinger::Webfinger,
+    protocol::{context::WithContext, public_key::PublicKey, verification::verify_domains_match},
     traits::{Actor, Object},
 };
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use reqwest::header;
 use sea_orm::{
+    sea_query::{Expr, Func},
     ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, ModelTrait, QueryFilter, QuerySelect,
     TransactionTrait,
 };
@@ -14,8 +17,11 @@ use url::Url;
 
 use crate::{
     ap::person::{ActorType, Person, PersonImage},
-    entity::user,
+    config::CONFIG,
+    entity::{domain_block, user},
     error::{Context, Error},
+    format_err,
+    sanitize::{sanitize_html, sanitize_text},
     state::State,
 };
 
@@ -23,6 +29,90 @@ impl user::Model {
     pub fn display_name(&self) -> &str {
         self.name.as_deref().unwrap_or(&self.handle)
     }
+
+    /// Finds a known user by `handle@host` case-insensitively, resolving it via WebFinger
+    /// otherwise.
+    #[tracing::instrument(skip(data))]
+    pub async fn find_or_resolve(
+        handle: &str,
+        host: &str,
+        data: &Data<State>,
+    ) -> Result<Self, Error> {
+        let existing = user::Entity::find()
+            .filter(
+                Expr::expr(Func::lower(Expr::col((user::Entity, user::Column::Handle))))
+                    .eq(handle.to_lowercase()),
+            )
+            .filter(
+                Expr::expr(Func::lower(Expr::col((user::Entity, user::Column::Host))))
+                    .eq(host.to_lowercase()),
+            )
+            .one(&*data.db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        if let Some(existing) = existing {
+            return Ok(existing);
+        }
+        Self::resolve(handle, host, data).await
+    }
+
+    /// Fetches a remote user of `handle@host` via WebFinger and stores it.
+    #[tracing::instrument(skip(data))]
+    pub async fn resolve(handle: &str, host: &str, data: &Data<State>) -> Result<Self, Error> {
+        let url = if CONFIG.debug {
+            format!(
+                "http://{}/.well-known/webfinger?resource=acct:{}@{}",
+                host, handle, host
+            )
+        } else {
+            format!(
+                "https://{}/.well-known/webfinger?resource=acct:{}@{}",
+                host, handle, host
+            )
+        };
+        let url = Url::parse(&url).context_bad_request("malformed host")?;
+        ensure_not_blocked(&url, data).await?;
+        let resp = data
+            .http_client
+            .get(url)
+            .send()
+            .await
+            .context_internal_server_error("failed to request HTTP")?
+            .error_for_status()
+            .context_internal_server_error("target server returned error")?
+            .json::<Webfinger>()
+            .await
+            .context_internal_server_error("failed to parse webfinger response")?;
+        let activity_url = resp
+            .links
+            .into_iter()
+            .find(|link| link.kind.as_deref() == Some("application/activity+json"))
+            .and_then(|link| link.href)
+            .context_internal_server_error("failed to find webfinger link")?;
+        ensure_not_blocked(&activity_url, data).await?;
+        let person = data
+            .http_client
+            .get(activity_url)
+            .header(header::ACCEPT, "application/activity+json")
+            .send()
+            .await
+            .context_internal_server_error("failed to request HTTP")?
+            .error_for_status()
+            .context_internal_server_error("target server returned error")?
+            .json::<WithContext<Person>>()
+            .await
+            .context_internal_server_error("failed to parse ActivityPub response")?;
+        Self::from_json(person.inner().clone(), data).await
+    }
+}
+
+/// Fails before contacting `url` if its domain is blocked, as `DomainBlockVerifier` does for
+/// fetches through the federation library.
+async fn ensure_not_blocked(url: &Url, data: &Data<State>) -> Result<(), Error> {
+    if domain_block::Model::is_blocked(url, &*data.db).await? {
+        return Err(format_err!(FORBIDDEN, "domain is blocked"));
+    }
+    Ok(())
 }
 
 #[async_trait]
@@ -81,12 +171,22 @@ impl Object for user::Model {
                 .map(|inbox| Url::parse(&inbox))
                 .transpose()
                 .context_internal_server_error("malformed user shared inbox URL")?,
+            outbox: None,
+            followers: None,
+            following: None,
+            featured: None,
             public_key: PublicKey {
                 id: format!("{}#main-key", id),
                 owner: id,
                 public_key_pem: self.public_key,
             },
             manually_approves_followers: self.manually_approves_followers,
+            also_known_as: self
+                .also_known_as
+                .iter()
+                .filter_map(|uri| Url::parse(uri).ok())
+                .collect(),
+            moved_to: self.moved_to.and_then(|uri| Url::parse(&uri).ok()),
         })
     }
 
@@ -106,8 +206,8 @@ impl Object for user::Model {
             id: Ulid::new().into(),
             last_fetched_at: Utc::now().fixed_offset(),
             handle: json.preferred_username,
-            name: json.name,
-            description: json.summary,
+            name: json.name.as_deref().map(sanitize_text),
+            description: json.summary.as_deref().map(sanitize_html),
             host: json
                 .id
                 .inner()
@@ -125,6 +225,8 @@ impl Object for user::Model {
                 ActorType::Person => false,
                 ActorType::Service | ActorType::Application => true,
             },
+            also_known_as: json.also_known_as.iter().map(Url::to_string).collect(),
+            moved_to: json.moved_to.as_ref().map(Url::to_string),
         };
 
         let tx = data
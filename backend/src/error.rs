@@ -1,7 +1,7 @@
 use std::fmt;
 
 use axum::{
-    http::StatusCode,
+    http::{header::RETRY_AFTER, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -17,6 +17,8 @@ pub struct Error {
     pub inner: anyhow::Error,
     pub status_code: StatusCode,
     pub context: SpanTrace,
+    /// Seconds for the `Retry-After` header
+    pub retry_after: Option<u64>,
 }
 
 impl Error {
@@ -35,8 +37,14 @@ impl Error {
             inner,
             status_code,
             context,
+            retry_after: None,
         }
     }
+
+    pub fn with_retry_after(mut self, seconds: u64) -> Self {
+        self.retry_after = Some(seconds);
+        self
+    }
 }
 
 #[derive(Serialize)]
@@ -73,7 +81,13 @@ impl IntoResponse for Error {
                 self.context
             );
         }
-        (self.status_code, Json(resp)).into_response()
+        let mut response = (self.status_code, Json(resp)).into_response();
+        if let Some(retry_after) = self.retry_after {
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(retry_after));
+        }
+        response
     }
 }
 
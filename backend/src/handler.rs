@@ -11,10 +11,11 @@ use utoipa_redoc::{Redoc, Servable};
 use crate::state::State;
 
 mod ap;
-mod api;
+pub(crate) mod api;
 mod file;
 mod frontend;
 mod nodeinfo;
+mod oauth;
 mod well_known;
 
 async fn server_header_middleware<B>(req: Request<B>, next: Next<B>) -> Response {
@@ -33,11 +34,47 @@ async fn server_header_middleware<B>(req: Request<B>, next: Next<B>) -> Response
     paths(
         self::api::auth::post_login,
         self::api::auth::get_check,
+        self::api::auth::get_sessions,
+        self::api::auth::delete_sessions,
+        self::api::auth::delete_session,
+        self::api::auth::get_lockouts,
+        self::api::auth::delete_lockout,
+        self::api::auth::post_token,
+        self::api::auth::post_login_challenge,
+        self::api::auth::post_recovery_codes,
+        self::api::auth::passkey::get_passkeys,
+        self::api::auth::passkey::post_passkey_challenge,
+        self::api::auth::passkey::post_passkey,
+        self::api::auth::passkey::delete_passkey,
+        self::api::auth::totp::get_totp,
+        self::api::auth::totp::post_totp,
+        self::api::auth::totp::post_totp_confirm,
+        self::api::auth::totp::delete_totp,
+        self::api::block::get_blocks,
+        self::api::block::post_block,
+        self::api::block::delete_block,
+        self::api::block::get_domain_blocks,
+        self::api::block::post_domain_block,
+        self::api::block::delete_domain_block,
+        self::api::bookmark::get_bookmarks,
+        self::api::delivery::get_deliveries,
+        self::api::delivery::post_delivery_retry,
+        self::api::draft::get_drafts,
+        self::api::draft::post_draft,
+        self::api::draft::get_draft,
+        self::api::draft::put_draft,
+        self::api::draft::delete_draft,
+        self::api::draft::post_draft_publish,
         self::api::emoji::get_emojis,
         self::api::emoji::post_emoji,
         self::api::emoji::get_emoji,
         self::api::emoji::delete_emoji,
         self::api::event::get_event_stream,
+        self::api::export::get_export_following,
+        self::api::export::get_export_followers,
+        self::api::export::get_export_blocks,
+        self::api::export::get_export_mutes,
+        self::api::export::get_export_bookmarks,
         self::api::file::get_files,
         self::api::file::post_file,
         self::api::file::get_file,
@@ -47,56 +84,124 @@ async fn server_header_middleware<B>(req: Request<B>, next: Next<B>) -> Response
         self::api::follow::delete_follow,
         self::api::follower::get_followers,
         self::api::follower::delete_follower,
+        self::api::follower::get_follow_requests,
+        self::api::follower::post_follow_request_accept,
+        self::api::follower::post_follow_request_reject,
         self::api::hashtag::get_hashtag_posts,
+        self::api::import::get_imports,
+        self::api::import::post_import,
+        self::api::import::get_import,
+        self::api::mute::get_mutes,
+        self::api::mute::post_mute,
+        self::api::mute::delete_mute,
         self::api::notification::get_notifications,
         self::api::notification::get_notification,
         self::api::post::get_posts,
         self::api::post::post_post,
         self::api::post::get_post,
+        self::api::post::put_post,
+        self::api::post::get_post_edits,
+        self::api::post::get_post_context,
+        self::api::post::post_post_vote,
         self::api::post::delete_post,
         self::api::post::get_post_reactions,
         self::api::post::post_post_reaction,
         self::api::post::delete_post_reaction,
+        self::api::post::post_post_pin,
+        self::api::post::delete_post_pin,
+        self::api::post::post_post_bookmark,
+        self::api::post::delete_post_bookmark,
         self::api::reaction::get_reaction,
         self::api::report::get_reports,
         self::api::report::post_report,
         self::api::report::get_report,
         self::api::resolve::get_resolve_user,
         self::api::resolve::get_resolve_link,
+        self::api::schedule::get_scheduled_posts,
+        self::api::schedule::get_scheduled_post,
+        self::api::schedule::put_scheduled_post,
+        self::api::schedule::delete_scheduled_post,
+        self::api::search::get_search,
         self::api::setting::get_setting,
         self::api::setting::post_setting,
         self::api::setting::put_setting,
+        self::api::setting::post_setting_move,
+        self::api::timeline::get_home_timeline,
+        self::api::timeline::get_local_timeline,
+        self::api::timeline::get_federated_timeline,
+        self::api::user::get_user_posts,
     ),
     components(schemas(
+        crate::dto::AccessKey,
+        crate::dto::AccessKeyType,
+        crate::dto::Bookmark,
+        crate::dto::CreateAccessKey,
+        crate::dto::CreateBlock,
+        crate::dto::CreatePasskey,
         crate::dto::CreateContentReaction,
+        crate::dto::CreateDomainBlock,
         crate::dto::CreateEmoji,
         crate::dto::CreateEmojiReaction,
         crate::dto::CreateFollow,
+        crate::dto::CreateMute,
+        crate::dto::CreatePoll,
         crate::dto::CreatePost,
         crate::dto::CreateReaction,
         crate::dto::CreateReport,
+        crate::dto::CreateVote,
+        crate::dto::Delivery,
+        crate::dto::DomainBlock,
+        crate::dto::Draft,
         crate::dto::Emoji,
         crate::dto::File,
         crate::dto::Follow,
+        crate::dto::FollowRequest,
         crate::dto::IdResponse,
+        crate::dto::Import,
+        crate::dto::ImportType,
         crate::dto::LocalEmoji,
         crate::dto::LocalFile,
+        crate::dto::LoginLockout,
         crate::dto::Mention,
+        crate::dto::Mute,
         crate::dto::NameResponse,
         crate::dto::Object,
         crate::dto::ObjectStoreType,
+        crate::dto::Passkey,
+        crate::dto::PasskeyAssertion,
+        crate::dto::Poll,
+        crate::dto::PollOption,
         crate::dto::Post,
+        crate::dto::PostContext,
+        crate::dto::PostEdit,
+        crate::dto::PostFormat,
         crate::dto::Reaction,
+        crate::dto::Reauthentication,
         crate::dto::Report,
+        crate::dto::ScheduledPost,
+        crate::dto::SearchResult,
+        crate::dto::SearchType,
+        crate::dto::SecondFactor,
         crate::dto::Setting,
+        crate::dto::UpdatePost,
         crate::dto::User,
         crate::dto::Visibility,
         crate::queue::Event,
         crate::queue::Notification,
         crate::queue::NotificationType,
         crate::queue::Update,
+        self::api::auth::PostLoginChallengeResp,
         self::api::auth::PostLoginReq,
         self::api::auth::PostLoginResp,
+        self::api::auth::RecoveryCodesResp,
+        self::api::auth::passkey::PasskeyUser,
+        self::api::auth::passkey::PostPasskeyChallengeResp,
+        self::api::auth::passkey::PostPasskeyResp,
+        self::api::auth::passkey::RelyingParty,
+        self::api::auth::totp::GetTotpResp,
+        self::api::auth::totp::PostTotpConfirmReq,
+        self::api::auth::totp::PostTotpResp,
+        self::api::setting::PostSettingMoveReq,
         self::api::setting::PostSettingReq,
         self::api::setting::PutSettingReq,
     )),
@@ -120,6 +225,7 @@ impl Modify for AccessKeyAddon {
 pub async fn create_router(federation_config: FederationConfig<State>) -> anyhow::Result<Router> {
     let api = self::api::create_router();
     let file = self::file::create_router();
+    let oauth = self::oauth::create_router();
     let well_known = self::well_known::create_router();
 
     let follow = self::ap::follow::create_router();
@@ -132,6 +238,7 @@ pub async fn create_router(federation_config: FederationConfig<State>) -> anyhow
     let router = Router::new()
         .nest("/api", api)
         .nest("/file", file)
+        .nest("/oauth", oauth)
         .nest("/.well-known", well_known)
         .route(
             "/nodeinfo/2.0",
@@ -148,6 +255,7 @@ pub async fn create_router(federation_config: FederationConfig<State>) -> anyhow
         )
         .layer(TraceLayer::new_for_http().make_span_with(DefaultMakeSpan::new().level(Level::INFO)))
         .route("/", routing::get(self::frontend::get_index))
+        .route("/tags/:name", routing::get(self::frontend::get_hashtag))
         .route("/*path", routing::get(self::frontend::get_not_found))
         .layer(FederationMiddleware::new(federation_config))
         .nest("/assets", assets)
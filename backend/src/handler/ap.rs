@@ -2,9 +2,19 @@ use activitypub_federation::{
     axum::inbox::{receive_activity, ActivityData},
     config::Data,
     protocol::context::WithContext,
+    traits::ActivityHandler,
 };
+use async_trait::async_trait;
+use serde::Deserialize;
+use ulid::Ulid;
+use url::Url;
 
-use crate::{ap::Activity, error::Result};
+use crate::{
+    ap::Activity,
+    entity::{block, domain_block},
+    error::{Error, Result},
+    format_err,
+};
 
 use super::State;
 
@@ -13,11 +23,47 @@ pub mod like;
 pub mod note;
 pub mod person;
 
+#[derive(Debug, Deserialize)]
+pub struct CollectionPageQuery {
+    #[serde(default)]
+    pub page: bool,
+    #[serde(default)]
+    pub after: Option<Ulid>,
+}
+
+/// Incoming activity which is dropped if its actor is blocked.
+#[derive(Deserialize)]
+#[serde(transparent)]
+struct InboxActivity(WithContext<Activity>);
+
+#[async_trait]
+impl ActivityHandler for InboxActivity {
+    type DataType = State;
+    type Error = Error;
+
+    fn id(&self) -> &Url {
+        self.0.id()
+    }
+
+    fn actor(&self) -> &Url {
+        self.0.actor()
+    }
+
+    async fn verify(&self, data: &Data<Self::DataType>) -> Result<()> {
+        if domain_block::Model::is_blocked(self.actor(), &*data.db).await?
+            || block::Model::is_blocked(self.actor(), &*data.db).await?
+        {
+            return Err(format_err!(FORBIDDEN, "actor is blocked"));
+        }
+        self.0.verify(data).await
+    }
+
+    async fn receive(self, data: &Data<Self::DataType>) -> Result<()> {
+        self.0.receive(data).await
+    }
+}
+
 #[tracing::instrument(skip(data, activity_data))]
 pub(super) async fn post_inbox(data: Data<State>, activity_data: ActivityData) -> Result<()> {
-    receive_activity::<WithContext<Activity>, crate::entity::user::Model, State>(
-        activity_data,
-        &data,
-    )
-    .await
+    receive_activity::<InboxActivity, crate::entity::user::Model, State>(activity_data, &data).await
 }
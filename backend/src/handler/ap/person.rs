@@ -2,19 +2,39 @@ use activitypub_federation::{
     axum::json::FederationJson, config::Data, protocol::context::WithContext, traits::Object,
 };
 use axum::{
+    extract,
     http::{header, HeaderMap, StatusCode},
     routing, Router,
 };
+use futures_util::{stream::FuturesOrdered, TryStreamExt};
+use sea_orm::{ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect};
+use url::Url;
 
 use crate::{
-    ap::person::{LocalPerson, Person},
-    error::Result,
+    ap::{
+        collection::{
+            collection_page_url, OrderedCollection, OrderedCollectionOrPage, OrderedCollectionPage,
+            COLLECTION_PAGE_SIZE,
+        },
+        note::Note,
+        person::{LocalPerson, Person},
+        CreateNoteOrAnnounce, NoteOrAnnounce,
+    },
+    entity::{follow, follower, pin, post, sea_orm_active_enums::Visibility, setting, user},
+    error::{Context, Result},
     handler::frontend::{FrontendContext, RespOrFrontend},
     state::State,
 };
 
+use super::CollectionPageQuery;
+
 pub fn create_router() -> Router {
-    Router::new().route("/", routing::get(get_person))
+    Router::new()
+        .route("/", routing::get(get_person))
+        .route("/outbox", routing::get(get_outbox))
+        .route("/followers", routing::get(get_followers))
+        .route("/following", routing::get(get_following))
+        .route("/featured", routing::get(get_featured))
 }
 
 #[tracing::instrument(skip(data))]
@@ -53,3 +73,243 @@ async fn get_person(
         RespOrFrontend::frontend(StatusCode::OK, &*data.db, ctx).await
     }
 }
+
+#[tracing::instrument(skip(data))]
+async fn get_outbox(
+    data: Data<State>,
+    extract::Query(query): extract::Query<CollectionPageQuery>,
+) -> Result<FederationJson<WithContext<OrderedCollectionOrPage<CreateNoteOrAnnounce>>>> {
+    let outbox = LocalPerson::outbox()?;
+
+    let base_query = post::Entity::find()
+        .filter(post::Column::UserId.is_null())
+        .filter(post::Column::Visibility.is_in([Visibility::Public, Visibility::Home]));
+
+    let total_items = base_query
+        .clone()
+        .count(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+
+    if !query.page {
+        return Ok(FederationJson(WithContext::new_default(
+            OrderedCollectionOrPage::Collection(OrderedCollection {
+                ty: Default::default(),
+                id: outbox.clone(),
+                total_items,
+                first: Some(collection_page_url(&outbox, None)),
+                ordered_items: None,
+            }),
+        )));
+    }
+
+    let pagination_query = if let Some(after) = query.after {
+        base_query.filter(post::Column::Id.lt(uuid::Uuid::from(after)))
+    } else {
+        base_query
+    };
+    let posts = pagination_query
+        .order_by_desc(post::Column::Id)
+        .limit(COLLECTION_PAGE_SIZE)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+
+    let next = if posts.len() as u64 == COLLECTION_PAGE_SIZE {
+        posts
+            .last()
+            .map(|post| collection_page_url(&outbox, Some(post.id.into())))
+    } else {
+        None
+    };
+
+    let ordered_items = posts
+        .into_iter()
+        .map(|post| async { post.into_json(&data).await?.into_activity() })
+        .collect::<FuturesOrdered<_>>()
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    Ok(FederationJson(WithContext::new_default(
+        OrderedCollectionOrPage::Page(OrderedCollectionPage {
+            ty: Default::default(),
+            id: collection_page_url(&outbox, query.after),
+            part_of: outbox,
+            total_items,
+            next,
+            ordered_items,
+        }),
+    )))
+}
+
+#[tracing::instrument(skip(data))]
+async fn get_featured(
+    data: Data<State>,
+) -> Result<FederationJson<WithContext<OrderedCollection<Note>>>> {
+    let posts = post::Entity::find()
+        .inner_join(pin::Entity)
+        .filter(post::Column::UserId.is_null())
+        .filter(post::Column::Visibility.is_in([Visibility::Public, Visibility::Home]))
+        .order_by_desc(pin::Column::CreatedAt)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+
+    let ordered_items = posts
+        .into_iter()
+        .map(|post| post.into_json(&data))
+        .collect::<FuturesOrdered<_>>()
+        .try_collect::<Vec<_>>()
+        .await?
+        .into_iter()
+        .filter_map(|post| match post {
+            NoteOrAnnounce::Note(note) => Some(*note),
+            NoteOrAnnounce::Announce(_) => None,
+        })
+        .collect::<Vec<_>>();
+
+    Ok(FederationJson(WithContext::new_default(
+        OrderedCollection {
+            ty: Default::default(),
+            id: LocalPerson::featured()?,
+            total_items: ordered_items.len() as u64,
+            first: None,
+            ordered_items: Some(ordered_items),
+        },
+    )))
+}
+
+fn user_collection(
+    collection_id: Url,
+    total_items: u64,
+    hidden: bool,
+    query: &CollectionPageQuery,
+    users: Option<Vec<(uuid::Uuid, String)>>,
+) -> OrderedCollectionOrPage<Url> {
+    match users {
+        Some(users) => {
+            let next = if users.len() as u64 == COLLECTION_PAGE_SIZE {
+                users
+                    .last()
+                    .map(|(id, _)| collection_page_url(&collection_id, Some((*id).into())))
+            } else {
+                None
+            };
+            let ordered_items = users
+                .into_iter()
+                .filter_map(|(_, uri)| Url::parse(&uri).ok())
+                .collect::<Vec<_>>();
+
+            OrderedCollectionOrPage::Page(OrderedCollectionPage {
+                ty: Default::default(),
+                id: collection_page_url(&collection_id, query.after),
+                part_of: collection_id,
+                total_items,
+                next,
+                ordered_items,
+            })
+        }
+        None => OrderedCollectionOrPage::Collection(OrderedCollection {
+            ty: Default::default(),
+            first: if hidden {
+                None
+            } else {
+                Some(collection_page_url(&collection_id, None))
+            },
+            id: collection_id,
+            total_items,
+            ordered_items: None,
+        }),
+    }
+}
+
+#[tracing::instrument(skip(data))]
+async fn get_followers(
+    data: Data<State>,
+    extract::Query(query): extract::Query<CollectionPageQuery>,
+) -> Result<FederationJson<WithContext<OrderedCollectionOrPage<Url>>>> {
+    let setting = setting::Model::get(&*data.db).await?;
+    let hidden = setting.hide_follow_collections;
+
+    let total_items = follower::Entity::find()
+        .count(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+
+    let users = if query.page && !hidden {
+        let pagination_query = follower::Entity::find().inner_join(user::Entity);
+        let pagination_query = if let Some(after) = query.after {
+            pagination_query.filter(user::Column::Id.lt(uuid::Uuid::from(after)))
+        } else {
+            pagination_query
+        };
+        let users = pagination_query
+            .select_only()
+            .column(user::Column::Id)
+            .column(user::Column::Uri)
+            .order_by_desc(user::Column::Id)
+            .limit(COLLECTION_PAGE_SIZE)
+            .into_tuple::<(uuid::Uuid, String)>()
+            .all(&*data.db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        Some(users)
+    } else {
+        None
+    };
+
+    Ok(FederationJson(WithContext::new_default(user_collection(
+        LocalPerson::followers()?,
+        total_items,
+        hidden,
+        &query,
+        users,
+    ))))
+}
+
+#[tracing::instrument(skip(data))]
+async fn get_following(
+    data: Data<State>,
+    extract::Query(query): extract::Query<CollectionPageQuery>,
+) -> Result<FederationJson<WithContext<OrderedCollectionOrPage<Url>>>> {
+    let setting = setting::Model::get(&*data.db).await?;
+    let hidden = setting.hide_follow_collections;
+
+    let total_items = follow::Entity::find()
+        .filter(follow::Column::Accepted.eq(true))
+        .count(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+
+    let users = if query.page && !hidden {
+        let pagination_query = follow::Entity::find()
+            .filter(follow::Column::Accepted.eq(true))
+            .inner_join(user::Entity);
+        let pagination_query = if let Some(after) = query.after {
+            pagination_query.filter(user::Column::Id.lt(uuid::Uuid::from(after)))
+        } else {
+            pagination_query
+        };
+        let users = pagination_query
+            .select_only()
+            .column(user::Column::Id)
+            .column(user::Column::Uri)
+            .order_by_desc(user::Column::Id)
+            .limit(COLLECTION_PAGE_SIZE)
+            .into_tuple::<(uuid::Uuid, String)>()
+            .all(&*data.db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        Some(users)
+    } else {
+        None
+    };
+
+    Ok(FederationJson(WithContext::new_default(user_collection(
+        LocalPerson::following()?,
+        total_items,
+        hidden,
+        &query,
+        users,
+    ))))
+}
@@ -1,48 +1,89 @@
-use axum::{routing, Router};
+use axum::{middleware, routing, Router};
+
+use crate::rate_limit;
 
 pub mod auth;
+pub mod block;
+pub mod bookmark;
+pub mod delivery;
+pub mod draft;
 pub mod emoji;
 pub mod event;
+pub mod export;
 pub mod file;
 pub mod follow;
 pub mod follower;
 pub mod hashtag;
+pub mod import;
+pub mod mastodon;
+pub mod mute;
 pub mod notification;
 pub mod post;
 pub mod reaction;
 pub mod report;
 pub mod resolve;
+pub mod schedule;
+pub mod search;
 pub mod setting;
+pub mod timeline;
+pub mod user;
 
 pub(super) fn create_router() -> Router {
     let auth = self::auth::create_router();
+    let block = self::block::create_router();
+    let bookmark = self::bookmark::create_router();
+    let delivery = self::delivery::create_router();
+    let draft = self::draft::create_router();
     let emoji = self::emoji::create_router();
     let event = self::event::create_router();
+    let export = self::export::create_router();
     let file = self::file::create_router();
     let follow = self::follow::create_router();
     let follower = self::follower::create_router();
     let hashtag = self::hashtag::create_router();
+    let import = self::import::create_router();
+    let mute = self::mute::create_router();
     let notification = self::notification::create_router();
     let post = self::post::create_router();
     let reaction = self::reaction::create_router();
     let report = self::report::create_router();
     let resolve = self::resolve::create_router();
+    let schedule = self::schedule::create_router();
+    let search = self::search::create_router();
     let setting = self::setting::create_router();
+    let timeline = self::timeline::create_router();
+    let user = self::user::create_router();
+    let v1 = self::mastodon::create_v1_router();
+    let v2 = self::mastodon::create_v2_router();
 
     Router::new()
         .nest("/auth", auth)
+        .nest("/block", block)
+        .nest("/bookmark", bookmark)
+        .nest("/delivery", delivery)
+        .nest("/draft", draft)
         .nest("/emoji", emoji)
         .nest("/event", event)
+        .nest("/export", export)
         .nest("/file", file)
         .nest("/follow", follow)
         .nest("/follower", follower)
         .nest("/hashtag", hashtag)
+        .nest("/import", import)
+        .nest("/mute", mute)
         .nest("/notification", notification)
         .nest("/post", post)
         .nest("/reaction", reaction)
         .nest("/report", report)
         .nest("/resolve", resolve)
+        .nest("/schedule", schedule)
+        .nest("/search", search)
         .nest("/setting", setting)
+        .nest("/timeline", timeline)
+        .nest("/user", user)
+        .nest("/v1", v1)
+        .nest("/v2", v2)
+        .layer(middleware::from_fn(rate_limit::limit_api))
         .route("/healthz", routing::get(get_healthz))
 }
 
@@ -1,25 +1,38 @@
 use activitypub_federation::config::Data;
 use async_trait::async_trait;
 use axum::{
-    extract::{FromRequestParts, TypedHeader},
+    extract::{self, FromRequestParts, TypedHeader},
+    handler::Handler,
     headers,
     http::request::Parts,
-    routing, Json, RequestPartsExt, Router,
+    middleware, routing, Json, RequestPartsExt, Router,
 };
 use axum_client_ip::InsecureClientIp;
-use chrono::Utc;
-use sea_orm::{ActiveModelTrait, ActiveValue, EntityTrait, ModelTrait, TransactionTrait};
+use chrono::{DateTime, FixedOffset, Utc};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, ModelTrait,
+    QueryFilter, QueryOrder, TransactionTrait,
+};
 use serde::{Deserialize, Serialize};
 use ulid::Ulid;
 use utoipa::ToSchema;
 
 use crate::{
-    entity::{access_key, setting},
+    config::CONFIG,
+    dto::{AccessKey, CreateAccessKey, LoginLockout, Reauthentication, SecondFactor},
+    entity::{access_key, login_lockout, passkey as passkey_entity, setting},
     error::{Context, Error, Result},
     format_err,
+    rate_limit::{self, ClientIp},
+    scope::{self, Scope, FULL_SCOPES},
     state::State,
+    two_factor, user_agent,
+    util::{hash_token, random_token},
 };
 
+pub mod passkey;
+pub mod totp;
+
 pub struct Access {
     pub key: access_key::Model,
 }
@@ -42,20 +55,38 @@ where
             .await
             .context_unauthorized("user not authorized")?;
 
-        let access_key_id =
-            Ulid::from_string(bearer.token()).context_unauthorized("user not authorized")?;
+        Self::from_token(bearer.token(), &data).await
+    }
+}
 
+impl Access {
+    /// Authorizes an access key given as a token outside the `Authorization` header.
+    pub async fn from_token(token: &str, data: &Data<State>) -> Result<Self> {
         let tx = data
             .db
             .begin()
             .await
             .context_internal_server_error("failed to begin database transaction")?;
 
-        let access_key = access_key::Entity::find_by_id(access_key_id)
+        let access_key = access_key::Entity::find()
+            .filter(access_key::Column::SecretHash.eq(hash_token(token)))
             .one(&tx)
             .await
             .context_internal_server_error("failed to request database")?
             .context_unauthorized("user not authorized")?;
+        if access_key
+            .expires_at
+            .is_some_and(|expires_at| expires_at < Utc::now())
+        {
+            access_key
+                .delete(&tx)
+                .await
+                .context_internal_server_error("failed to delete from database")?;
+            tx.commit()
+                .await
+                .context_internal_server_error("failed to commit database transaction")?;
+            return Err(format_err!(UNAUTHORIZED, "access key expired"));
+        }
 
         let mut access_key_activemodel: access_key::ActiveModel = access_key.into();
         access_key_activemodel.last_used_at = ActiveValue::Set(Some(Utc::now().fixed_offset()));
@@ -70,24 +101,71 @@ where
 
         Ok(Access { key: access_key })
     }
+
+    /// Fails unless the access key was granted `scope`.
+    pub fn require(&self, scope: Scope) -> Result<()> {
+        if scope.is_granted(&self.key.scopes) {
+            Ok(())
+        } else {
+            Err(format_err!(FORBIDDEN, "access key lacks scope {}", scope))
+        }
+    }
+
+    /// Fails unless the access key was issued by password login, as only those may manage
+    /// other access keys.
+    pub fn require_session(&self) -> Result<()> {
+        if self.key.application_id.is_none() && !self.key.personal {
+            Ok(())
+        } else {
+            Err(format_err!(FORBIDDEN, "password login required"))
+        }
+    }
 }
 
 pub(super) fn create_router() -> Router {
+    let passkey = self::passkey::create_router();
+    let totp = self::totp::create_router();
+
     Router::new()
-        .route("/login", routing::post(post_login))
+        .route(
+            "/login",
+            routing::post(post_login.layer(middleware::from_fn(rate_limit::limit_login))),
+        )
         .route("/logout", routing::post(post_logout))
         .route("/check", routing::get(get_check))
+        .route(
+            "/session",
+            routing::get(get_sessions).delete(delete_sessions),
+        )
+        .route("/session/:id", routing::delete(delete_session))
+        .route("/token", routing::post(post_token))
+        .route(
+            "/login/challenge",
+            routing::post(post_login_challenge.layer(middleware::from_fn(rate_limit::limit_login))),
+        )
+        .route("/lockout", routing::get(get_lockouts))
+        .route("/lockout/:id", routing::delete(delete_lockout))
+        .route("/recovery-code", routing::post(post_recovery_codes))
+        .nest("/passkey", passkey)
+        .nest("/totp", totp)
 }
 
 #[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct PostLoginReq {
     password: String,
+    /// Keeps the session until logout if not set
+    #[serde(default)]
+    expires_at: Option<DateTime<FixedOffset>>,
+    /// Required once two-factor authentication is enabled
+    #[serde(flatten)]
+    second_factor: SecondFactor,
 }
 
 #[derive(Serialize, ToSchema)]
 pub struct PostLoginResp {
-    #[schema(value_type = String, format = "ulid")]
-    token: Ulid,
+    /// Bearer token, which is only ever returned here
+    token: String,
 }
 
 #[utoipa::path(
@@ -95,34 +173,60 @@ pub struct PostLoginResp {
     path = "/api/auth/login",
     request_body = PostLoginReq,
     responses(
-        (status = 200, body = PostLoginResp)
+        (status = 200, body = PostLoginResp),
+        (status = 401, description = "Second factor required"),
+        (status = 429, description = "Too many attempts, see `Retry-After`"),
     )
 )]
 #[tracing::instrument(skip(data, req))]
 async fn post_login(
     data: Data<State>,
     InsecureClientIp(client_ip): InsecureClientIp,
+    ClientIp(lockout_ip): ClientIp,
+    user_agent: Option<TypedHeader<headers::UserAgent>>,
     Json(req): Json<PostLoginReq>,
 ) -> Result<Json<PostLoginResp>> {
+    rate_limit::check_lockout(lockout_ip, &*data.db).await?;
+
+    let user_agent = user_agent.map(|TypedHeader(user_agent)| user_agent.to_string());
     let setting = setting::Model::get(&*data.db).await?;
     if bcrypt::verify(&req.password, &setting.user_password_hash)
         .context_bad_request("failed to authenticate")?
     {
+        if two_factor::is_enabled(&setting, &*data.db).await? {
+            if req.second_factor.is_empty() {
+                return Err(format_err!(UNAUTHORIZED, "second factor required"));
+            }
+            if !two_factor::verify(&req.second_factor, &setting, &*data.db).await? {
+                rate_limit::record_login_failure(lockout_ip, &*data.db).await?;
+                return Err(format_err!(BAD_REQUEST, "failed to authenticate"));
+            }
+        }
+
+        let token = random_token();
         let access_key_activemodel = access_key::ActiveModel {
             id: ActiveValue::Set(Ulid::new().into()),
-            // TODO: Parse user agent for more expressive name?
-            name: ActiveValue::Set(client_ip.to_string()),
+            name: ActiveValue::Set(user_agent::describe(
+                user_agent.as_deref().unwrap_or_default(),
+            )),
             last_used_at: ActiveValue::NotSet,
+            application_id: ActiveValue::Set(None),
+            scopes: ActiveValue::Set(FULL_SCOPES.iter().map(|scope| scope.to_string()).collect()),
+            created_at: ActiveValue::Set(Utc::now().fixed_offset()),
+            expires_at: ActiveValue::Set(req.expires_at),
+            user_agent: ActiveValue::Set(user_agent),
+            ip: ActiveValue::Set(Some(client_ip.to_string())),
+            personal: ActiveValue::Set(false),
+            secret_hash: ActiveValue::Set(hash_token(&token)),
         };
-        let access_key = access_key_activemodel
+        access_key_activemodel
             .insert(&*data.db)
             .await
             .context_internal_server_error("failed to insert to database")?;
 
-        Ok(Json(PostLoginResp {
-            token: access_key.id.into(),
-        }))
+        Ok(Json(PostLoginResp { token }))
     } else {
+        rate_limit::record_login_failure(lockout_ip, &*data.db).await?;
         Err(format_err!(BAD_REQUEST, "failed to authenticate"))
     }
 }
@@ -157,3 +261,322 @@ async fn post_logout(data: Data<State>, access: Access) -> Result<()> {
 async fn get_check(_access: Access) {
     // noop
 }
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/session",
+    responses(
+        (status = 200, body = Vec<AccessKey>),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn get_sessions(data: Data<State>, access: Access) -> Result<Json<Vec<AccessKey>>> {
+    access.require_session()?;
+
+    let access_keys = access_key::Entity::find()
+        .order_by_desc(access_key::Column::CreatedAt)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    let current_id = access.key.id.into();
+    Ok(Json(
+        access_keys
+            .into_iter()
+            .map(|access_key| AccessKey::from_model(access_key, current_id))
+            .collect(),
+    ))
+}
+
+/// Logs out everywhere, revoking every access key but personal tokens.
+#[utoipa::path(
+    delete,
+    path = "/api/auth/session",
+    responses(
+        (status = 200),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn delete_sessions(data: Data<State>, access: Access) -> Result<()> {
+    access.require_session()?;
+
+    access_key::Entity::delete_many()
+        .filter(access_key::Column::Personal.eq(false))
+        .exec(&*data.db)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+    Ok(())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/auth/session/{id}",
+    params(
+        ("id" = String, format = "ulid"),
+    ),
+    responses(
+        (status = 200),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn delete_session(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<Ulid>,
+) -> Result<()> {
+    access.require_session()?;
+
+    let access_key = access_key::Entity::find_by_id(id)
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("access key not found")?;
+    access_key
+        .delete(&*data.db)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+    Ok(())
+}
+
+/// Lists client IPs locked out after too many failed logins, including expired lockouts.
+#[utoipa::path(
+    get,
+    path = "/api/auth/lockout",
+    responses(
+        (status = 200, body = Vec<LoginLockout>),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn get_lockouts(data: Data<State>, access: Access) -> Result<Json<Vec<LoginLockout>>> {
+    access.require_session()?;
+
+    let lockouts = login_lockout::Entity::find()
+        .order_by_desc(login_lockout::Column::CreatedAt)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    Ok(Json(
+        lockouts.into_iter().map(LoginLockout::from_model).collect(),
+    ))
+}
+
+/// Lifts a lockout before it expires.
+#[utoipa::path(
+    delete,
+    path = "/api/auth/lockout/{id}",
+    params(
+        ("id" = String, format = "ulid"),
+    ),
+    responses(
+        (status = 200),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn delete_lockout(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<Ulid>,
+) -> Result<()> {
+    access.require_session()?;
+
+    let lockout = login_lockout::Entity::find_by_id(id)
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("lockout not found")?;
+    lockout
+        .delete(&*data.db)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+    Ok(())
+}
+
+/// Creates a named personal token, for scripts that should not log in with the password.
+#[utoipa::path(
+    post,
+    path = "/api/auth/token",
+    request_body = CreateAccessKey,
+    responses(
+        (status = 200, body = PostLoginResp),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn post_token(
+    data: Data<State>,
+    access: Access,
+    Json(req): Json<CreateAccessKey>,
+) -> Result<Json<PostLoginResp>> {
+    access.require_session()?;
+
+    let scopes = match req.scopes {
+        Some(scopes) => {
+            if scopes.is_empty() || !scopes.iter().all(|scope| scope::is_valid(scope)) {
+                return Err(format_err!(BAD_REQUEST, "unknown scope"));
+            }
+            scopes
+        }
+        None => FULL_SCOPES.iter().map(|scope| scope.to_string()).collect(),
+    };
+    if req.name.trim().is_empty() {
+        return Err(format_err!(BAD_REQUEST, "name must not be empty"));
+    }
+
+    let token = random_token();
+    let access_key_activemodel = access_key::ActiveModel {
+        id: ActiveValue::Set(Ulid::new().into()),
+        name: ActiveValue::Set(req.name),
+        last_used_at: ActiveValue::NotSet,
+        application_id: ActiveValue::Set(None),
+        scopes: ActiveValue::Set(scopes),
+        created_at: ActiveValue::Set(Utc::now().fixed_offset()),
+        expires_at: ActiveValue::Set(req.expires_at),
+        user_agent: ActiveValue::Set(None),
+        ip: ActiveValue::Set(None),
+        personal: ActiveValue::Set(true),
+        secret_hash: ActiveValue::Set(hash_token(&token)),
+    };
+    access_key_activemodel
+        .insert(&*data.db)
+        .await
+        .context_internal_server_error("failed to insert to database")?;
+
+    Ok(Json(PostLoginResp { token }))
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PostLoginChallengeResp {
+    /// Base64url encoded
+    challenge: String,
+    rp_id: String,
+    /// Base64url encoded IDs of the registered passkeys
+    allow_credentials: Vec<String>,
+    /// In milliseconds
+    timeout: i64,
+}
+
+/// Issues a challenge for a passkey assertion at login.
+#[utoipa::path(
+    post,
+    path = "/api/auth/login/challenge",
+    responses(
+        (status = 200, body = PostLoginChallengeResp),
+    ),
+)]
+#[tracing::instrument(skip(data))]
+async fn post_login_challenge(data: Data<State>) -> Result<Json<PostLoginChallengeResp>> {
+    let allow_credentials = passkey_entity::Entity::find()
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .into_iter()
+        .map(|passkey| passkey.credential_id)
+        .collect::<Vec<_>>();
+    if allow_credentials.is_empty() {
+        return Err(format_err!(NOT_FOUND, "no passkey registered"));
+    }
+
+    Ok(Json(PostLoginChallengeResp {
+        challenge: two_factor::create_challenge(&*data.db).await?,
+        rp_id: CONFIG.public_domain.clone(),
+        allow_credentials,
+        timeout: two_factor::CHALLENGE_LIFETIME.num_milliseconds(),
+    }))
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryCodesResp {
+    /// Shown only once; each can be used in place of a second factor once
+    recovery_codes: Vec<String>,
+}
+
+/// Replaces the recovery codes with new ones.
+#[utoipa::path(
+    post,
+    path = "/api/auth/recovery-code",
+    request_body = Reauthentication,
+    responses(
+        (status = 200, body = RecoveryCodesResp),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access, req))]
+async fn post_recovery_codes(
+    data: Data<State>,
+    access: Access,
+    ClientIp(client_ip): ClientIp,
+    Json(req): Json<Reauthentication>,
+) -> Result<Json<RecoveryCodesResp>> {
+    access.require_session()?;
+
+    let setting = setting::Model::get(&*data.db).await?;
+    two_factor::reauthenticate(&req, &setting, client_ip, &*data.db).await?;
+    // Verifying a recovery code may have updated the setting
+    let setting = setting::Model::get(&*data.db).await?;
+
+    let (recovery_codes, hashes) = two_factor::generate_recovery_codes();
+    let mut setting_activemodel: setting::ActiveModel = setting.into();
+    setting_activemodel.user_recovery_code_hashes = ActiveValue::Set(hashes);
+    setting_activemodel
+        .update(&*data.db)
+        .await
+        .context_internal_server_error("failed to update database")?;
+
+    Ok(Json(RecoveryCodesResp { recovery_codes }))
+}
+
+/// Generates recovery codes if there are none, as when the first second factor is enabled.
+/// Returns an empty list otherwise.
+async fn generate_initial_recovery_codes(
+    setting: setting::Model,
+    db: &impl ConnectionTrait,
+) -> Result<Vec<String>> {
+    if !setting.user_recovery_code_hashes.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let (recovery_codes, hashes) = two_factor::generate_recovery_codes();
+    let mut setting_activemodel: setting::ActiveModel = setting.into();
+    setting_activemodel.user_recovery_code_hashes = ActiveValue::Set(hashes);
+    setting_activemodel
+        .update(db)
+        .await
+        .context_internal_server_error("failed to update database")?;
+    Ok(recovery_codes)
+}
+
+/// Removes recovery codes once no second factor is left, as they are useless by then.
+async fn clear_unused_recovery_codes(db: &impl ConnectionTrait) -> Result<()> {
+    let setting = setting::Model::get(db).await?;
+    if two_factor::is_enabled(&setting, db).await? {
+        return Ok(());
+    }
+
+    let mut setting_activemodel: setting::ActiveModel = setting.into();
+    setting_activemodel.user_recovery_code_hashes = ActiveValue::Set(Vec::new());
+    setting_activemodel
+        .update(db)
+        .await
+        .context_internal_server_error("failed to update database")?;
+    Ok(())
+}
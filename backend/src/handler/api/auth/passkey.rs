@@ -0,0 +1,247 @@
+use activitypub_federation::config::Data;
+use axum::{extract, routing, Json, Router};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, ModelTrait, PaginatorTrait,
+    QueryFilter, QueryOrder,
+};
+use serde::Serialize;
+use ulid::Ulid;
+use utoipa::ToSchema;
+
+use crate::{
+    config::CONFIG,
+    dto::{CreatePasskey, Passkey, Reauthentication},
+    entity::{passkey, setting},
+    error::{Context, Result},
+    format_err,
+    rate_limit::ClientIp,
+    state::State,
+    two_factor,
+};
+
+use super::{clear_unused_recovery_codes, generate_initial_recovery_codes, Access};
+
+pub(super) fn create_router() -> Router {
+    Router::new()
+        .route("/", routing::get(get_passkeys).post(post_passkey))
+        .route("/challenge", routing::post(post_passkey_challenge))
+        .route("/:id", routing::delete(delete_passkey))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/passkey",
+    responses(
+        (status = 200, body = Vec<Passkey>),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn get_passkeys(data: Data<State>, access: Access) -> Result<Json<Vec<Passkey>>> {
+    access.require_session()?;
+
+    let passkeys = passkey::Entity::find()
+        .order_by_desc(passkey::Column::CreatedAt)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    Ok(Json(
+        passkeys.into_iter().map(Passkey::from_model).collect(),
+    ))
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RelyingParty {
+    id: String,
+    name: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PasskeyUser {
+    /// Base64url encoded
+    id: String,
+    name: String,
+    display_name: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PostPasskeyChallengeResp {
+    /// Base64url encoded
+    challenge: String,
+    rp: RelyingParty,
+    user: PasskeyUser,
+    /// COSE algorithms supported
+    algorithms: Vec<i32>,
+    /// Base64url encoded IDs of the registered passkeys
+    exclude_credentials: Vec<String>,
+    /// In milliseconds
+    timeout: i64,
+}
+
+/// Issues a challenge and the options for `navigator.credentials.create()`.
+#[utoipa::path(
+    post,
+    path = "/api/auth/passkey/challenge",
+    responses(
+        (status = 200, body = PostPasskeyChallengeResp),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn post_passkey_challenge(
+    data: Data<State>,
+    access: Access,
+) -> Result<Json<PostPasskeyChallengeResp>> {
+    access.require_session()?;
+
+    let setting = setting::Model::get(&*data.db).await?;
+    let exclude_credentials = passkey::Entity::find()
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .into_iter()
+        .map(|passkey| passkey.credential_id)
+        .collect();
+
+    Ok(Json(PostPasskeyChallengeResp {
+        challenge: two_factor::create_challenge(&*data.db).await?,
+        rp: RelyingParty {
+            id: CONFIG.public_domain.clone(),
+            name: setting.instance_name,
+        },
+        user: PasskeyUser {
+            id: URL_SAFE_NO_PAD.encode(setting.id.as_bytes()),
+            display_name: setting
+                .user_name
+                .unwrap_or_else(|| setting.user_handle.clone()),
+            name: setting.user_handle,
+        },
+        algorithms: vec![two_factor::ES256, two_factor::EDDSA],
+        exclude_credentials,
+        timeout: two_factor::CHALLENGE_LIFETIME.num_milliseconds(),
+    }))
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PostPasskeyResp {
+    #[schema(value_type = String, format = "ulid")]
+    id: Ulid,
+    /// Generated only along with the first second factor, empty otherwise
+    recovery_codes: Vec<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/passkey",
+    request_body = CreatePasskey,
+    responses(
+        (status = 200, body = PostPasskeyResp),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access, req))]
+async fn post_passkey(
+    data: Data<State>,
+    access: Access,
+    Json(req): Json<CreatePasskey>,
+) -> Result<Json<PostPasskeyResp>> {
+    access.require_session()?;
+
+    if req.name.trim().is_empty() {
+        return Err(format_err!(BAD_REQUEST, "name must not be empty"));
+    }
+    let client_data_json = two_factor::decode_base64url(&req.client_data_json)?;
+    let authenticator_data = two_factor::decode_base64url(&req.authenticator_data)?;
+    let public_key = two_factor::decode_base64url(&req.public_key)?;
+
+    let challenge = two_factor::verify_client_data(&client_data_json, "webauthn.create")?;
+    two_factor::consume_challenge(&challenge, &*data.db).await?;
+    let parsed = two_factor::parse_authenticator_data(&authenticator_data, &CONFIG.public_domain)?;
+    let credential_id = parsed
+        .credential_id
+        .context_bad_request("attested credential data not found")?;
+    two_factor::raw_public_key(req.public_key_algorithm, &public_key)?;
+
+    let credential_id = URL_SAFE_NO_PAD.encode(credential_id);
+    let existing_count = passkey::Entity::find()
+        .filter(passkey::Column::CredentialId.eq(&credential_id))
+        .count(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    if existing_count > 0 {
+        return Err(format_err!(CONFLICT, "passkey already registered"));
+    }
+
+    let passkey_activemodel = passkey::ActiveModel {
+        id: ActiveValue::Set(Ulid::new().into()),
+        name: ActiveValue::Set(req.name),
+        credential_id: ActiveValue::Set(credential_id),
+        public_key: ActiveValue::Set(URL_SAFE_NO_PAD.encode(public_key)),
+        algorithm: ActiveValue::Set(req.public_key_algorithm),
+        sign_count: ActiveValue::Set(i64::from(parsed.sign_count)),
+        created_at: ActiveValue::Set(Utc::now().fixed_offset()),
+        last_used_at: ActiveValue::Set(None),
+    };
+    let passkey = passkey_activemodel
+        .insert(&*data.db)
+        .await
+        .context_internal_server_error("failed to insert to database")?;
+
+    let setting = setting::Model::get(&*data.db).await?;
+    Ok(Json(PostPasskeyResp {
+        id: passkey.id.into(),
+        recovery_codes: generate_initial_recovery_codes(setting, &*data.db).await?,
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/auth/passkey/{id}",
+    params(
+        ("id" = String, format = "ulid"),
+    ),
+    request_body = Reauthentication,
+    responses(
+        (status = 200),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access, req))]
+async fn delete_passkey(
+    data: Data<State>,
+    access: Access,
+    ClientIp(client_ip): ClientIp,
+    extract::Path(id): extract::Path<Ulid>,
+    Json(req): Json<Reauthentication>,
+) -> Result<()> {
+    access.require_session()?;
+
+    let passkey = passkey::Entity::find_by_id(id)
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("passkey not found")?;
+    let setting = setting::Model::get(&*data.db).await?;
+    two_factor::reauthenticate(&req, &setting, client_ip, &*data.db).await?;
+
+    passkey
+        .delete(&*data.db)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+
+    clear_unused_recovery_codes(&*data.db).await?;
+    Ok(())
+}
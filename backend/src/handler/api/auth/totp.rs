@@ -0,0 +1,178 @@
+use activitypub_federation::config::Data;
+use axum::{routing, Json, Router};
+use sea_orm::{ActiveModelTrait, ActiveValue};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{
+    dto::Reauthentication,
+    entity::setting,
+    error::{Context, Result},
+    format_err,
+    rate_limit::ClientIp,
+    state::State,
+    two_factor,
+};
+
+use super::{
+    clear_unused_recovery_codes, generate_initial_recovery_codes, Access, RecoveryCodesResp,
+};
+
+pub(super) fn create_router() -> Router {
+    Router::new()
+        .route(
+            "/",
+            routing::get(get_totp).post(post_totp).delete(delete_totp),
+        )
+        .route("/confirm", routing::post(post_totp_confirm))
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct GetTotpResp {
+    enabled: bool,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/totp",
+    responses(
+        (status = 200, body = GetTotpResp),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn get_totp(data: Data<State>, access: Access) -> Result<Json<GetTotpResp>> {
+    access.require_session()?;
+
+    let setting = setting::Model::get(&*data.db).await?;
+    Ok(Json(GetTotpResp {
+        enabled: setting.user_totp_enabled,
+    }))
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PostTotpResp {
+    /// Base32 encoded, for entering manually
+    secret: String,
+    /// `otpauth://` URL, usually shown as a QR code
+    url: String,
+}
+
+/// Starts TOTP enrollment with a new secret, which takes effect once confirmed.
+#[utoipa::path(
+    post,
+    path = "/api/auth/totp",
+    responses(
+        (status = 200, body = PostTotpResp),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn post_totp(data: Data<State>, access: Access) -> Result<Json<PostTotpResp>> {
+    access.require_session()?;
+
+    let setting = setting::Model::get(&*data.db).await?;
+    if setting.user_totp_enabled {
+        return Err(format_err!(CONFLICT, "TOTP already enabled"));
+    }
+
+    let secret = two_factor::generate_totp_secret();
+    let url = two_factor::totp_url(&secret, &setting.user_handle)?;
+    let mut setting_activemodel: setting::ActiveModel = setting.into();
+    setting_activemodel.user_totp_secret = ActiveValue::Set(Some(secret.clone()));
+    setting_activemodel
+        .update(&*data.db)
+        .await
+        .context_internal_server_error("failed to update database")?;
+
+    Ok(Json(PostTotpResp { secret, url }))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct PostTotpConfirmReq {
+    code: String,
+}
+
+/// Enables TOTP once the authenticator app shows a matching code.
+#[utoipa::path(
+    post,
+    path = "/api/auth/totp/confirm",
+    request_body = PostTotpConfirmReq,
+    responses(
+        (status = 200, body = RecoveryCodesResp),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access, req))]
+async fn post_totp_confirm(
+    data: Data<State>,
+    access: Access,
+    Json(req): Json<PostTotpConfirmReq>,
+) -> Result<Json<RecoveryCodesResp>> {
+    access.require_session()?;
+
+    let setting = setting::Model::get(&*data.db).await?;
+    if setting.user_totp_enabled {
+        return Err(format_err!(CONFLICT, "TOTP already enabled"));
+    }
+    let secret = setting
+        .user_totp_secret
+        .as_deref()
+        .context_bad_request("TOTP enrollment not started")?;
+    let step = two_factor::verify_totp(secret, &setting.user_handle, &req.code, None)?
+        .context_bad_request("wrong TOTP code")?;
+
+    let mut setting_activemodel: setting::ActiveModel = setting.into();
+    setting_activemodel.user_totp_enabled = ActiveValue::Set(true);
+    setting_activemodel.user_totp_last_step = ActiveValue::Set(Some(step));
+    let setting = setting_activemodel
+        .update(&*data.db)
+        .await
+        .context_internal_server_error("failed to update database")?;
+
+    Ok(Json(RecoveryCodesResp {
+        recovery_codes: generate_initial_recovery_codes(setting, &*data.db).await?,
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/auth/totp",
+    request_body = Reauthentication,
+    responses(
+        (status = 200),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access, req))]
+async fn delete_totp(
+    data: Data<State>,
+    access: Access,
+    ClientIp(client_ip): ClientIp,
+    Json(req): Json<Reauthentication>,
+) -> Result<()> {
+    access.require_session()?;
+
+    let setting = setting::Model::get(&*data.db).await?;
+    two_factor::reauthenticate(&req, &setting, client_ip, &*data.db).await?;
+    let setting = setting::Model::get(&*data.db).await?;
+
+    let mut setting_activemodel: setting::ActiveModel = setting.into();
+    setting_activemodel.user_totp_secret = ActiveValue::Set(None);
+    setting_activemodel.user_totp_enabled = ActiveValue::Set(false);
+    setting_activemodel
+        .update(&*data.db)
+        .await
+        .context_internal_server_error("failed to update database")?;
+
+    clear_unused_recovery_codes(&*data.db).await?;
+    Ok(())
+}
@@ -0,0 +1,310 @@
+use activitypub_federation::config::Data;
+use axum::{extract, routing, Json, Router};
+use chrono::Utc;
+use sea_orm::{
+    sea_query::OnConflict, ActiveValue, ColumnTrait, EntityTrait, ModelTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, QuerySelect, TransactionTrait,
+};
+use ulid::Ulid;
+use url::Url;
+
+use crate::{
+    ap::{block::Block, undo::Undo},
+    dto::{
+        CreateBlock, CreateDomainBlock, DomainBlock, IdPaginationQuery, TimestampPaginationQuery,
+        User,
+    },
+    entity::{block, domain_block, follow_request, follower, user},
+    error::{Context, Result},
+    format_err,
+    scope::{Resource, Scope},
+    state::State,
+};
+
+use super::auth::Access;
+
+pub(super) fn create_router() -> Router {
+    Router::new()
+        .route("/user", routing::get(get_blocks).post(post_block))
+        .route("/user/:id", routing::delete(delete_block))
+        .route(
+            "/domain",
+            routing::get(get_domain_blocks).post(post_domain_block),
+        )
+        .route("/domain/:domain", routing::delete(delete_domain_block))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/block/user",
+    params(IdPaginationQuery),
+    responses(
+        (status = 200, body = Vec<User>),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn get_blocks(
+    data: Data<State>,
+    access: Access,
+    extract::Query(query): extract::Query<IdPaginationQuery>,
+) -> Result<Json<Vec<User>>> {
+    access.require(Scope::Read(Resource::Blocks))?;
+
+    let pagination_query = block::Entity::find().find_also_related(user::Entity);
+    let pagination_query = if let Some(after) = query.after {
+        pagination_query.filter(user::Column::Id.lt(uuid::Uuid::from(after)))
+    } else {
+        pagination_query
+    };
+    let blocks = pagination_query
+        .order_by_desc(user::Column::Id)
+        .limit(query.size)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    let blocks = blocks
+        .into_iter()
+        .filter_map(|(_, user)| user)
+        .filter_map(|user| User::from_model(user).ok())
+        .collect::<Vec<_>>();
+    Ok(Json(blocks))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/block/user",
+    request_body = CreateBlock,
+    responses(
+        (status = 200),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn post_block(data: Data<State>, access: Access, Json(req): Json<CreateBlock>) -> Result<()> {
+    access.require(Scope::Write(Resource::Blocks))?;
+
+    let tx = data
+        .db
+        .begin()
+        .await
+        .context_internal_server_error("failed to begin database transaction")?;
+
+    let user = user::Entity::find_by_id(req.to_id)
+        .one(&tx)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("user not found")?;
+
+    let existing_count = block::Entity::find_by_id(req.to_id)
+        .count(&tx)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    if existing_count != 0 {
+        return Ok(());
+    }
+
+    let block_activemodel = block::ActiveModel {
+        to_id: ActiveValue::Set(req.to_id.into()),
+        created_at: ActiveValue::Set(Utc::now().fixed_offset()),
+    };
+    block::Entity::insert(block_activemodel)
+        .exec(&tx)
+        .await
+        .context_internal_server_error("failed to insert to database")?;
+
+    follower::Entity::delete_by_id(req.to_id)
+        .exec(&tx)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+    follow_request::Entity::delete_by_id(req.to_id)
+        .exec(&tx)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+
+    tx.commit()
+        .await
+        .context_internal_server_error("failed to commit database transaction")?;
+
+    let inbox =
+        Url::parse(&user.inbox).context_internal_server_error("malformed user inbox URL")?;
+    let block = Block::new(
+        req.to_id,
+        user.uri
+            .parse()
+            .context_internal_server_error("malformed user URI")?,
+    )?;
+    block.send(&data, inbox).await?;
+
+    Ok(())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/block/user/{id}",
+    params(
+        ("id" = String, format = "ulid", description = "user id to unblock"),
+    ),
+    responses(
+        (status = 200),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn delete_block(
+    data: Data<State>,
+    extract::Path(id): extract::Path<Ulid>,
+    access: Access,
+) -> Result<()> {
+    access.require(Scope::Write(Resource::Blocks))?;
+
+    let tx = data
+        .db
+        .begin()
+        .await
+        .context_internal_server_error("failed to begin database transaction")?;
+
+    let existing = block::Entity::find_by_id(id)
+        .find_also_related(user::Entity)
+        .one(&tx)
+        .await
+        .context_internal_server_error("failed to query database")?;
+
+    if let Some((existing, user)) = existing {
+        let user = user.context_internal_server_error("user not found")?;
+
+        ModelTrait::delete(existing, &tx)
+            .await
+            .context_internal_server_error("failed to delete from database")?;
+
+        tx.commit()
+            .await
+            .context_internal_server_error("failed to commit database transaction")?;
+
+        let inbox =
+            Url::parse(&user.inbox).context_internal_server_error("malformed user inbox URL")?;
+        let block = Block::new(
+            id,
+            user.uri
+                .parse()
+                .context_internal_server_error("malformed user URI")?,
+        )?;
+        let undo = Undo::<Block>::new(block)?;
+        undo.send(&data, vec![inbox]).await?;
+    }
+
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/block/domain",
+    params(TimestampPaginationQuery),
+    responses(
+        (status = 200, body = Vec<DomainBlock>),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn get_domain_blocks(
+    data: Data<State>,
+    access: Access,
+    extract::Query(query): extract::Query<TimestampPaginationQuery>,
+) -> Result<Json<Vec<DomainBlock>>> {
+    access.require(Scope::Read(Resource::Blocks))?;
+
+    let pagination_query = domain_block::Entity::find();
+    let pagination_query = if let Some(after) = query.after {
+        pagination_query.filter(domain_block::Column::CreatedAt.lt(after))
+    } else {
+        pagination_query
+    };
+    let domain_blocks = pagination_query
+        .order_by_desc(domain_block::Column::CreatedAt)
+        .limit(query.size)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    let domain_blocks = domain_blocks
+        .into_iter()
+        .map(DomainBlock::from_model)
+        .collect::<Vec<_>>();
+    Ok(Json(domain_blocks))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/block/domain",
+    request_body = CreateDomainBlock,
+    responses(
+        (status = 200),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn post_domain_block(
+    data: Data<State>,
+    access: Access,
+    Json(req): Json<CreateDomainBlock>,
+) -> Result<()> {
+    access.require(Scope::Write(Resource::Blocks))?;
+
+    let domain = req.domain.trim().trim_matches('.').to_lowercase();
+    if domain.is_empty() || domain.contains(['/', ':', '@']) {
+        return Err(format_err!(BAD_REQUEST, "invalid domain"));
+    }
+
+    let domain_block_activemodel = domain_block::ActiveModel {
+        domain: ActiveValue::Set(domain),
+        created_at: ActiveValue::Set(Utc::now().fixed_offset()),
+    };
+    domain_block::Entity::insert(domain_block_activemodel)
+        .on_conflict(
+            OnConflict::column(domain_block::Column::Domain)
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(&*data.db)
+        .await
+        .context_internal_server_error("failed to insert to database")?;
+
+    Ok(())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/block/domain/{domain}",
+    params(
+        ("domain" = String, description = "domain to unblock"),
+    ),
+    responses(
+        (status = 200),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn delete_domain_block(
+    data: Data<State>,
+    extract::Path(domain): extract::Path<String>,
+    access: Access,
+) -> Result<()> {
+    access.require(Scope::Write(Resource::Blocks))?;
+
+    domain_block::Entity::delete_by_id(domain.to_lowercase())
+        .exec(&*data.db)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use activitypub_federation::config::Data;
+use axum::{extract, routing, Json, Router};
+use futures_util::{stream::FuturesOrdered, TryStreamExt};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+
+use crate::{
+    dto::{Bookmark, TimestampPaginationQuery},
+    entity::{bookmark, post},
+    error::{Context, Result},
+    scope::{Resource, Scope},
+    state::State,
+};
+
+use super::auth::Access;
+
+pub(super) fn create_router() -> Router {
+    Router::new().route("/", routing::get(get_bookmarks))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/bookmark",
+    params(TimestampPaginationQuery),
+    responses(
+        (status = 200, body = Vec<Bookmark>),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn get_bookmarks(
+    data: Data<State>,
+    access: Access,
+    extract::Query(query): extract::Query<TimestampPaginationQuery>,
+) -> Result<Json<Vec<Bookmark>>> {
+    access.require(Scope::Read(Resource::Bookmarks))?;
+
+    let pagination_query = bookmark::Entity::find().find_also_related(post::Entity);
+    let pagination_query = if let Some(after) = query.after {
+        pagination_query.filter(bookmark::Column::CreatedAt.lt(after))
+    } else {
+        pagination_query
+    };
+    let bookmarks = pagination_query
+        .order_by_desc(bookmark::Column::CreatedAt)
+        .limit(query.size)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    let bookmarks = bookmarks
+        .into_iter()
+        .filter_map(|(bookmark, post)| Some((bookmark, post?)))
+        .map(|(bookmark, post)| Bookmark::from_model(bookmark, post, &*data.db))
+        .collect::<FuturesOrdered<_>>()
+        .try_collect()
+        .await?;
+    Ok(Json(bookmarks))
+}
@@ -0,0 +1,123 @@
+use activitypub_federation::config::Data;
+use axum::{extract, routing, Json, Router};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+    TransactionTrait,
+};
+use ulid::Ulid;
+
+use crate::{
+    delivery::notify_worker,
+    dto::{Delivery, IdPaginationQuery},
+    entity::{delivery, delivery_inbox},
+    error::{Context, Result},
+    scope::{Resource, Scope},
+    state::State,
+};
+
+use super::auth::Access;
+
+pub(super) fn create_router() -> Router {
+    Router::new()
+        .route("/", routing::get(get_deliveries))
+        .route("/:id/retry", routing::post(post_delivery_retry))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/delivery",
+    params(IdPaginationQuery),
+    responses(
+        (status = 200, body = Vec<Delivery>),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn get_deliveries(
+    data: Data<State>,
+    access: Access,
+    extract::Query(query): extract::Query<IdPaginationQuery>,
+) -> Result<Json<Vec<Delivery>>> {
+    access.require(Scope::Read(Resource::Accounts))?;
+
+    let pagination_query = delivery::Entity::find();
+    let pagination_query = if let Some(after) = query.after {
+        pagination_query.filter(delivery::Column::Id.lt(uuid::Uuid::from(after)))
+    } else {
+        pagination_query
+    };
+    let deliveries = pagination_query
+        .order_by_desc(delivery::Column::Id)
+        .limit(query.size)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    let deliveries = deliveries
+        .into_iter()
+        .filter_map(|delivery| Delivery::from_model(delivery).ok())
+        .collect::<Vec<_>>();
+    Ok(Json(deliveries))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/delivery/{id}/retry",
+    params(
+        ("id" = String, format = "ulid"),
+    ),
+    responses(
+        (status = 200),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn post_delivery_retry(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<Ulid>,
+) -> Result<()> {
+    access.require(Scope::Write(Resource::Accounts))?;
+
+    let tx = data
+        .db
+        .begin()
+        .await
+        .context_internal_server_error("failed to begin database transaction")?;
+
+    let existing = delivery::Entity::find_by_id(id)
+        .one(&tx)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("delivery not found")?;
+
+    // Retrying manually lifts suspension of the inbox
+    delivery_inbox::Entity::delete_by_id(existing.inbox.clone())
+        .exec(&tx)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+
+    let delivery_activemodel = delivery::ActiveModel {
+        id: ActiveValue::Unchanged(existing.id),
+        attempts: ActiveValue::Set(0),
+        next_attempt_at: ActiveValue::Set(Utc::now().fixed_offset()),
+        failed_at: ActiveValue::Set(None),
+        ..Default::default()
+    };
+    delivery_activemodel
+        .update(&tx)
+        .await
+        .context_internal_server_error("failed to update database")?;
+
+    notify_worker(&tx).await?;
+
+    tx.commit()
+        .await
+        .context_internal_server_error("failed to commit database transaction")?;
+
+    Ok(())
+}
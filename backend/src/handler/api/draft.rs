@@ -0,0 +1,337 @@
+use activitypub_federation::config::Data;
+use axum::{extract, routing, Json, Router};
+use chrono::Utc;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ActiveValue, ColumnTrait, DatabaseTransaction, EntityTrait,
+    QueryFilter, QueryOrder, QuerySelect, TransactionTrait,
+};
+use ulid::Ulid;
+
+use crate::{
+    dto::{CreatePost, Draft, IdResponse, TimestampPaginationQuery},
+    entity::{draft, local_file, scheduled_post},
+    error::{Context, Result},
+    format_err,
+    scope::{Resource, Scope},
+    state::State,
+};
+
+use super::{
+    auth::Access,
+    post::{insert_post, prepare_post, send_post, validate_post},
+};
+
+pub(super) fn create_router() -> Router {
+    Router::new()
+        .route("/", routing::get(get_drafts).post(post_draft))
+        .route(
+            "/:id",
+            routing::get(get_draft).put(put_draft).delete(delete_draft),
+        )
+        .route("/:id/publish", routing::post(post_draft_publish))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/draft",
+    params(TimestampPaginationQuery),
+    responses(
+        (status = 200, body = Vec<Draft>),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn get_drafts(
+    data: Data<State>,
+    access: Access,
+    extract::Query(query): extract::Query<TimestampPaginationQuery>,
+) -> Result<Json<Vec<Draft>>> {
+    access.require(Scope::Read(Resource::Statuses))?;
+
+    let pagination_query = draft::Entity::find();
+    let pagination_query = if let Some(after) = query.after {
+        pagination_query.filter(draft::Column::UpdatedAt.lt(after))
+    } else {
+        pagination_query
+    };
+    let drafts = pagination_query
+        .order_by_desc(draft::Column::UpdatedAt)
+        .limit(query.size)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    let drafts = drafts
+        .into_iter()
+        .map(Draft::from_model)
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Json(drafts))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/draft",
+    request_body = CreatePost,
+    responses(
+        (status = 200, body = IdResponse),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access, req))]
+async fn post_draft(
+    data: Data<State>,
+    access: Access,
+    Json(req): Json<CreatePost>,
+) -> Result<Json<IdResponse>> {
+    access.require(Scope::Write(Resource::Statuses))?;
+
+    let request =
+        serde_json::to_value(&req).context_internal_server_error("failed to serialize draft")?;
+
+    let tx = data
+        .db
+        .begin()
+        .await
+        .context_internal_server_error("failed to begin database transaction")?;
+
+    let id = Ulid::new();
+    let now = Utc::now().fixed_offset();
+    let draft_activemodel = draft::ActiveModel {
+        id: ActiveValue::Set(id.into()),
+        request: ActiveValue::Set(request),
+        created_at: ActiveValue::Set(now),
+        updated_at: ActiveValue::Set(now),
+    };
+    draft_activemodel
+        .insert(&tx)
+        .await
+        .context_internal_server_error("failed to insert to database")?;
+    attach_files(id, &req.files, &tx).await?;
+
+    tx.commit()
+        .await
+        .context_internal_server_error("failed to commit database transaction")?;
+
+    Ok(Json(IdResponse { id }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/draft/{id}",
+    params(
+        ("id" = String, format = "ulid"),
+    ),
+    responses(
+        (status = 200, body = Draft),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn get_draft(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<Ulid>,
+) -> Result<Json<Draft>> {
+    access.require(Scope::Read(Resource::Statuses))?;
+
+    let draft = draft::Entity::find_by_id(id)
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("draft not found")?;
+    Ok(Json(Draft::from_model(draft)?))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/draft/{id}",
+    params(
+        ("id" = String, format = "ulid"),
+    ),
+    request_body = CreatePost,
+    responses(
+        (status = 200, body = Draft),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access, req))]
+async fn put_draft(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<Ulid>,
+    Json(req): Json<CreatePost>,
+) -> Result<Json<Draft>> {
+    access.require(Scope::Write(Resource::Statuses))?;
+
+    let request =
+        serde_json::to_value(&req).context_internal_server_error("failed to serialize draft")?;
+
+    let tx = data
+        .db
+        .begin()
+        .await
+        .context_internal_server_error("failed to begin database transaction")?;
+
+    let draft = draft::Entity::find_by_id(id)
+        .one(&tx)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("draft not found")?;
+
+    let draft_activemodel = draft::ActiveModel {
+        id: ActiveValue::Unchanged(draft.id),
+        request: ActiveValue::Set(request),
+        updated_at: ActiveValue::Set(Utc::now().fixed_offset()),
+        ..Default::default()
+    };
+    let draft = draft_activemodel
+        .update(&tx)
+        .await
+        .context_internal_server_error("failed to update database")?;
+
+    local_file::Entity::update_many()
+        .filter(local_file::Column::DraftId.eq(draft.id))
+        .col_expr(
+            local_file::Column::DraftId,
+            Expr::value(Option::<uuid::Uuid>::None),
+        )
+        .exec(&tx)
+        .await
+        .context_internal_server_error("failed to update database")?;
+    attach_files(id, &req.files, &tx).await?;
+
+    tx.commit()
+        .await
+        .context_internal_server_error("failed to commit database transaction")?;
+
+    Ok(Json(Draft::from_model(draft)?))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/draft/{id}",
+    params(
+        ("id" = String, format = "ulid"),
+    ),
+    responses(
+        (status = 200),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn delete_draft(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<Ulid>,
+) -> Result<()> {
+    access.require(Scope::Write(Resource::Statuses))?;
+
+    let res = draft::Entity::delete_by_id(uuid::Uuid::from(id))
+        .exec(&*data.db)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+    if res.rows_affected == 0 {
+        return Err(format_err!(NOT_FOUND, "draft not found"));
+    }
+
+    Ok(())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/draft/{id}/publish",
+    params(
+        ("id" = String, format = "ulid"),
+    ),
+    responses(
+        (status = 200, body = IdResponse),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn post_draft_publish(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<Ulid>,
+) -> Result<Json<IdResponse>> {
+    access.require(Scope::Write(Resource::Statuses))?;
+
+    let draft = draft::Entity::find_by_id(id)
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("draft not found")?;
+    let req = serde_json::from_value::<CreatePost>(draft.request)
+        .context_internal_server_error("malformed draft")?;
+    validate_post(&req)?;
+
+    if let Some(scheduled_at) = req.scheduled_at {
+        let tx = data
+            .db
+            .begin()
+            .await
+            .context_internal_server_error("failed to begin database transaction")?;
+        remove_draft(id, &tx).await?;
+        let id = scheduled_post::Model::schedule(scheduled_at, req, &tx).await?;
+        tx.commit()
+            .await
+            .context_internal_server_error("failed to commit database transaction")?;
+
+        return Ok(Json(IdResponse { id }));
+    }
+
+    let prepared = prepare_post(req, &data).await?;
+
+    let tx = data
+        .db
+        .begin()
+        .await
+        .context_internal_server_error("failed to begin database transaction")?;
+    remove_draft(id, &tx).await?;
+    let (post, mentions) = insert_post(prepared, &tx).await?;
+    tx.commit()
+        .await
+        .context_internal_server_error("failed to commit database transaction")?;
+
+    let id = post.id.into();
+    send_post(post, mentions, &data).await?;
+
+    Ok(Json(IdResponse { id }))
+}
+
+/// Deletes the draft being published, failing if it has already been published or deleted.
+async fn remove_draft(id: Ulid, tx: &DatabaseTransaction) -> Result<()> {
+    let res = draft::Entity::delete_by_id(uuid::Uuid::from(id))
+        .exec(tx)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+    if res.rows_affected == 0 {
+        return Err(format_err!(CONFLICT, "draft has already been published"));
+    }
+
+    Ok(())
+}
+
+async fn attach_files(id: Ulid, files: &[Ulid], tx: &DatabaseTransaction) -> Result<()> {
+    for local_file_id in files {
+        let file = local_file::Entity::find_by_id(*local_file_id)
+            .one(tx)
+            .await
+            .context_internal_server_error("failed to query database")?
+            .context_not_found("file not found")?;
+        file.attach_to_draft(id, tx).await?;
+    }
+
+    Ok(())
+}
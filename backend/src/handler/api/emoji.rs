@@ -12,6 +12,7 @@ use crate::{
     entity::{emoji, local_file},
     error::{Context, Result},
     format_err,
+    scope::{Resource, Scope},
     state::State,
 };
 
@@ -34,12 +35,14 @@ pub(super) fn create_router() -> Router {
         ("access_key" = []),
     ),
 )]
-#[tracing::instrument(skip(data, _access))]
+#[tracing::instrument(skip(data, access))]
 async fn get_emojis(
     data: Data<State>,
-    _access: Access,
+    access: Access,
     extract::Query(query): extract::Query<TimestampPaginationQuery>,
 ) -> Result<Json<Vec<LocalEmoji>>> {
+    access.require(Scope::Read(Resource::Media))?;
+
     let pagination_query = emoji::Entity::find();
     let pagination_query = if let Some(after) = query.after {
         pagination_query.filter(emoji::Column::CreatedAt.lt(after))
@@ -72,12 +75,14 @@ async fn get_emojis(
         ("access_key" = []),
     ),
 )]
-#[tracing::instrument(skip(data, _access))]
+#[tracing::instrument(skip(data, access))]
 async fn post_emoji(
     data: Data<State>,
-    _access: Access,
+    access: Access,
     Json(req): Json<CreateEmoji>,
 ) -> Result<Json<NameResponse>> {
+    access.require(Scope::Write(Resource::Media))?;
+
     let tx = data
         .db
         .begin()
@@ -131,12 +136,14 @@ async fn post_emoji(
         ("access_key" = []),
     ),
 )]
-#[tracing::instrument(skip(data, _access))]
+#[tracing::instrument(skip(data, access))]
 async fn get_emoji(
     data: Data<State>,
-    _access: Access,
+    access: Access,
     extract::Path(name): extract::Path<String>,
 ) -> Result<Json<LocalEmoji>> {
+    access.require(Scope::Read(Resource::Media))?;
+
     let (emoji, file) = emoji::Entity::find_by_id(name)
         .find_also_related(local_file::Entity)
         .order_by_desc(emoji::Column::CreatedAt)
@@ -161,12 +168,14 @@ async fn get_emoji(
         ("access_key" = []),
     ),
 )]
-#[tracing::instrument(skip(data, _access))]
+#[tracing::instrument(skip(data, access))]
 async fn delete_emoji(
     data: Data<State>,
-    _access: Access,
+    access: Access,
     extract::Path(name): extract::Path<String>,
 ) -> Result<()> {
+    access.require(Scope::Write(Resource::Media))?;
+
     let tx = data
         .db
         .begin()
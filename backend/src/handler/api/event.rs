@@ -7,7 +7,12 @@ use axum::{
 };
 use futures_util::Stream;
 
-use crate::{error::Error, queue::event_stream, state::State};
+use crate::{
+    error::Error,
+    queue::event_stream,
+    scope::{Resource, Scope},
+    state::State,
+};
 
 use super::auth::Access;
 
@@ -25,11 +30,13 @@ pub(super) fn create_router() -> Router {
         ("access_key" = []),
     ),
 )]
-#[tracing::instrument(skip(data, _access))]
+#[tracing::instrument(skip(data, access))]
 async fn get_event_stream(
     data: Data<State>,
-    _access: Access,
+    access: Access,
 ) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, Error> {
-    let stream = event_stream(data.pg_listener().await?).await?;
+    access.require(Scope::Read(Resource::Notifications))?;
+
+    let stream = event_stream(data.pg_listener().await?, data.db.clone()).await?;
     Ok(Sse::new(data.stopper.stop_stream(stream)))
 }
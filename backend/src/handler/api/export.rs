@@ -0,0 +1,201 @@
+use activitypub_federation::config::Data;
+use axum::{http::header, routing, Router};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+
+use crate::{
+    entity::{block, bookmark, follow, follower, mute, post, user},
+    error::{Context, Result},
+    scope::{Resource, Scope},
+    state::State,
+};
+
+use super::auth::Access;
+
+type CsvResponse = ([(header::HeaderName, &'static str); 2], String);
+
+pub(super) fn create_router() -> Router {
+    Router::new()
+        .route("/following", routing::get(get_export_following))
+        .route("/followers", routing::get(get_export_followers))
+        .route("/blocks", routing::get(get_export_blocks))
+        .route("/mutes", routing::get(get_export_mutes))
+        .route("/bookmarks", routing::get(get_export_bookmarks))
+}
+
+fn csv_response(file_name: &'static str, body: String) -> CsvResponse {
+    (
+        [
+            (header::CONTENT_TYPE, "text/csv"),
+            (header::CONTENT_DISPOSITION, file_name),
+        ],
+        body,
+    )
+}
+
+fn account_address(user: &user::Model) -> String {
+    format!("{}@{}", user.handle, user.host)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/export/following",
+    responses(
+        (status = 200, body = String, content_type = "text/csv"),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn get_export_following(data: Data<State>, access: Access) -> Result<CsvResponse> {
+    access.require(Scope::Read(Resource::Follows))?;
+
+    let follows = follow::Entity::find()
+        .find_also_related(user::Entity)
+        .order_by_asc(user::Column::Id)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+
+    let mut body = String::from("Account address,Show boosts,Notify on new posts,Languages\n");
+    for user in follows.into_iter().filter_map(|(_, user)| user) {
+        body.push_str(&account_address(&user));
+        body.push_str(",true,false,\n");
+    }
+    Ok(csv_response(
+        r#"attachment; filename="following_accounts.csv""#,
+        body,
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/export/followers",
+    responses(
+        (status = 200, body = String, content_type = "text/csv"),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn get_export_followers(data: Data<State>, access: Access) -> Result<CsvResponse> {
+    access.require(Scope::Read(Resource::Follows))?;
+
+    let followers = follower::Entity::find()
+        .find_also_related(user::Entity)
+        .order_by_asc(user::Column::Id)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+
+    let mut body = String::from("Account address\n");
+    for user in followers.into_iter().filter_map(|(_, user)| user) {
+        body.push_str(&account_address(&user));
+        body.push('\n');
+    }
+    Ok(csv_response(
+        r#"attachment; filename="followers_accounts.csv""#,
+        body,
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/export/blocks",
+    responses(
+        (status = 200, body = String, content_type = "text/csv"),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn get_export_blocks(data: Data<State>, access: Access) -> Result<CsvResponse> {
+    access.require(Scope::Read(Resource::Blocks))?;
+
+    let blocks = block::Entity::find()
+        .find_also_related(user::Entity)
+        .order_by_asc(block::Column::CreatedAt)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+
+    // Mastodon exports blocks without a header
+    let mut body = String::new();
+    for user in blocks.into_iter().filter_map(|(_, user)| user) {
+        body.push_str(&account_address(&user));
+        body.push('\n');
+    }
+    Ok(csv_response(
+        r#"attachment; filename="blocked_accounts.csv""#,
+        body,
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/export/mutes",
+    responses(
+        (status = 200, body = String, content_type = "text/csv"),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn get_export_mutes(data: Data<State>, access: Access) -> Result<CsvResponse> {
+    access.require(Scope::Read(Resource::Mutes))?;
+
+    let mutes = mute::Entity::find()
+        .filter(mute::Column::UserId.is_not_null())
+        .find_also_related(user::Entity)
+        .order_by_asc(mute::Column::Id)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+
+    // Muted users never notify, so notifications are always hidden
+    let mut body = String::from("Account address,Hide notifications\n");
+    for user in mutes.into_iter().filter_map(|(_, user)| user) {
+        body.push_str(&account_address(&user));
+        body.push_str(",true\n");
+    }
+    Ok(csv_response(
+        r#"attachment; filename="muted_accounts.csv""#,
+        body,
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/export/bookmarks",
+    responses(
+        (status = 200, body = String, content_type = "text/csv"),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn get_export_bookmarks(data: Data<State>, access: Access) -> Result<CsvResponse> {
+    access.require(Scope::Read(Resource::Bookmarks))?;
+
+    let bookmarks = bookmark::Entity::find()
+        .find_also_related(post::Entity)
+        .order_by_asc(bookmark::Column::CreatedAt)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+
+    // Mastodon exports bookmarks without a header
+    let mut body = String::new();
+    for post in bookmarks.into_iter().filter_map(|(_, post)| post) {
+        body.push_str(&post.uri);
+        body.push('\n');
+    }
+    Ok(csv_response(
+        r#"attachment; filename="bookmarks.csv""#,
+        body,
+    ))
+}
@@ -8,6 +8,7 @@ use crate::{
     entity::local_file,
     error::{Context, Result},
     format_err,
+    scope::{Resource, Scope},
     state::State,
 };
 
@@ -30,12 +31,14 @@ pub(super) fn create_router() -> Router {
         ("access_key" = []),
     ),
 )]
-#[tracing::instrument(skip(data, _access))]
+#[tracing::instrument(skip(data, access))]
 async fn get_files(
     data: Data<State>,
-    _access: Access,
+    access: Access,
     extract::Query(query): extract::Query<IdPaginationQuery>,
 ) -> Result<Json<Vec<LocalFile>>> {
+    access.require(Scope::Read(Resource::Media))?;
+
     let pagination_query = local_file::Entity::find();
     let pagination_query = if let Some(after) = query.after {
         pagination_query.filter(local_file::Column::Id.lt(uuid::Uuid::from(after)))
@@ -66,13 +69,15 @@ async fn get_files(
         ("access_key" = []),
     ),
 )]
-#[tracing::instrument(skip(data, _access, req))]
+#[tracing::instrument(skip(data, access, req))]
 async fn post_file(
     data: Data<State>,
-    _access: Access,
+    access: Access,
     extract::Query(query): extract::Query<CreateFileQuery>,
     req: Bytes,
 ) -> Result<Json<IdResponse>> {
+    access.require(Scope::Write(Resource::Media))?;
+
     let file = local_file::Model::put(req, query.media_type, query.alt, &*data.db).await?;
     Ok(Json(IdResponse { id: file.id.into() }))
 }
@@ -90,12 +95,14 @@ async fn post_file(
         ("access_key" = []),
     ),
 )]
-#[tracing::instrument(skip(data, _access))]
+#[tracing::instrument(skip(data, access))]
 async fn get_file(
     data: Data<State>,
-    _access: Access,
+    access: Access,
     extract::Path(id): extract::Path<Ulid>,
 ) -> Result<Json<LocalFile>> {
+    access.require(Scope::Read(Resource::Media))?;
+
     let file = local_file::Entity::find_by_id(id)
         .order_by_desc(local_file::Column::Id)
         .one(&*data.db)
@@ -118,12 +125,14 @@ async fn get_file(
         ("access_key" = []),
     ),
 )]
-#[tracing::instrument(skip(data, _access))]
+#[tracing::instrument(skip(data, access))]
 async fn delete_file(
     data: Data<State>,
-    _access: Access,
+    access: Access,
     extract::Path(id): extract::Path<Ulid>,
 ) -> Result<()> {
+    access.require(Scope::Write(Resource::Media))?;
+
     let tx = data
         .db
         .begin()
@@ -136,7 +145,10 @@ async fn delete_file(
         .context_internal_server_error("failed to query database")?;
 
     if let Some(existing) = existing {
-        if existing.post_id.is_some() || existing.emoji_name.is_some() {
+        if existing.post_id.is_some()
+            || existing.emoji_name.is_some()
+            || existing.draft_id.is_some()
+        {
             return Err(format_err!(
                 BAD_REQUEST,
                 "cannot delete file currently in use"
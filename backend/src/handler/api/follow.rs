@@ -13,6 +13,7 @@ use crate::{
     entity::{follow, user},
     error::{Context, Result},
     format_err,
+    scope::{Resource, Scope},
     state::State,
 };
 
@@ -37,9 +38,11 @@ pub(super) fn create_router() -> Router {
 )]
 async fn get_follows(
     data: Data<State>,
-    _access: Access,
+    access: Access,
     extract::Query(query): extract::Query<IdPaginationQuery>,
 ) -> Result<Json<Vec<DtoFollow>>> {
+    access.require(Scope::Read(Resource::Follows))?;
+
     let pagination_query = follow::Entity::find().find_also_related(user::Entity);
     let pagination_query = if let Some(after) = query.after {
         pagination_query.filter(user::Column::Id.lt(uuid::Uuid::from(after)))
@@ -71,12 +74,14 @@ async fn get_follows(
         ("access_key" = []),
     ),
 )]
-#[tracing::instrument(skip(data, _access))]
-async fn post_follow(
+#[tracing::instrument(skip(data, access))]
+pub(super) async fn post_follow(
     data: Data<State>,
-    _access: Access,
+    access: Access,
     Json(req): Json<CreateFollow>,
 ) -> Result<()> {
+    access.require(Scope::Write(Resource::Follows))?;
+
     let tx = data
         .db
         .begin()
@@ -133,12 +138,14 @@ async fn post_follow(
         ("access_key" = []),
     ),
 )]
-#[tracing::instrument(skip(data, _access))]
-async fn delete_follow(
+#[tracing::instrument(skip(data, access))]
+pub(super) async fn delete_follow(
     data: Data<State>,
     extract::Path(id): extract::Path<Ulid>,
-    _access: Access,
+    access: Access,
 ) -> Result<()> {
+    access.require(Scope::Write(Resource::Follows))?;
+
     let tx = data
         .db
         .begin()
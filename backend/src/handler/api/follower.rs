@@ -1,15 +1,21 @@
 use activitypub_federation::config::Data;
 use axum::{extract, routing, Json, Router};
 use sea_orm::{
-    ColumnTrait, EntityTrait, ModelTrait, QueryFilter, QueryOrder, QuerySelect, TransactionTrait,
+    ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, ModelTrait, QueryFilter, QueryOrder,
+    QuerySelect, TransactionTrait,
 };
 use ulid::Ulid;
 
 use crate::{
-    ap::follow::FollowReject,
-    dto::{IdPaginationQuery, User},
-    entity::{follower, user},
+    ap::{
+        follow::{Follow, FollowAccept, FollowReject},
+        person::LocalPerson,
+    },
+    dto::{FollowRequest, IdPaginationQuery, TimestampPaginationQuery, User},
+    entity::{follow_request, follower, user},
     error::{Context, Result},
+    queue::{Event, Notification, NotificationType},
+    scope::{Resource, Scope},
     state::State,
 };
 
@@ -19,6 +25,15 @@ pub(super) fn create_router() -> Router {
     Router::new()
         .route("/", routing::get(get_followers))
         .route("/:id", routing::delete(delete_follower))
+        .route("/request", routing::get(get_follow_requests))
+        .route(
+            "/request/:id/accept",
+            routing::post(post_follow_request_accept),
+        )
+        .route(
+            "/request/:id/reject",
+            routing::post(post_follow_request_reject),
+        )
 }
 
 #[utoipa::path(
@@ -32,12 +47,14 @@ pub(super) fn create_router() -> Router {
         ("access_key" = []),
     ),
 )]
-#[tracing::instrument(skip(data, _access))]
+#[tracing::instrument(skip(data, access))]
 async fn get_followers(
     data: Data<State>,
-    _access: Access,
+    access: Access,
     extract::Query(query): extract::Query<IdPaginationQuery>,
 ) -> Result<Json<Vec<User>>> {
+    access.require(Scope::Read(Resource::Follows))?;
+
     let pagination_query = follower::Entity::find().find_also_related(user::Entity);
     let pagination_query = if let Some(after) = query.after {
         pagination_query.filter(user::Column::Id.lt(uuid::Uuid::from(after)))
@@ -71,12 +88,14 @@ async fn get_followers(
         ("access_key" = []),
     ),
 )]
-#[tracing::instrument(skip(data, _access))]
+#[tracing::instrument(skip(data, access))]
 async fn delete_follower(
     data: Data<State>,
     extract::Path(id): extract::Path<Ulid>,
-    _access: Access,
+    access: Access,
 ) -> Result<()> {
+    access.require(Scope::Write(Resource::Follows))?;
+
     let tx = data
         .db
         .begin()
@@ -91,6 +110,11 @@ async fn delete_follower(
         .context_bad_request("follower not found")?;
     let user = user.context_internal_server_error("user not found")?;
 
+    let follow_uri = follower
+        .uri
+        .parse()
+        .context_internal_server_error("malformed follower URI")?;
+
     follower
         .delete(&tx)
         .await
@@ -100,12 +124,201 @@ async fn delete_follower(
         .await
         .context_internal_server_error("failed to commit database transaction")?;
 
-    let reject = FollowReject::new(
-        user.id.into(),
-        user.uri
+    let reject = FollowReject::new(Follow {
+        ty: Default::default(),
+        id: Some(follow_uri),
+        actor: user
+            .uri
+            .parse()
+            .context_internal_server_error("malformed user URI")?,
+        object: LocalPerson::id(),
+    })?;
+    reject
+        .send(
+            &data,
+            user.inbox
+                .parse()
+                .context_internal_server_error("malformed user inbox URL")?,
+        )
+        .await?;
+
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/follower/request",
+    params(TimestampPaginationQuery),
+    responses(
+        (status = 200, body = Vec<FollowRequest>),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn get_follow_requests(
+    data: Data<State>,
+    access: Access,
+    extract::Query(query): extract::Query<TimestampPaginationQuery>,
+) -> Result<Json<Vec<FollowRequest>>> {
+    access.require(Scope::Read(Resource::Follows))?;
+
+    let pagination_query = follow_request::Entity::find().find_also_related(user::Entity);
+    let pagination_query = if let Some(after) = query.after {
+        pagination_query.filter(follow_request::Column::CreatedAt.lt(after))
+    } else {
+        pagination_query
+    };
+    let follow_requests = pagination_query
+        .order_by_desc(follow_request::Column::CreatedAt)
+        .limit(query.size)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    let follow_requests = follow_requests
+        .into_iter()
+        .filter_map(|(follow_request, user)| user.map(|user| (follow_request, user)))
+        .filter_map(|(follow_request, user)| FollowRequest::from_model(follow_request, user).ok())
+        .collect::<Vec<_>>();
+    Ok(Json(follow_requests))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/follower/request/{id}/accept",
+    params(
+        ("id" = String, format = "ulid", description = "user id of follow request"),
+    ),
+    responses(
+        (status = 200),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn post_follow_request_accept(
+    data: Data<State>,
+    extract::Path(id): extract::Path<Ulid>,
+    access: Access,
+) -> Result<()> {
+    access.require(Scope::Write(Resource::Follows))?;
+
+    let tx = data
+        .db
+        .begin()
+        .await
+        .context_internal_server_error("failed to begin database transaction")?;
+
+    let (follow_request, user) = follow_request::Entity::find_by_id(id)
+        .find_also_related(user::Entity)
+        .one(&tx)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("follow request not found")?;
+    let user = user.context_internal_server_error("user not found")?;
+
+    let follower_activemodel = follower::ActiveModel {
+        from_id: ActiveValue::Set(follow_request.from_id),
+        uri: ActiveValue::Set(follow_request.uri.clone()),
+    };
+    follower_activemodel
+        .insert(&tx)
+        .await
+        .context_internal_server_error("failed to insert to database")?;
+
+    let follow_uri = follow_request
+        .uri
+        .parse()
+        .context_internal_server_error("malformed follow request URI")?;
+
+    follow_request
+        .delete(&tx)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+
+    tx.commit()
+        .await
+        .context_internal_server_error("failed to commit database transaction")?;
+
+    let accept = FollowAccept::new(Follow {
+        ty: Default::default(),
+        id: Some(follow_uri),
+        actor: user
+            .uri
+            .parse()
+            .context_internal_server_error("malformed user URI")?,
+        object: LocalPerson::id(),
+    })?;
+    accept.send(&data).await?;
+
+    let event = Event::Notification(Notification::new(NotificationType::CreateFollower {
+        user_id: user.id.into(),
+    }));
+    event.send(&*data.db).await?;
+
+    Ok(())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/follower/request/{id}/reject",
+    params(
+        ("id" = String, format = "ulid", description = "user id of follow request"),
+    ),
+    responses(
+        (status = 200),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn post_follow_request_reject(
+    data: Data<State>,
+    extract::Path(id): extract::Path<Ulid>,
+    access: Access,
+) -> Result<()> {
+    access.require(Scope::Write(Resource::Follows))?;
+
+    let tx = data
+        .db
+        .begin()
+        .await
+        .context_internal_server_error("failed to begin database transaction")?;
+
+    let (follow_request, user) = follow_request::Entity::find_by_id(id)
+        .find_also_related(user::Entity)
+        .one(&tx)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("follow request not found")?;
+    let user = user.context_internal_server_error("user not found")?;
+
+    let follow_uri = follow_request
+        .uri
+        .parse()
+        .context_internal_server_error("malformed follow request URI")?;
+
+    follow_request
+        .delete(&tx)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+
+    tx.commit()
+        .await
+        .context_internal_server_error("failed to commit database transaction")?;
+
+    let reject = FollowReject::new(Follow {
+        ty: Default::default(),
+        id: Some(follow_uri),
+        actor: user
+            .uri
             .parse()
             .context_internal_server_error("malformed user URI")?,
-    )?;
+        object: LocalPerson::id(),
+    })?;
     reject
         .send(
             &data,
@@ -7,6 +7,8 @@ use crate::{
     dto::{IdPaginationQuery, Post},
     entity::{hashtag, post},
     error::{Context, Result},
+    mute::MuteFilter,
+    scope::{Resource, Scope},
     state::State,
 };
 
@@ -30,14 +32,19 @@ pub(super) fn create_router() -> Router {
         ("access_key" = []),
     ),
 )]
-#[tracing::instrument(skip(data, _access))]
+#[tracing::instrument(skip(data, access))]
 async fn get_hashtag_posts(
     data: Data<State>,
-    _access: Access,
+    access: Access,
     extract::Path(name): extract::Path<String>,
     extract::Query(query): extract::Query<IdPaginationQuery>,
 ) -> Result<Json<Vec<Post>>> {
-    let pagination_query = hashtag::Entity::find().find_also_related(post::Entity);
+    access.require(Scope::Read(Resource::Statuses))?;
+
+    let mute_filter = MuteFilter::load(&*data.db).await?;
+    let pagination_query = hashtag::Entity::find()
+        .find_also_related(post::Entity)
+        .filter(mute_filter.post_condition());
     let pagination_query = if let Some(after) = query.after {
         pagination_query.filter(post::Column::Id.lt(uuid::Uuid::from(after)))
     } else {
@@ -0,0 +1,143 @@
+use activitypub_federation::config::Data;
+use axum::{extract, routing, Json, Router};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+};
+use serde::Deserialize;
+use ulid::Ulid;
+use utoipa::IntoParams;
+
+use crate::{
+    dto::{IdPaginationQuery, IdResponse, Import, ImportType},
+    entity::import,
+    error::{Context, Result},
+    format_err,
+    import::parse_csv,
+    scope::{Resource, Scope},
+    state::State,
+};
+
+use super::auth::Access;
+
+pub(super) fn create_router() -> Router {
+    Router::new()
+        .route("/", routing::get(get_imports).post(post_import))
+        .route("/:id", routing::get(get_import))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/import",
+    params(IdPaginationQuery),
+    responses(
+        (status = 200, body = Vec<Import>),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn get_imports(
+    data: Data<State>,
+    access: Access,
+    extract::Query(query): extract::Query<IdPaginationQuery>,
+) -> Result<Json<Vec<Import>>> {
+    access.require(Scope::Read(Resource::Accounts))?;
+
+    let pagination_query = import::Entity::find();
+    let pagination_query = if let Some(after) = query.after {
+        pagination_query.filter(import::Column::Id.lt(uuid::Uuid::from(after)))
+    } else {
+        pagination_query
+    };
+    let imports = pagination_query
+        .order_by_desc(import::Column::Id)
+        .limit(query.size)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    let imports = imports.into_iter().map(Import::from_model).collect();
+    Ok(Json(imports))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+struct PostImportQuery {
+    #[serde(rename = "type")]
+    #[param(rename = "type", value_type = ImportType)]
+    ty: ImportType,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/import",
+    params(PostImportQuery),
+    request_body(content = String, content_type = "text/csv", description = "CSV exported from Mastodon"),
+    responses(
+        (status = 200, body = IdResponse),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access, body))]
+async fn post_import(
+    data: Data<State>,
+    access: Access,
+    extract::Query(query): extract::Query<PostImportQuery>,
+    body: String,
+) -> Result<Json<IdResponse>> {
+    access.require(Scope::Write(Resource::Accounts))?;
+
+    let entries = parse_csv(&body);
+    if entries.is_empty() {
+        return Err(format_err!(BAD_REQUEST, "no entries found"));
+    }
+
+    let id = Ulid::new();
+    let import_activemodel = import::ActiveModel {
+        id: ActiveValue::Set(id.into()),
+        r#type: ActiveValue::Set(query.ty.into()),
+        entries: ActiveValue::Set(entries),
+        processed_count: ActiveValue::Set(0),
+        failed_count: ActiveValue::Set(0),
+        created_at: ActiveValue::Set(Utc::now().fixed_offset()),
+        finished_at: ActiveValue::Set(None),
+    };
+    import_activemodel
+        .insert(&*data.db)
+        .await
+        .context_internal_server_error("failed to insert to database")?;
+
+    Ok(Json(IdResponse { id }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/import/{id}",
+    params(
+        ("id" = String, format = "ulid"),
+    ),
+    responses(
+        (status = 200, body = Import),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn get_import(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<Ulid>,
+) -> Result<Json<Import>> {
+    access.require(Scope::Read(Resource::Accounts))?;
+
+    let import = import::Entity::find_by_id(id)
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("import not found")?;
+    Ok(Json(Import::from_model(import)))
+}
@@ -0,0 +1,313 @@
+//! Subset of the Mastodon client API, mapped onto our own entities.
+//!
+//! Only the single local account exists, identified by the ID of the setting.
+
+use activitypub_federation::config::Data;
+use async_trait::async_trait;
+use axum::{
+    body::{Bytes, HttpBody},
+    extract::FromRequest,
+    http::{header, HeaderMap, HeaderValue, Request, Uri},
+    routing, BoxError, Router,
+};
+use futures_util::{stream::FuturesOrdered, TryStreamExt};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Select};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer};
+use serde_json::{Map, Value};
+use url::form_urlencoded;
+
+use crate::{
+    config::CONFIG,
+    dto::{
+        mastodon::{Account, MastodonId, Status},
+        Post,
+    },
+    entity::post,
+    error::{Context, Error, Result},
+    format_err,
+    state::State,
+};
+
+pub mod account;
+pub mod app;
+pub mod instance;
+pub mod media;
+pub mod notification;
+pub mod status;
+pub mod streaming;
+pub mod timeline;
+
+pub(super) fn create_v1_router() -> Router {
+    let accounts = self::account::create_router();
+    let apps = self::app::create_router();
+    let media = self::media::create_router();
+    let notifications = self::notification::create_router();
+    let statuses = self::status::create_router();
+    let streaming = self::streaming::create_router();
+    let timelines = self::timeline::create_router();
+
+    Router::new()
+        .nest("/accounts", accounts)
+        .nest("/apps", apps)
+        .route("/instance", routing::get(self::instance::get_instance_v1))
+        .nest("/media", media)
+        .nest("/notifications", notifications)
+        .route(
+            "/polls/:id/votes",
+            routing::post(self::status::post_poll_votes),
+        )
+        .nest("/statuses", statuses)
+        .nest("/streaming", streaming)
+        .nest("/timelines", timelines)
+}
+
+pub(super) fn create_v2_router() -> Router {
+    Router::new()
+        .route("/instance", routing::get(self::instance::get_instance_v2))
+        .route("/media", routing::post(self::media::post_media))
+}
+
+fn default_limit() -> u64 {
+    20
+}
+
+/// Pagination by `max_id`, `since_id` and `min_id`, returning the newest first.
+#[derive(Debug, Deserialize)]
+pub(super) struct PaginationQuery {
+    max_id: Option<MastodonId>,
+    since_id: Option<MastodonId>,
+    min_id: Option<MastodonId>,
+    #[serde(default = "default_limit")]
+    limit: u64,
+}
+
+impl PaginationQuery {
+    /// Queries a page of `select`, paginated by `column` holding ULIDs.
+    pub(super) async fn fetch<E>(
+        &self,
+        select: Select<E>,
+        column: E::Column,
+        data: &Data<State>,
+    ) -> Result<Vec<E::Model>>
+    where
+        E: EntityTrait,
+    {
+        let select = if let Some(max_id) = self.max_id {
+            select.filter(column.lt(uuid::Uuid::from(max_id.0)))
+        } else {
+            select
+        };
+        let select = if let Some(since_id) = self.since_id {
+            select.filter(column.gt(uuid::Uuid::from(since_id.0)))
+        } else {
+            select
+        };
+        let limit = self.limit.clamp(1, 40);
+
+        // `min_id` asks for the page right after it, so it is queried in ascending order
+        if let Some(min_id) = self.min_id {
+            let mut models = select
+                .filter(column.gt(uuid::Uuid::from(min_id.0)))
+                .order_by_asc(column)
+                .limit(limit)
+                .all(&*data.db)
+                .await
+                .context_internal_server_error("failed to query database")?;
+            models.reverse();
+            Ok(models)
+        } else {
+            select
+                .order_by_desc(column)
+                .limit(limit)
+                .all(&*data.db)
+                .await
+                .context_internal_server_error("failed to query database")
+        }
+    }
+}
+
+/// Builds the `Link` header pointing to the pages around the one from `newest` to `oldest`.
+pub(super) fn link_header(
+    uri: &Uri,
+    newest: Option<MastodonId>,
+    oldest: Option<MastodonId>,
+) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let (Some(newest), Some(oldest)) = (newest, oldest) else {
+        return headers;
+    };
+
+    let params = form_urlencoded::parse(uri.query().unwrap_or_default().as_bytes())
+        .filter(|(key, _)| !matches!(key.as_ref(), "max_id" | "since_id" | "min_id"))
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect::<Vec<_>>();
+    let page_url = |key: &str, id: MastodonId| {
+        let query = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(&params)
+            .append_pair(key, &id.to_string())
+            .finish();
+        format!("https://{}{}?{}", CONFIG.public_domain, uri.path(), query)
+    };
+    let link = format!(
+        r#"<{}>; rel="next", <{}>; rel="prev""#,
+        page_url("max_id", oldest),
+        page_url("min_id", newest)
+    );
+    if let Ok(link) = HeaderValue::from_str(&link) {
+        headers.insert(header::LINK, link);
+    }
+    headers
+}
+
+/// Collects the values of `key[]` (or plain `key`) from `query`, which `Query` cannot parse.
+pub(super) fn query_array(query: Option<&str>, key: &str) -> Vec<String> {
+    let array_key = format!("{}[]", key);
+    form_urlencoded::parse(query.unwrap_or_default().as_bytes())
+        .filter(|(k, _)| *k == key || *k == array_key)
+        .map(|(_, value)| value.into_owned())
+        .collect()
+}
+
+/// Converts posts into statuses, all of which see the local account as `me`.
+pub(super) async fn statuses_from_models(
+    posts: Vec<post::Model>,
+    data: &Data<State>,
+) -> Result<Vec<Status>> {
+    let me = Account::local(&*data.db).await?;
+    let posts: Vec<Post> = posts
+        .into_iter()
+        .map(|post| Post::from_model(post, &*data.db))
+        .collect::<FuturesOrdered<_>>()
+        .try_collect()
+        .await?;
+    Status::from_posts(posts, &me, &*data.db).await
+}
+
+/// Maximum nesting of `key[...]` form fields, the same as the recursion limit of `serde_json`
+const MAX_FORM_FIELD_DEPTH: usize = 128;
+
+/// Request body sent either as JSON or as a form, the latter with `key[]` for arrays and
+/// `key[field]` for objects.
+pub(crate) struct JsonOrForm<T>(pub T);
+
+#[async_trait]
+impl<S, B, T> FromRequest<S, B> for JsonOrForm<T>
+where
+    T: DeserializeOwned,
+    B: HttpBody + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request(req: Request<B>, state: &S) -> Result<Self> {
+        let is_form = req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .map(|content_type| content_type.starts_with("application/x-www-form-urlencoded"))
+            .unwrap_or(false);
+        let body = Bytes::from_request(req, state)
+            .await
+            .context_bad_request("failed to read request body")?;
+
+        let value = if is_form {
+            let mut value = Value::Object(Map::new());
+            for (key, field) in form_urlencoded::parse(&body) {
+                let segments = key
+                    .split('[')
+                    .map(|segment| segment.trim_end_matches(']'))
+                    .collect::<Vec<_>>();
+                if segments.len() > MAX_FORM_FIELD_DEPTH {
+                    return Err(format_err!(BAD_REQUEST, "form field is nested too deeply"));
+                }
+                insert_form_field(&mut value, &segments, field.into_owned());
+            }
+            value
+        } else if body.is_empty() {
+            Value::Object(Map::new())
+        } else {
+            serde_json::from_slice(&body).context_bad_request("malformed JSON body")?
+        };
+
+        let body = serde_json::from_value(value).context_bad_request("malformed request body")?;
+        Ok(Self(body))
+    }
+}
+
+fn insert_form_field(target: &mut Value, segments: &[&str], field: String) {
+    match segments.split_first() {
+        None => *target = Value::String(field),
+        Some((&"", rest)) => {
+            if !target.is_array() {
+                *target = Value::Array(Vec::new());
+            }
+            if let Value::Array(array) = target {
+                let mut element = Value::Null;
+                insert_form_field(&mut element, rest, field);
+                array.push(element);
+            }
+        }
+        Some((segment, rest)) => {
+            if !target.is_object() {
+                *target = Value::Object(Map::new());
+            }
+            if let Value::Object(object) = target {
+                let entry = object.entry(*segment).or_insert(Value::Null);
+                insert_form_field(entry, rest, field);
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Lenient<T> {
+    Value(T),
+    String(String),
+}
+
+/// Deserializes `T` also from its string form, as sent by forms.
+pub(super) fn lenient<'de, D, T>(deserializer: D) -> std::result::Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    match Lenient::<T>::deserialize(deserializer)? {
+        Lenient::Value(value) => Ok(value),
+        Lenient::String(value) => value.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+/// [`lenient`] for each element of an array.
+pub(super) fn lenient_vec<'de, D, T>(deserializer: D) -> std::result::Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    Vec::<Lenient<T>>::deserialize(deserializer)?
+        .into_iter()
+        .map(|element| match element {
+            Lenient::Value(value) => Ok(value),
+            Lenient::String(value) => value.parse().map_err(serde::de::Error::custom),
+        })
+        .collect()
+}
+
+/// [`lenient`] for optional fields, also accepting an empty string as `None`.
+pub(super) fn lenient_option<'de, D, T>(deserializer: D) -> std::result::Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    match Option::<Lenient<T>>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(Lenient::Value(value)) => Ok(Some(value)),
+        Some(Lenient::String(value)) if value.is_empty() => Ok(None),
+        Some(Lenient::String(value)) => value.parse().map(Some).map_err(serde::de::Error::custom),
+    }
+}
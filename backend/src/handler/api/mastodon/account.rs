@@ -0,0 +1,328 @@
+use activitypub_federation::config::Data;
+use axum::{
+    extract::{self, OriginalUri, RawQuery},
+    http::HeaderMap,
+    routing, Json, Router,
+};
+use chrono::Utc;
+use sea_orm::{
+    sea_query::{Condition, Query},
+    ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter,
+};
+use serde::Deserialize;
+use ulid::Ulid;
+
+use crate::{
+    dto::{
+        mastodon::{Account, AccountSource, CredentialAccount, MastodonId, Relationship, Status},
+        CreateFollow,
+    },
+    entity::{
+        block, domain_block, follow, follow_request, follower, mute, pin, post, setting, user,
+    },
+    error::{Context, Result},
+    handler::api::{
+        auth::Access,
+        follow::{delete_follow, post_follow},
+    },
+    mute::MuteFilter,
+    scope::{Resource, Scope},
+    state::State,
+};
+
+use super::{link_header, query_array, statuses_from_models, PaginationQuery};
+
+pub(super) fn create_router() -> Router {
+    Router::new()
+        .route("/verify_credentials", routing::get(get_verify_credentials))
+        .route("/relationships", routing::get(get_relationships))
+        .route("/:id", routing::get(get_account))
+        .route("/:id/statuses", routing::get(get_account_statuses))
+        .route("/:id/followers", routing::get(get_account_followers))
+        .route("/:id/following", routing::get(get_account_following))
+        .route("/:id/follow", routing::post(post_account_follow))
+        .route("/:id/unfollow", routing::post(post_account_unfollow))
+}
+
+/// Returns whether `id` is of the local account rather than a remote user.
+async fn is_local(id: MastodonId, data: &Data<State>) -> Result<bool> {
+    let setting = setting::Model::get(&*data.db).await?;
+    Ok(Ulid::from(setting.id) == id.0)
+}
+
+async fn find_relationship(id: MastodonId, data: &Data<State>) -> Result<Relationship> {
+    let mut relationship = Relationship {
+        id,
+        following: false,
+        showing_reblogs: true,
+        notifying: false,
+        followed_by: false,
+        blocking: false,
+        blocked_by: false,
+        muting: false,
+        muting_notifications: false,
+        requested: false,
+        domain_blocking: false,
+        endorsed: false,
+        note: String::new(),
+    };
+    let Some(user) = user::Entity::find_by_id(id.0)
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+    else {
+        return Ok(relationship);
+    };
+
+    if let Some(follow) = follow::Entity::find_by_id(user.id)
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+    {
+        relationship.following = follow.accepted;
+        relationship.requested = !follow.accepted;
+    }
+    relationship.followed_by = follower::Entity::find_by_id(user.id)
+        .count(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        > 0;
+    relationship.blocking = block::Entity::find_by_id(user.id)
+        .count(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        > 0;
+    relationship.muting = mute::Entity::find()
+        .filter(mute::Column::UserId.eq(user.id))
+        .filter(
+            Condition::any()
+                .add(mute::Column::ExpiresAt.is_null())
+                .add(mute::Column::ExpiresAt.gt(Utc::now())),
+        )
+        .count(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        > 0;
+    relationship.muting_notifications = relationship.muting;
+    relationship.domain_blocking = domain_block::Entity::find_by_id(user.host)
+        .count(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        > 0;
+
+    Ok(relationship)
+}
+
+#[tracing::instrument(skip(data, access))]
+async fn get_verify_credentials(
+    data: Data<State>,
+    access: Access,
+) -> Result<Json<CredentialAccount>> {
+    access.require(Scope::Read(Resource::Accounts))?;
+
+    let account = Account::local(&*data.db).await?;
+    let follow_requests_count = follow_request::Entity::find()
+        .count(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    let source = AccountSource {
+        privacy: "public",
+        sensitive: false,
+        language: String::new(),
+        note: account.note.clone(),
+        fields: Vec::new(),
+        follow_requests_count,
+    };
+    Ok(Json(CredentialAccount { account, source }))
+}
+
+#[tracing::instrument(skip(data, access))]
+async fn get_relationships(
+    data: Data<State>,
+    access: Access,
+    RawQuery(query): RawQuery,
+) -> Result<Json<Vec<Relationship>>> {
+    access.require(Scope::Read(Resource::Follows))?;
+
+    let mut relationships = Vec::new();
+    for id in query_array(query.as_deref(), "id") {
+        let id = id.parse().context_bad_request("malformed account ID")?;
+        relationships.push(find_relationship(id, &data).await?);
+    }
+    Ok(Json(relationships))
+}
+
+#[tracing::instrument(skip(data, access))]
+async fn get_account(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<MastodonId>,
+) -> Result<Json<Account>> {
+    access.require(Scope::Read(Resource::Accounts))?;
+
+    if is_local(id, &data).await? {
+        return Ok(Json(Account::local(&*data.db).await?));
+    }
+
+    let user = user::Entity::find_by_id(id.0)
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("account not found")?;
+    Ok(Json(Account::from_user(user)))
+}
+
+#[derive(Debug, Deserialize)]
+struct GetAccountStatusesQuery {
+    #[serde(default)]
+    pinned: bool,
+    #[serde(default)]
+    exclude_replies: bool,
+    #[serde(default)]
+    exclude_reblogs: bool,
+}
+
+#[tracing::instrument(skip(data, access))]
+async fn get_account_statuses(
+    data: Data<State>,
+    access: Access,
+    uri: OriginalUri,
+    extract::Path(id): extract::Path<MastodonId>,
+    extract::Query(query): extract::Query<PaginationQuery>,
+    extract::Query(statuses_query): extract::Query<GetAccountStatusesQuery>,
+) -> Result<(HeaderMap, Json<Vec<Status>>)> {
+    access.require(Scope::Read(Resource::Statuses))?;
+
+    let mute_filter = MuteFilter::load(&*data.db).await?;
+    let select = post::Entity::find()
+        .filter(post::Model::visible_condition())
+        .filter(mute_filter.post_condition());
+    let select = if is_local(id, &data).await? {
+        select.filter(post::Column::UserId.is_null())
+    } else {
+        select.filter(post::Column::UserId.eq(uuid::Uuid::from(id.0)))
+    };
+    let select = if statuses_query.pinned {
+        select.filter(
+            post::Column::Id.in_subquery(
+                Query::select()
+                    .column(pin::Column::PostId)
+                    .from(pin::Entity)
+                    .to_owned(),
+            ),
+        )
+    } else {
+        select
+    };
+    let select = if statuses_query.exclude_replies {
+        select.filter(post::Column::ReplyId.is_null())
+    } else {
+        select
+    };
+    let select = if statuses_query.exclude_reblogs {
+        select.filter(
+            Condition::any()
+                .add(post::Column::RepostId.is_null())
+                .add(post::Column::Text.ne("")),
+        )
+    } else {
+        select
+    };
+
+    let posts = query.fetch(select, post::Column::Id, &data).await?;
+    let headers = link_header(
+        &uri.0,
+        posts.first().map(|post| MastodonId::from(post.id)),
+        posts.last().map(|post| MastodonId::from(post.id)),
+    );
+    Ok((headers, Json(statuses_from_models(posts, &data).await?)))
+}
+
+/// Queries a page of the users in `subquery`, which only exist for the local account.
+async fn get_account_users(
+    data: &Data<State>,
+    uri: &OriginalUri,
+    id: MastodonId,
+    subquery: sea_orm::sea_query::SelectStatement,
+    query: PaginationQuery,
+) -> Result<(HeaderMap, Json<Vec<Account>>)> {
+    if !is_local(id, data).await? {
+        return Ok((HeaderMap::new(), Json(Vec::new())));
+    }
+
+    let select = user::Entity::find().filter(user::Column::Id.in_subquery(subquery));
+    let users = query.fetch(select, user::Column::Id, data).await?;
+    let headers = link_header(
+        &uri.0,
+        users.first().map(|user| MastodonId::from(user.id)),
+        users.last().map(|user| MastodonId::from(user.id)),
+    );
+    Ok((
+        headers,
+        Json(users.into_iter().map(Account::from_user).collect()),
+    ))
+}
+
+#[tracing::instrument(skip(data, access))]
+async fn get_account_followers(
+    data: Data<State>,
+    access: Access,
+    uri: OriginalUri,
+    extract::Path(id): extract::Path<MastodonId>,
+    extract::Query(query): extract::Query<PaginationQuery>,
+) -> Result<(HeaderMap, Json<Vec<Account>>)> {
+    access.require(Scope::Read(Resource::Accounts))?;
+
+    let subquery = Query::select()
+        .column(follower::Column::FromId)
+        .from(follower::Entity)
+        .to_owned();
+    get_account_users(&data, &uri, id, subquery, query).await
+}
+
+#[tracing::instrument(skip(data, access))]
+async fn get_account_following(
+    data: Data<State>,
+    access: Access,
+    uri: OriginalUri,
+    extract::Path(id): extract::Path<MastodonId>,
+    extract::Query(query): extract::Query<PaginationQuery>,
+) -> Result<(HeaderMap, Json<Vec<Account>>)> {
+    access.require(Scope::Read(Resource::Accounts))?;
+
+    let subquery = Query::select()
+        .column(follow::Column::ToId)
+        .from(follow::Entity)
+        .and_where(follow::Column::Accepted.eq(true))
+        .to_owned();
+    get_account_users(&data, &uri, id, subquery, query).await
+}
+
+#[tracing::instrument(skip(data, access))]
+async fn post_account_follow(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<MastodonId>,
+) -> Result<Json<Relationship>> {
+    access.require(Scope::Write(Resource::Follows))?;
+
+    post_follow(
+        data.reset_request_count(),
+        access,
+        Json(CreateFollow { to_id: id.0 }),
+    )
+    .await?;
+    Ok(Json(find_relationship(id, &data).await?))
+}
+
+#[tracing::instrument(skip(data, access))]
+async fn post_account_unfollow(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<MastodonId>,
+) -> Result<Json<Relationship>> {
+    access.require(Scope::Write(Resource::Follows))?;
+
+    delete_follow(data.reset_request_count(), extract::Path(id.0), access).await?;
+    Ok(Json(find_relationship(id, &data).await?))
+}
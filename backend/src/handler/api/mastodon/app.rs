@@ -0,0 +1,103 @@
+use activitypub_federation::config::Data;
+use axum::{routing, Json, Router};
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, ActiveValue, EntityTrait};
+use serde::Deserialize;
+use ulid::Ulid;
+use url::Url;
+
+use crate::{
+    dto::mastodon::Application,
+    entity::oauth_application,
+    error::{Context, Result},
+    format_err,
+    handler::api::auth::Access,
+    scope,
+    state::State,
+    util::random_token,
+};
+
+use super::JsonOrForm;
+
+/// Redirect URI for clients without one, which show the code to the user instead.
+pub const OOB_REDIRECT_URI: &str = "urn:ietf:wg:oauth:2.0:oob";
+
+pub(super) fn create_router() -> Router {
+    Router::new()
+        .route("/", routing::post(post_app))
+        .route("/verify_credentials", routing::get(get_verify_credentials))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RedirectUris {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+impl RedirectUris {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            Self::Single(uris) => uris.split_whitespace().map(str::to_string).collect(),
+            Self::Multiple(uris) => uris,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PostAppReq {
+    client_name: String,
+    redirect_uris: RedirectUris,
+    #[serde(default)]
+    scopes: Option<String>,
+    #[serde(default)]
+    website: Option<String>,
+}
+
+#[tracing::instrument(skip(data))]
+async fn post_app(
+    data: Data<State>,
+    JsonOrForm(req): JsonOrForm<PostAppReq>,
+) -> Result<Json<Application>> {
+    let redirect_uris = req.redirect_uris.into_vec();
+    if redirect_uris.is_empty() {
+        return Err(format_err!(BAD_REQUEST, "redirect_uris must not be empty"));
+    }
+    for redirect_uri in &redirect_uris {
+        if redirect_uri != OOB_REDIRECT_URI && Url::parse(redirect_uri).is_err() {
+            return Err(format_err!(BAD_REQUEST, "malformed redirect URI"));
+        }
+    }
+    let scopes = scope::parse(req.scopes.as_deref()).context_bad_request("unknown scope")?;
+
+    let application_activemodel = oauth_application::ActiveModel {
+        id: ActiveValue::Set(Ulid::new().into()),
+        name: ActiveValue::Set(req.client_name),
+        website: ActiveValue::Set(req.website.filter(|website| !website.is_empty())),
+        redirect_uris: ActiveValue::Set(redirect_uris),
+        scopes: ActiveValue::Set(scopes),
+        client_id: ActiveValue::Set(random_token()),
+        client_secret: ActiveValue::Set(random_token()),
+        created_at: ActiveValue::Set(Utc::now().fixed_offset()),
+    };
+    let application = application_activemodel
+        .insert(&*data.db)
+        .await
+        .context_internal_server_error("failed to insert to database")?;
+
+    Ok(Json(Application::from_model(application, true)))
+}
+
+#[tracing::instrument(skip(data, access))]
+async fn get_verify_credentials(data: Data<State>, access: Access) -> Result<Json<Application>> {
+    let application_id = access
+        .key
+        .application_id
+        .context_not_found("access key not issued to application")?;
+    let application = oauth_application::Entity::find_by_id(application_id)
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("application not found")?;
+    Ok(Json(Application::from_model(application, false)))
+}
@@ -0,0 +1,95 @@
+use activitypub_federation::config::Data;
+use axum::Json;
+use sea_orm::{ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QuerySelect};
+
+use crate::{
+    config::CONFIG,
+    dto::mastodon::{
+        instance_version, Account, InstanceConfiguration, InstanceStats, InstanceUrls, InstanceV1,
+        InstanceV2, InstanceV2Configuration, InstanceV2ConfigurationUrls, InstanceV2Contact,
+        InstanceV2Registrations, InstanceV2Thumbnail, InstanceV2Usage, InstanceV2UsageUsers,
+    },
+    entity::{post, setting, user},
+    error::{Context, Result},
+    state::State,
+};
+
+#[tracing::instrument(skip(data))]
+pub(super) async fn get_instance_v1(data: Data<State>) -> Result<Json<InstanceV1>> {
+    let setting = setting::Model::get(&*data.db).await?;
+    let account = Account::local(&*data.db).await?;
+
+    let domain_count = user::Entity::find()
+        .select_only()
+        .column(user::Column::Host)
+        .distinct()
+        .count(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    let status_count = post::Entity::find()
+        .filter(post::Column::UserId.is_null())
+        .count(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+
+    let description = setting.instance_description.unwrap_or_default();
+    Ok(Json(InstanceV1 {
+        uri: CONFIG.public_domain.clone(),
+        title: setting.instance_name,
+        short_description: description.clone(),
+        description,
+        email: setting.maintainer_email.unwrap_or_default(),
+        version: instance_version(),
+        urls: InstanceUrls {
+            streaming_api: format!("https://{}", CONFIG.public_domain),
+        },
+        stats: InstanceStats {
+            user_count: 1,
+            status_count,
+            domain_count,
+        },
+        thumbnail: None,
+        languages: Vec::new(),
+        registrations: false,
+        approval_required: false,
+        invites_enabled: false,
+        configuration: InstanceConfiguration::new(),
+        contact_account: account,
+        rules: Vec::new(),
+    }))
+}
+
+#[tracing::instrument(skip(data))]
+pub(super) async fn get_instance_v2(data: Data<State>) -> Result<Json<InstanceV2>> {
+    let setting = setting::Model::get(&*data.db).await?;
+    let account = Account::local(&*data.db).await?;
+
+    Ok(Json(InstanceV2 {
+        domain: CONFIG.public_domain.clone(),
+        title: setting.instance_name,
+        version: instance_version(),
+        source_url: env!("CARGO_PKG_REPOSITORY").to_string(),
+        description: setting.instance_description.unwrap_or_default(),
+        usage: InstanceV2Usage {
+            users: InstanceV2UsageUsers { active_month: 1 },
+        },
+        thumbnail: InstanceV2Thumbnail { url: None },
+        languages: Vec::new(),
+        configuration: InstanceV2Configuration {
+            urls: InstanceV2ConfigurationUrls {
+                streaming: format!("https://{}", CONFIG.public_domain),
+            },
+            inner: InstanceConfiguration::new(),
+        },
+        registrations: InstanceV2Registrations {
+            enabled: false,
+            approval_required: false,
+            message: None,
+        },
+        contact: InstanceV2Contact {
+            email: setting.maintainer_email.unwrap_or_default(),
+            account,
+        },
+        rules: Vec::new(),
+    }))
+}
@@ -0,0 +1,244 @@
+use activitypub_federation::config::Data;
+use axum::{body::Bytes, extract, http::HeaderMap, routing, Json, Router};
+use mime::Mime;
+use sea_orm::{ActiveModelTrait, ActiveValue, EntityTrait};
+use serde::Deserialize;
+
+use crate::{
+    dto::mastodon::{MastodonId, MediaAttachment},
+    entity::local_file,
+    error::{Context, Result},
+    format_err,
+    handler::api::auth::Access,
+    scope::{Resource, Scope},
+    state::State,
+};
+
+use super::JsonOrForm;
+
+pub(super) fn create_router() -> Router {
+    Router::new()
+        .route("/", routing::post(post_media))
+        .route("/:id", routing::get(get_media).put(put_media))
+}
+
+fn media_attachment(file: local_file::Model) -> Result<MediaAttachment> {
+    let media_type: Mime = file
+        .media_type
+        .parse()
+        .context_internal_server_error("malformed file media type")?;
+    Ok(MediaAttachment::new(
+        MastodonId::from(file.id).to_string(),
+        &media_type,
+        file.url,
+        file.alt,
+    ))
+}
+
+struct MultipartField {
+    name: String,
+    file_name: Option<String>,
+    content_type: Option<String>,
+    data: Bytes,
+}
+
+fn find_bytes(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack
+        .get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|position| position + from)
+}
+
+fn disposition_param(disposition: &str, key: &str) -> Option<String> {
+    disposition.split(';').find_map(|param| {
+        let (param_key, value) = param.trim().split_once('=')?;
+        (param_key.eq_ignore_ascii_case(key)).then(|| value.trim_matches('"').to_string())
+    })
+}
+
+/// Parses a `multipart/form-data` body, which is all media uploads are sent as.
+fn parse_multipart(content_type: &str, body: Bytes) -> Result<Vec<MultipartField>> {
+    let boundary = content_type
+        .split(';')
+        .find_map(|param| param.trim().strip_prefix("boundary="))
+        .context_bad_request("multipart boundary not found")?
+        .trim_matches('"');
+    let delimiter = format!("\r\n--{}", boundary);
+    let delimiter = delimiter.as_bytes();
+
+    let mut fields = Vec::new();
+    // The first delimiter may not be preceded by a line break
+    let mut position = find_bytes(&body, &delimiter[2..], 0)
+        .context_bad_request("malformed multipart body")?
+        + delimiter.len()
+        - 2;
+    loop {
+        if body.get(position..position + 2) == Some(b"--") {
+            break;
+        }
+        let headers_start = position + 2;
+        let headers_end = find_bytes(&body, b"\r\n\r\n", headers_start)
+            .context_bad_request("malformed multipart body")?;
+        let data_end = find_bytes(&body, delimiter, headers_end)
+            .context_bad_request("malformed multipart body")?;
+
+        let headers = String::from_utf8_lossy(&body[headers_start..headers_end]);
+        let mut name = None;
+        let mut file_name = None;
+        let mut content_type = None;
+        for line in headers.split("\r\n") {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            if key.trim().eq_ignore_ascii_case("content-disposition") {
+                name = disposition_param(value, "name");
+                file_name = disposition_param(value, "filename");
+            } else if key.trim().eq_ignore_ascii_case("content-type") {
+                content_type = Some(value.trim().to_string());
+            }
+        }
+        if let Some(name) = name {
+            fields.push(MultipartField {
+                name,
+                file_name,
+                content_type,
+                data: body.slice(headers_end + 4..data_end),
+            });
+        }
+
+        position = data_end + delimiter.len();
+    }
+
+    Ok(fields)
+}
+
+#[tracing::instrument(skip(data, access, headers, body))]
+pub(super) async fn post_media(
+    data: Data<State>,
+    access: Access,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<MediaAttachment>> {
+    access.require(Scope::Write(Resource::Media))?;
+
+    let content_type = headers
+        .get(axum::http::header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .unwrap_or_default();
+    if !content_type.starts_with("multipart/form-data") {
+        return Err(format_err!(
+            UNSUPPORTED_MEDIA_TYPE,
+            "media must be sent as multipart/form-data"
+        ));
+    }
+
+    let mut file = None;
+    let mut description = None;
+    for field in parse_multipart(content_type, body)? {
+        match field.name.as_str() {
+            "file" => file = Some(field),
+            "description" => {
+                description = Some(String::from_utf8_lossy(&field.data).into_owned())
+                    .filter(|description| !description.is_empty())
+            }
+            _ => {}
+        }
+    }
+    let file = file.context_bad_request("file not found")?;
+
+    let media_type = file
+        .content_type
+        .as_deref()
+        .and_then(|content_type| content_type.parse().ok())
+        .filter(|media_type| *media_type != mime::APPLICATION_OCTET_STREAM)
+        .or_else(|| {
+            file.file_name
+                .as_deref()
+                .and_then(|file_name| mime_guess::from_path(file_name).first())
+        })
+        .unwrap_or(mime::APPLICATION_OCTET_STREAM);
+    let file = local_file::Model::put(file.data, media_type, description, &*data.db).await?;
+
+    Ok(Json(media_attachment(file)?))
+}
+
+#[tracing::instrument(skip(data, access))]
+async fn get_media(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<MastodonId>,
+) -> Result<Json<MediaAttachment>> {
+    access.require(Scope::Read(Resource::Media))?;
+
+    let file = local_file::Entity::find_by_id(id.0)
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("media not found")?;
+    Ok(Json(media_attachment(file)?))
+}
+
+#[derive(Debug, Deserialize)]
+struct PutMediaReq {
+    #[serde(default)]
+    description: Option<String>,
+}
+
+#[tracing::instrument(skip(data, access))]
+async fn put_media(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<MastodonId>,
+    JsonOrForm(req): JsonOrForm<PutMediaReq>,
+) -> Result<Json<MediaAttachment>> {
+    access.require(Scope::Write(Resource::Media))?;
+
+    let file = local_file::Entity::find_by_id(id.0)
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("media not found")?;
+    if file.post_id.is_some() {
+        return Err(format_err!(CONFLICT, "media already attached to post"));
+    }
+
+    let mut file_activemodel: local_file::ActiveModel = file.into();
+    file_activemodel.alt = ActiveValue::Set(
+        req.description
+            .filter(|description| !description.is_empty()),
+    );
+    let file = file_activemodel
+        .update(&*data.db)
+        .await
+        .context_internal_server_error("failed to update database")?;
+    Ok(Json(media_attachment(file)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_multipart_fields() {
+        let body = "--XyZ\r\n\
+                    Content-Disposition: form-data; name=\"description\"\r\n\r\n\
+                    a cat\r\n\
+                    --XyZ\r\n\
+                    Content-Disposition: form-data; name=\"file\"; filename=\"cat.png\"\r\n\
+                    Content-Type: image/png\r\n\r\n\
+                    \x7fPNG\r\n\r\n\
+                    --XyZ--\r\n";
+        let fields = parse_multipart("multipart/form-data; boundary=XyZ", Bytes::from(body))
+            .map_err(|error| error.inner)
+            .unwrap();
+
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[0].name, "description");
+        assert_eq!(&fields[0].data[..], b"a cat");
+        assert_eq!(fields[1].name, "file");
+        assert_eq!(fields[1].file_name.as_deref(), Some("cat.png"));
+        assert_eq!(fields[1].content_type.as_deref(), Some("image/png"));
+        assert_eq!(&fields[1].data[..], "\x7fPNG\r\n".as_bytes());
+    }
+}
@@ -0,0 +1,193 @@
+use activitypub_federation::config::Data;
+use axum::{
+    extract::{self, OriginalUri, RawQuery},
+    http::HeaderMap,
+    routing, Json, Router,
+};
+use sea_orm::{ConnectionTrait, EntityTrait, QueryFilter};
+use ulid::Ulid;
+
+use crate::{
+    dto::mastodon::{Account, MastodonId, Notification, Status},
+    entity::{notification, post, reaction, user},
+    error::{Context, Result},
+    handler::api::auth::Access,
+    mute::MuteFilter,
+    queue::{self, NotificationType},
+    scope::{Resource, Scope},
+    state::State,
+};
+
+use super::{link_header, query_array, PaginationQuery};
+
+pub(super) fn create_router() -> Router {
+    Router::new()
+        .route("/", routing::get(get_notifications))
+        .route("/:id", routing::get(get_notification))
+}
+
+async fn find_account(
+    user_id: Option<uuid::Uuid>,
+    me: &Account,
+    db: &impl ConnectionTrait,
+) -> Result<Option<Account>> {
+    let Some(user_id) = user_id else {
+        return Ok(Some(me.clone()));
+    };
+    let user = user::Entity::find_by_id(user_id)
+        .one(db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    Ok(user.map(Account::from_user))
+}
+
+async fn find_status(
+    post_id: Ulid,
+    me: &Account,
+    db: &impl ConnectionTrait,
+) -> Result<Option<Status>> {
+    let post = post::Entity::find_by_id(post_id)
+        .one(db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    match post {
+        Some(post) => Ok(Some(Status::from_model(post, me, db).await?)),
+        None => Ok(None),
+    }
+}
+
+/// Converts `notification`, returning `None` if Mastodon has no such type or what it refers to
+/// is gone.
+pub(super) async fn from_notification(
+    notification: queue::Notification,
+    me: &Account,
+    db: &impl ConnectionTrait,
+) -> Result<Option<Notification>> {
+    let (ty, account, status) = match notification.ty {
+        NotificationType::CreateFollower { user_id } => (
+            "follow",
+            find_account(Some(user_id.into()), me, db).await?,
+            None,
+        ),
+        NotificationType::FollowRequested { user_id } => (
+            "follow_request",
+            find_account(Some(user_id.into()), me, db).await?,
+            None,
+        ),
+        NotificationType::Mentioned { post_id }
+        | NotificationType::Replied { post_id }
+        | NotificationType::Quoted { post_id } => {
+            let Some(status) = find_status(post_id, me, db).await? else {
+                return Ok(None);
+            };
+            ("mention", Some(status.account.clone()), Some(status))
+        }
+        NotificationType::Reposted { user_id, post_id } => (
+            "reblog",
+            find_account(Some(user_id.into()), me, db).await?,
+            find_status(post_id, me, db).await?,
+        ),
+        NotificationType::Reacted {
+            post_id,
+            reaction_id,
+        } => {
+            let Some(reaction) = reaction::Entity::find_by_id(reaction_id)
+                .one(db)
+                .await
+                .context_internal_server_error("failed to query database")?
+            else {
+                return Ok(None);
+            };
+            (
+                "favourite",
+                find_account(reaction.user_id, me, db).await?,
+                find_status(post_id, me, db).await?,
+            )
+        }
+        NotificationType::PollEnded { post_id } => {
+            let Some(status) = find_status(post_id, me, db).await? else {
+                return Ok(None);
+            };
+            ("poll", Some(status.account.clone()), Some(status))
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(account.map(|account| Notification::new(notification.id, ty, account, status)))
+}
+
+#[tracing::instrument(skip(data, access))]
+async fn get_notifications(
+    data: Data<State>,
+    access: Access,
+    uri: OriginalUri,
+    RawQuery(raw_query): RawQuery,
+    extract::Query(query): extract::Query<PaginationQuery>,
+) -> Result<(HeaderMap, Json<Vec<Notification>>)> {
+    access.require(Scope::Read(Resource::Notifications))?;
+
+    let types = query_array(raw_query.as_deref(), "types");
+    let exclude_types = query_array(raw_query.as_deref(), "exclude_types");
+
+    let mute_filter = MuteFilter::load(&*data.db).await?;
+    let models = query
+        .fetch(
+            notification::Entity::find().filter(mute_filter.notification_condition()),
+            notification::Column::Id,
+            &data,
+        )
+        .await?;
+    let headers = link_header(
+        &uri.0,
+        models.first().map(|model| MastodonId::from(model.id)),
+        models.last().map(|model| MastodonId::from(model.id)),
+    );
+
+    let me = Account::local(&*data.db).await?;
+    let mut notifications = Vec::with_capacity(models.len());
+    for model in models {
+        let Ok(ty) = serde_json::from_value(model.payload) else {
+            continue;
+        };
+        let notification = queue::Notification {
+            id: model.id.into(),
+            ty,
+        };
+        let Some(notification) = from_notification(notification, &me, &*data.db).await? else {
+            continue;
+        };
+        if (!types.is_empty() && !types.iter().any(|ty| ty == notification.ty))
+            || exclude_types.iter().any(|ty| ty == notification.ty)
+        {
+            continue;
+        }
+        notifications.push(notification);
+    }
+
+    Ok((headers, Json(notifications)))
+}
+
+#[tracing::instrument(skip(data, access))]
+async fn get_notification(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<MastodonId>,
+) -> Result<Json<Notification>> {
+    access.require(Scope::Read(Resource::Notifications))?;
+
+    let model = notification::Entity::find_by_id(id.0)
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("notification not found")?;
+    let notification = queue::Notification {
+        id: model.id.into(),
+        ty: serde_json::from_value(model.payload)
+            .context_internal_server_error("malformed notification payload")?,
+    };
+    let me = Account::local(&*data.db).await?;
+    let notification = from_notification(notification, &me, &*data.db)
+        .await?
+        .context_not_found("notification not found")?;
+    Ok(Json(notification))
+}
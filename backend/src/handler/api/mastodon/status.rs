@@ -0,0 +1,401 @@
+use activitypub_federation::config::Data;
+use axum::{
+    extract,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing, Json, Router,
+};
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use serde::Deserialize;
+use ulid::Ulid;
+
+use crate::{
+    dto::{
+        mastodon::{
+            visibility_to_str, Account, MastodonId, Poll, ScheduledStatus, ScheduledStatusParams,
+            Status, StatusContext, StatusVisibility,
+        },
+        CreateContentReaction, CreatePoll, CreatePost, CreateReaction, CreateVote, Post,
+        PostFormat, Visibility,
+    },
+    entity::{post, scheduled_post},
+    error::{Context, Result},
+    handler::api::{
+        auth::Access,
+        post::{
+            create_post, delete_post, delete_post_bookmark, delete_post_pin, delete_post_reaction,
+            get_post_context, post_post_bookmark, post_post_pin, post_post_reaction,
+            post_post_vote, validate_post, GetPostContextQuery,
+        },
+    },
+    scope::{Resource, Scope},
+    state::State,
+};
+
+use super::{lenient, lenient_option, lenient_vec, JsonOrForm};
+
+/// Reaction sent for favourites, which Mastodon has no other content for.
+const FAVOURITE_REACTION: &str = "❤️";
+
+pub(super) fn create_router() -> Router {
+    Router::new()
+        .route("/", routing::post(post_status))
+        .route("/:id", routing::get(get_status).delete(delete_status))
+        .route("/:id/context", routing::get(get_status_context))
+        .route("/:id/favourite", routing::post(post_status_favourite))
+        .route("/:id/unfavourite", routing::post(post_status_unfavourite))
+        .route("/:id/reblog", routing::post(post_status_reblog))
+        .route("/:id/unreblog", routing::post(post_status_unreblog))
+        .route("/:id/bookmark", routing::post(post_status_bookmark))
+        .route("/:id/unbookmark", routing::post(post_status_unbookmark))
+        .route("/:id/pin", routing::post(post_status_pin))
+        .route("/:id/unpin", routing::post(post_status_unpin))
+}
+
+/// Queries the status of post `id`.
+async fn find_status(id: Ulid, data: &Data<State>) -> Result<Status> {
+    let post = post::Entity::find_by_id(id)
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("post not found")?;
+    let me = Account::local(&*data.db).await?;
+    Status::from_model(post, &me, &*data.db).await
+}
+
+/// Treats `CONFLICT` as success, since Mastodon clients expect these actions to be idempotent.
+fn ignore_conflict(result: Result<()>) -> Result<()> {
+    match result {
+        Err(error) if error.status_code == StatusCode::CONFLICT => Ok(()),
+        result => result,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PostStatusPoll {
+    options: Vec<String>,
+    #[serde(deserialize_with = "lenient")]
+    expires_in: i64,
+    #[serde(default, deserialize_with = "lenient")]
+    multiple: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct PostStatusReq {
+    #[serde(default)]
+    status: Option<String>,
+    #[serde(default)]
+    media_ids: Vec<MastodonId>,
+    #[serde(default)]
+    poll: Option<PostStatusPoll>,
+    #[serde(default, deserialize_with = "lenient_option")]
+    in_reply_to_id: Option<MastodonId>,
+    #[serde(default, deserialize_with = "lenient")]
+    sensitive: bool,
+    #[serde(default)]
+    spoiler_text: Option<String>,
+    #[serde(default)]
+    visibility: Option<StatusVisibility>,
+    #[serde(default, deserialize_with = "lenient_option")]
+    scheduled_at: Option<DateTime<FixedOffset>>,
+}
+
+#[tracing::instrument(skip(data, access, req))]
+async fn post_status(
+    data: Data<State>,
+    access: Access,
+    JsonOrForm(req): JsonOrForm<PostStatusReq>,
+) -> Result<Response> {
+    access.require(Scope::Write(Resource::Statuses))?;
+
+    let req = CreatePost {
+        reply_id: req.in_reply_to_id.map(|id| id.0),
+        repost_id: None,
+        text: req.status.unwrap_or_default(),
+        format: Some(PostFormat::Plain),
+        title: req
+            .spoiler_text
+            .filter(|spoiler_text| !spoiler_text.is_empty()),
+        visibility: req.visibility.map(Into::into).unwrap_or(Visibility::Public),
+        is_sensitive: req.sensitive,
+        files: req.media_ids.into_iter().map(|id| id.0).collect(),
+        mentions: Vec::new(),
+        emojis: Vec::new(),
+        hashtags: Vec::new(),
+        poll: req.poll.map(|poll| CreatePoll {
+            options: poll.options,
+            ends_at: Some((Utc::now() + Duration::seconds(poll.expires_in)).fixed_offset()),
+            is_multiple: poll.multiple,
+        }),
+        scheduled_at: req.scheduled_at,
+    };
+    validate_post(&req)?;
+
+    if let Some(scheduled_at) = req.scheduled_at {
+        let params = ScheduledStatusParams {
+            text: req.text.clone(),
+            visibility: visibility_to_str(&req.visibility),
+            sensitive: req.is_sensitive,
+            spoiler_text: req.title.clone(),
+            media_ids: req.files.iter().copied().map(MastodonId).collect(),
+            in_reply_to_id: req.reply_id.map(MastodonId),
+        };
+        let id = scheduled_post::Model::schedule(scheduled_at, req, &*data.db).await?;
+        return Ok(Json(ScheduledStatus {
+            id: MastodonId(id),
+            scheduled_at: scheduled_at.into(),
+            params,
+            media_attachments: Vec::new(),
+        })
+        .into_response());
+    }
+
+    let id = create_post(req, &data).await?;
+    Ok(Json(find_status(id, &data).await?).into_response())
+}
+
+#[tracing::instrument(skip(data, access))]
+async fn get_status(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<MastodonId>,
+) -> Result<Json<Status>> {
+    access.require(Scope::Read(Resource::Statuses))?;
+
+    Ok(Json(find_status(id.0, &data).await?))
+}
+
+/// Returns the deleted status, so that clients can offer to redraft it.
+#[tracing::instrument(skip(data, access))]
+async fn delete_status(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<MastodonId>,
+) -> Result<Json<Status>> {
+    access.require(Scope::Write(Resource::Statuses))?;
+
+    let status = find_status(id.0, &data).await?;
+    delete_post(data, access, extract::Path(id.0)).await?;
+    Ok(Json(status))
+}
+
+#[tracing::instrument(skip(data, access))]
+async fn get_status_context(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<MastodonId>,
+) -> Result<Json<StatusContext>> {
+    access.require(Scope::Read(Resource::Statuses))?;
+
+    let query = GetPostContextQuery {
+        depth: 64,
+        fetch_remote: false,
+    };
+    let Json(context) = get_post_context(
+        data.reset_request_count(),
+        access,
+        extract::Path(id.0),
+        extract::Query(query),
+    )
+    .await?;
+
+    let me = Account::local(&*data.db).await?;
+    let ancestors = Status::from_posts(context.ancestors, &me, &*data.db).await?;
+    let descendants = Status::from_posts(context.descendants, &me, &*data.db).await?;
+    Ok(Json(StatusContext {
+        ancestors,
+        descendants,
+    }))
+}
+
+#[tracing::instrument(skip(data, access))]
+async fn post_status_favourite(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<MastodonId>,
+) -> Result<Json<Status>> {
+    access.require(Scope::Write(Resource::Favourites))?;
+
+    let req = CreateReaction::Content(CreateContentReaction {
+        content: FAVOURITE_REACTION.to_string(),
+    });
+    ignore_conflict(
+        post_post_reaction(
+            data.reset_request_count(),
+            access,
+            extract::Path(id.0),
+            Json(req),
+        )
+        .await,
+    )?;
+    Ok(Json(find_status(id.0, &data).await?))
+}
+
+#[tracing::instrument(skip(data, access))]
+async fn post_status_unfavourite(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<MastodonId>,
+) -> Result<Json<Status>> {
+    access.require(Scope::Write(Resource::Favourites))?;
+
+    delete_post_reaction(data.reset_request_count(), access, extract::Path(id.0)).await?;
+    Ok(Json(find_status(id.0, &data).await?))
+}
+
+#[derive(Debug, Deserialize)]
+struct PostStatusReblogReq {
+    #[serde(default)]
+    visibility: Option<StatusVisibility>,
+}
+
+/// Queries our repost of post `id`, which has no text of its own.
+async fn find_reblog(id: Ulid, data: &Data<State>) -> Result<Option<post::Model>> {
+    post::Entity::find()
+        .filter(post::Column::RepostId.eq(uuid::Uuid::from(id)))
+        .filter(post::Column::UserId.is_null())
+        .filter(post::Column::Text.eq(""))
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")
+}
+
+#[tracing::instrument(skip(data, access, req))]
+async fn post_status_reblog(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<MastodonId>,
+    JsonOrForm(req): JsonOrForm<PostStatusReblogReq>,
+) -> Result<Json<Status>> {
+    access.require(Scope::Write(Resource::Statuses))?;
+
+    let reblog_id = match find_reblog(id.0, &data).await? {
+        Some(reblog) => reblog.id.into(),
+        None => {
+            let req = CreatePost {
+                reply_id: None,
+                repost_id: Some(id.0),
+                text: String::new(),
+                format: None,
+                title: None,
+                visibility: req.visibility.map(Into::into).unwrap_or(Visibility::Public),
+                is_sensitive: false,
+                files: Vec::new(),
+                mentions: Vec::new(),
+                emojis: Vec::new(),
+                hashtags: Vec::new(),
+                poll: None,
+                scheduled_at: None,
+            };
+            create_post(req, &data).await?
+        }
+    };
+    Ok(Json(find_status(reblog_id, &data).await?))
+}
+
+#[tracing::instrument(skip(data, access))]
+async fn post_status_unreblog(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<MastodonId>,
+) -> Result<Json<Status>> {
+    access.require(Scope::Write(Resource::Statuses))?;
+
+    if let Some(reblog) = find_reblog(id.0, &data).await? {
+        delete_post(
+            data.reset_request_count(),
+            access,
+            extract::Path(reblog.id.into()),
+        )
+        .await?;
+    }
+    Ok(Json(find_status(id.0, &data).await?))
+}
+
+#[tracing::instrument(skip(data, access))]
+async fn post_status_bookmark(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<MastodonId>,
+) -> Result<Json<Status>> {
+    access.require(Scope::Write(Resource::Bookmarks))?;
+
+    ignore_conflict(
+        post_post_bookmark(data.reset_request_count(), access, extract::Path(id.0)).await,
+    )?;
+    Ok(Json(find_status(id.0, &data).await?))
+}
+
+#[tracing::instrument(skip(data, access))]
+async fn post_status_unbookmark(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<MastodonId>,
+) -> Result<Json<Status>> {
+    access.require(Scope::Write(Resource::Bookmarks))?;
+
+    delete_post_bookmark(data.reset_request_count(), access, extract::Path(id.0)).await?;
+    Ok(Json(find_status(id.0, &data).await?))
+}
+
+#[tracing::instrument(skip(data, access))]
+async fn post_status_pin(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<MastodonId>,
+) -> Result<Json<Status>> {
+    access.require(Scope::Write(Resource::Accounts))?;
+
+    ignore_conflict(post_post_pin(data.reset_request_count(), access, extract::Path(id.0)).await)?;
+    Ok(Json(find_status(id.0, &data).await?))
+}
+
+#[tracing::instrument(skip(data, access))]
+async fn post_status_unpin(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<MastodonId>,
+) -> Result<Json<Status>> {
+    access.require(Scope::Write(Resource::Accounts))?;
+
+    delete_post_pin(data.reset_request_count(), access, extract::Path(id.0)).await?;
+    Ok(Json(find_status(id.0, &data).await?))
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct PostPollVotesReq {
+    #[serde(deserialize_with = "lenient_vec")]
+    choices: Vec<u32>,
+}
+
+/// Polls share the ID of their post.
+#[tracing::instrument(skip(data, access))]
+pub(super) async fn post_poll_votes(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<MastodonId>,
+    JsonOrForm(req): JsonOrForm<PostPollVotesReq>,
+) -> Result<Json<Poll>> {
+    access.require(Scope::Write(Resource::Statuses))?;
+
+    let req = CreateVote {
+        choices: req.choices,
+    };
+    post_post_vote(
+        data.reset_request_count(),
+        access,
+        extract::Path(id.0),
+        Json(req),
+    )
+    .await?;
+
+    let post = post::Entity::find_by_id(id.0)
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("post not found")?;
+    let post = Post::from_model(post, &*data.db).await?;
+    let poll = post.poll.context_not_found("poll not found")?;
+    Ok(Json(Poll::from_dto(id, poll)))
+}
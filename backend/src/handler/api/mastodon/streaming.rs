@@ -0,0 +1,201 @@
+use std::convert::Infallible;
+
+use activitypub_federation::config::Data;
+use axum::{
+    extract,
+    headers::{authorization::Bearer, Authorization, HeaderMapExt},
+    http::HeaderMap,
+    response::{sse::Event as SseEvent, Sse},
+    routing, Router,
+};
+use futures_util::{Stream, StreamExt};
+use sea_orm::{EntityTrait, QueryFilter};
+use serde::Deserialize;
+use ulid::Ulid;
+
+use crate::{
+    dto::mastodon::{Account, Status},
+    entity::{follow, post, sea_orm_active_enums::Visibility},
+    error::{Context, Result},
+    handler::api::auth::Access,
+    mute::MuteFilter,
+    queue::{listen_events, Event, Update},
+    scope::{Resource, Scope},
+    state::State,
+};
+
+use super::notification::from_notification;
+
+pub(super) fn create_router() -> Router {
+    Router::new()
+        .route("/health", routing::get(get_streaming_health))
+        .route("/user", routing::get(get_user_stream))
+        .route("/public", routing::get(get_public_stream))
+        .route("/public/local", routing::get(get_public_local_stream))
+}
+
+#[derive(Clone, Copy, Debug)]
+enum StreamType {
+    User,
+    Public,
+    PublicLocal,
+}
+
+impl StreamType {
+    /// Returns whether `post` belongs to this stream. Visibility and mutes are checked by
+    /// [`status_event`] beforehand.
+    async fn includes(self, post: &post::Model, data: &Data<State>) -> Result<bool> {
+        match self {
+            Self::User => match post.user_id {
+                None => Ok(true),
+                Some(user_id) => Ok(follow::Entity::find_by_id(user_id)
+                    .one(&*data.db)
+                    .await
+                    .context_internal_server_error("failed to query database")?
+                    .map(|follow| follow.accepted)
+                    .unwrap_or(false)),
+            },
+            Self::Public => Ok(post.visibility == Visibility::Public),
+            Self::PublicLocal => {
+                Ok(post.visibility == Visibility::Public && post.user_id.is_none())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamingQuery {
+    #[serde(default)]
+    access_token: Option<String>,
+}
+
+/// Browsers cannot set headers on `EventSource`, so the token may also come as a query.
+async fn authorize(
+    headers: &HeaderMap,
+    query: StreamingQuery,
+    data: &Data<State>,
+) -> Result<Access> {
+    let token = match headers.typed_get::<Authorization<Bearer>>() {
+        Some(bearer) => bearer.token().to_string(),
+        None => query
+            .access_token
+            .context_unauthorized("user not authorized")?,
+    };
+    let access = Access::from_token(&token, data).await?;
+    access.require(Scope::Read(Resource::Statuses))?;
+    Ok(access)
+}
+
+async fn status_event(
+    name: &'static str,
+    post_id: Ulid,
+    stream_type: StreamType,
+    data: &Data<State>,
+) -> Result<Option<SseEvent>> {
+    let mute_filter = MuteFilter::cached(&*data.db).await?;
+    let Some(post) = post::Entity::find_by_id(post_id)
+        .filter(post::Model::visible_condition())
+        .filter(mute_filter.post_condition())
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+    else {
+        return Ok(None);
+    };
+    if !stream_type.includes(&post, data).await? {
+        return Ok(None);
+    }
+
+    let me = Account::local(&*data.db).await?;
+    let status = Status::from_model(post, &me, &*data.db).await?;
+    let status = serde_json::to_string(&status)
+        .context_internal_server_error("failed to serialize status")?;
+    Ok(Some(SseEvent::default().event(name).data(status)))
+}
+
+async fn to_sse_event(
+    event: Event,
+    stream_type: StreamType,
+    data: &Data<State>,
+) -> Result<Option<SseEvent>> {
+    match event {
+        Event::Update(Update::CreatePost { post_id }) => {
+            status_event("update", post_id, stream_type, data).await
+        }
+        Event::Update(Update::UpdatePost { post_id }) => {
+            status_event("status.update", post_id, stream_type, data).await
+        }
+        Event::Update(Update::DeletePost { post_id }) => Ok(Some(
+            SseEvent::default()
+                .event("delete")
+                .data(u128::from(post_id).to_string()),
+        )),
+        Event::Notification(notification) if matches!(stream_type, StreamType::User) => {
+            let me = Account::local(&*data.db).await?;
+            let Some(notification) = from_notification(notification, &me, &*data.db).await? else {
+                return Ok(None);
+            };
+            let notification = serde_json::to_string(&notification)
+                .context_internal_server_error("failed to serialize notification")?;
+            Ok(Some(
+                SseEvent::default().event("notification").data(notification),
+            ))
+        }
+        _ => Ok(None),
+    }
+}
+
+async fn stream(
+    stream_type: StreamType,
+    data: Data<State>,
+) -> Result<Sse<impl Stream<Item = std::result::Result<SseEvent, Infallible>>>> {
+    let events = listen_events(data.pg_listener().await?, data.db.clone()).await?;
+    let stopper = data.stopper.clone();
+    let events = events.filter_map(move |event| {
+        let data = data.reset_request_count();
+        async move {
+            match to_sse_event(event, stream_type, &data).await {
+                Ok(event) => event.map(Ok),
+                Err(error) => {
+                    tracing::error!("failed to construct SSE event\n{:?}", error.inner);
+                    None
+                }
+            }
+        }
+    });
+    Ok(Sse::new(stopper.stop_stream(events)))
+}
+
+async fn get_streaming_health() -> &'static str {
+    "OK"
+}
+
+#[tracing::instrument(skip(data, headers, query))]
+async fn get_user_stream(
+    data: Data<State>,
+    headers: HeaderMap,
+    extract::Query(query): extract::Query<StreamingQuery>,
+) -> Result<Sse<impl Stream<Item = std::result::Result<SseEvent, Infallible>>>> {
+    authorize(&headers, query, &data).await?;
+    stream(StreamType::User, data).await
+}
+
+#[tracing::instrument(skip(data, headers, query))]
+async fn get_public_stream(
+    data: Data<State>,
+    headers: HeaderMap,
+    extract::Query(query): extract::Query<StreamingQuery>,
+) -> Result<Sse<impl Stream<Item = std::result::Result<SseEvent, Infallible>>>> {
+    authorize(&headers, query, &data).await?;
+    stream(StreamType::Public, data).await
+}
+
+#[tracing::instrument(skip(data, headers, query))]
+async fn get_public_local_stream(
+    data: Data<State>,
+    headers: HeaderMap,
+    extract::Query(query): extract::Query<StreamingQuery>,
+) -> Result<Sse<impl Stream<Item = std::result::Result<SseEvent, Infallible>>>> {
+    authorize(&headers, query, &data).await?;
+    stream(StreamType::PublicLocal, data).await
+}
@@ -0,0 +1,100 @@
+use activitypub_federation::config::Data;
+use axum::{
+    extract::{self, OriginalUri},
+    http::HeaderMap,
+    routing, Json, Router,
+};
+use sea_orm::{
+    sea_query::{Condition, Query},
+    ColumnTrait, EntityTrait, QueryFilter,
+};
+use serde::Deserialize;
+
+use crate::{
+    dto::mastodon::{MastodonId, Status},
+    entity::{follow, post, sea_orm_active_enums::Visibility},
+    error::Result,
+    handler::api::auth::Access,
+    mute::MuteFilter,
+    scope::{Resource, Scope},
+    state::State,
+};
+
+use super::{link_header, statuses_from_models, PaginationQuery};
+
+pub(super) fn create_router() -> Router {
+    Router::new()
+        .route("/home", routing::get(get_home_timeline))
+        .route("/public", routing::get(get_public_timeline))
+}
+
+/// Queries a page of visible, unmuted statuses matching `condition`.
+async fn get_timeline(
+    data: &Data<State>,
+    uri: &OriginalUri,
+    condition: Condition,
+    query: PaginationQuery,
+) -> Result<(HeaderMap, Json<Vec<Status>>)> {
+    let mute_filter = MuteFilter::load(&*data.db).await?;
+    let select = post::Entity::find()
+        .filter(post::Model::visible_condition())
+        .filter(mute_filter.post_condition())
+        .filter(condition);
+    let posts = query.fetch(select, post::Column::Id, data).await?;
+    let headers = link_header(
+        &uri.0,
+        posts.first().map(|post| MastodonId::from(post.id)),
+        posts.last().map(|post| MastodonId::from(post.id)),
+    );
+    Ok((headers, Json(statuses_from_models(posts, data).await?)))
+}
+
+#[tracing::instrument(skip(data, access))]
+async fn get_home_timeline(
+    data: Data<State>,
+    access: Access,
+    uri: OriginalUri,
+    extract::Query(query): extract::Query<PaginationQuery>,
+) -> Result<(HeaderMap, Json<Vec<Status>>)> {
+    access.require(Scope::Read(Resource::Statuses))?;
+
+    let condition = Condition::any().add(post::Column::UserId.is_null()).add(
+        post::Column::UserId.in_subquery(
+            Query::select()
+                .column(follow::Column::ToId)
+                .from(follow::Entity)
+                .and_where(follow::Column::Accepted.eq(true))
+                .to_owned(),
+        ),
+    );
+    get_timeline(&data, &uri, condition, query).await
+}
+
+#[derive(Debug, Deserialize)]
+struct GetPublicTimelineQuery {
+    #[serde(default)]
+    local: bool,
+    #[serde(default)]
+    remote: bool,
+}
+
+#[tracing::instrument(skip(data, access))]
+async fn get_public_timeline(
+    data: Data<State>,
+    access: Access,
+    uri: OriginalUri,
+    extract::Query(query): extract::Query<PaginationQuery>,
+    extract::Query(public_query): extract::Query<GetPublicTimelineQuery>,
+) -> Result<(HeaderMap, Json<Vec<Status>>)> {
+    access.require(Scope::Read(Resource::Statuses))?;
+
+    let condition = Condition::all().add(post::Column::Visibility.eq(Visibility::Public));
+    let condition = if public_query.local {
+        condition.add(post::Column::UserId.is_null())
+    } else if public_query.remote {
+        condition.add(post::Column::UserId.is_not_null())
+    } else {
+        condition
+    };
+    get_timeline(&data, &uri, condition, query).await
+}
@@ -0,0 +1,220 @@
+use activitypub_federation::config::Data;
+use axum::{extract, routing, Json, Router};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter,
+    QueryOrder, QuerySelect, TransactionTrait,
+};
+use ulid::Ulid;
+
+use crate::{
+    dto::{CreateMute, IdPaginationQuery, IdResponse, Mute},
+    entity::{mute, post, user},
+    error::{Context, Result},
+    format_err,
+    mute::MuteFilter,
+    scope::{Resource, Scope},
+    state::State,
+};
+
+use super::auth::Access;
+
+/// Maximum number of ancestors followed to find the root of a muted thread
+const MAX_THREAD_DEPTH: usize = 64;
+
+pub(super) fn create_router() -> Router {
+    Router::new()
+        .route("/", routing::get(get_mutes).post(post_mute))
+        .route("/:id", routing::delete(delete_mute))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/mute",
+    params(IdPaginationQuery),
+    responses(
+        (status = 200, body = Vec<Mute>),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn get_mutes(
+    data: Data<State>,
+    access: Access,
+    extract::Query(query): extract::Query<IdPaginationQuery>,
+) -> Result<Json<Vec<Mute>>> {
+    access.require(Scope::Read(Resource::Mutes))?;
+
+    let pagination_query = mute::Entity::find().find_also_related(user::Entity);
+    let pagination_query = if let Some(after) = query.after {
+        pagination_query.filter(mute::Column::Id.lt(uuid::Uuid::from(after)))
+    } else {
+        pagination_query
+    };
+    let mutes = pagination_query
+        .order_by_desc(mute::Column::Id)
+        .limit(query.size)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    let mutes = mutes
+        .into_iter()
+        .filter_map(|(mute, user)| Mute::from_model(mute, user).ok())
+        .collect::<Vec<_>>();
+    Ok(Json(mutes))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/mute",
+    request_body = CreateMute,
+    responses(
+        (status = 200, body = IdResponse),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn post_mute(
+    data: Data<State>,
+    access: Access,
+    Json(req): Json<CreateMute>,
+) -> Result<Json<IdResponse>> {
+    access.require(Scope::Write(Resource::Mutes))?;
+
+    let tx = data
+        .db
+        .begin()
+        .await
+        .context_internal_server_error("failed to begin database transaction")?;
+
+    let (user_id, domain, thread_id) = match (req.user_id, req.domain, req.post_id) {
+        (Some(user_id), None, None) => {
+            let user_count = user::Entity::find_by_id(user_id)
+                .count(&tx)
+                .await
+                .context_internal_server_error("failed to query database")?;
+            if user_count == 0 {
+                return Err(format_err!(NOT_FOUND, "user not found"));
+            }
+            (Some(user_id.into()), None, None)
+        }
+        (None, Some(domain), None) => {
+            let domain = domain.trim().to_lowercase();
+            if domain.is_empty() {
+                return Err(format_err!(BAD_REQUEST, "invalid domain"));
+            }
+            (None, Some(domain), None)
+        }
+        (None, None, Some(post_id)) => {
+            // Mute from the root so that the whole conversation is hidden
+            let mut post = post::Entity::find_by_id(post_id)
+                .one(&tx)
+                .await
+                .context_internal_server_error("failed to query database")?
+                .context_not_found("post not found")?;
+            // Bounded, as remote replies may form a cycle
+            for _ in 0..MAX_THREAD_DEPTH {
+                let Some(reply_id) = post.reply_id else {
+                    break;
+                };
+                let Some(parent) = post::Entity::find_by_id(reply_id)
+                    .one(&tx)
+                    .await
+                    .context_internal_server_error("failed to query database")?
+                else {
+                    break;
+                };
+                post = parent;
+            }
+            (None, None, Some(post.id))
+        }
+        _ => {
+            return Err(format_err!(
+                BAD_REQUEST,
+                "exactly one of userId, domain and postId must be given"
+            ))
+        }
+    };
+
+    if let Some(expires_at) = req.expires_at {
+        if expires_at <= Utc::now() {
+            return Err(format_err!(BAD_REQUEST, "expiry must be in the future"));
+        }
+    }
+
+    let existing = mute::Entity::find()
+        .filter(match (user_id, &domain, thread_id) {
+            (Some(user_id), _, _) => mute::Column::UserId.eq(user_id),
+            (_, Some(domain), _) => mute::Column::Domain.eq(domain.as_str()),
+            (_, _, Some(thread_id)) => mute::Column::ThreadId.eq(thread_id),
+            _ => unreachable!(),
+        })
+        .one(&tx)
+        .await
+        .context_internal_server_error("failed to query database")?;
+
+    let mute = if let Some(existing) = existing {
+        let mute_activemodel = mute::ActiveModel {
+            id: ActiveValue::Unchanged(existing.id),
+            expires_at: ActiveValue::Set(req.expires_at),
+            ..Default::default()
+        };
+        mute_activemodel
+            .update(&tx)
+            .await
+            .context_internal_server_error("failed to update database")?
+    } else {
+        let mute_activemodel = mute::ActiveModel {
+            id: ActiveValue::Set(Ulid::new().into()),
+            user_id: ActiveValue::Set(user_id),
+            domain: ActiveValue::Set(domain),
+            thread_id: ActiveValue::Set(thread_id),
+            created_at: ActiveValue::Set(Utc::now().fixed_offset()),
+            expires_at: ActiveValue::Set(req.expires_at),
+        };
+        mute_activemodel
+            .insert(&tx)
+            .await
+            .context_internal_server_error("failed to insert to database")?
+    };
+
+    tx.commit()
+        .await
+        .context_internal_server_error("failed to commit database transaction")?;
+    MuteFilter::invalidate();
+
+    Ok(Json(IdResponse { id: mute.id.into() }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/mute/{id}",
+    params(
+        ("id" = String, format = "ulid"),
+    ),
+    responses(
+        (status = 200),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn delete_mute(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<Ulid>,
+) -> Result<()> {
+    access.require(Scope::Write(Resource::Mutes))?;
+
+    mute::Entity::delete_by_id(id)
+        .exec(&*data.db)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+    MuteFilter::invalidate();
+    Ok(())
+}
@@ -7,7 +7,9 @@ use crate::{
     dto::IdPaginationQuery,
     entity::notification,
     error::{Context, Error},
+    mute::MuteFilter,
     queue::Notification,
+    scope::{Resource, Scope},
     state::State,
 };
 
@@ -30,13 +32,17 @@ pub(super) fn create_router() -> Router {
         ("access_key" = []),
     ),
 )]
-#[tracing::instrument(skip(data, _access))]
+#[tracing::instrument(skip(data, access))]
 async fn get_notifications(
     data: Data<State>,
-    _access: Access,
+    access: Access,
     extract::Query(query): extract::Query<IdPaginationQuery>,
 ) -> Result<Json<Vec<Notification>>, Error> {
-    let pagination_query = notification::Entity::find();
+    access.require(Scope::Read(Resource::Notifications))?;
+
+    let mute_filter = MuteFilter::load(&*data.db).await?;
+    let pagination_query =
+        notification::Entity::find().filter(mute_filter.notification_condition());
     let pagination_query = if let Some(after) = query.after {
         pagination_query.filter(notification::Column::Id.lt(uuid::Uuid::from(after)))
     } else {
@@ -73,12 +79,14 @@ async fn get_notifications(
         ("access_key" = []),
     ),
 )]
-#[tracing::instrument(skip(data, _access))]
+#[tracing::instrument(skip(data, access))]
 async fn get_notification(
     data: Data<State>,
-    _access: Access,
+    access: Access,
     extract::Path(id): extract::Path<Ulid>,
 ) -> Result<Json<Notification>, Error> {
+    access.require(Scope::Read(Resource::Notifications))?;
+
     let notification = notification::Entity::find_by_id(id)
         .one(&*data.db)
         .await
@@ -1,32 +1,84 @@
+use std::{collections::HashMap, time::Duration};
+
 use activitypub_federation::{config::Data, traits::Object};
-use axum::{extract, routing, Json, Router};
+use axum::{
+    extract,
+    handler::Handler,
+    http::Request,
+    middleware::{self, Next},
+    response::Response,
+    routing, Json, Router,
+};
 use chrono::Utc;
 use futures_util::{stream::FuturesOrdered, TryStreamExt};
 use sea_orm::{
-    ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, ModelTrait, PaginatorTrait,
-    QueryFilter, QueryOrder, QuerySelect, TransactionTrait,
+    sea_query::OnConflict, ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait,
+    DatabaseTransaction, DbBackend, EntityTrait, ModelTrait, PaginatorTrait, QueryFilter,
+    QueryOrder, QuerySelect, Statement, TransactionTrait,
 };
+use serde::Deserialize;
 use ulid::Ulid;
 use url::Url;
+use utoipa::IntoParams;
 
 use crate::{
-    ap::{delete::Delete, like::Like, undo::Undo},
-    dto::{CreatePost, CreateReaction, IdPaginationQuery, IdResponse, Post, Reaction, Visibility},
+    ap::{
+        delete::Delete,
+        generate_object_id,
+        like::Like,
+        note::{Note, UpdateNote},
+        person::{LocalPerson, PersonUpdate},
+        undo::Undo,
+        NoteOrAnnounce,
+    },
+    dto::{
+        CreatePost, CreateReaction, CreateVote, IdPaginationQuery, IdResponse, Mention, Post,
+        PostContext, PostEdit, PostFormat, Reaction, UpdatePost, Visibility,
+    },
     entity::{
-        emoji, hashtag, local_file, mention, post, post_emoji, reaction, sea_orm_active_enums, user,
+        bookmark, emoji, hashtag, local_file, mention, pin, poll, poll_option, poll_vote, post,
+        post_edit, post_emoji, reaction, scheduled_post, sea_orm_active_enums, user,
     },
     error::{Context, Result},
-    format_err,
+    format_err, markup,
+    mute::MuteFilter,
+    queue::{Event, Update},
+    rate_limit,
+    sanitize::{sanitize_html, sanitize_text},
+    scope::{Resource, Scope},
     state::State,
     util::get_follower_inboxes,
 };
 
 use super::auth::Access;
 
+/// Maximum number of our posts pinned to the profile at once.
+const MAX_PINNED_POSTS: u64 = 5;
+
+/// Time budget for fetching remote replies while building a post context
+const REMOTE_REPLIES_TIMEOUT: Duration = Duration::from_secs(10);
+
 pub(super) fn create_router() -> Router {
     Router::new()
         .route("/", routing::get(get_posts).post(post_post))
-        .route("/:id", routing::get(get_post).delete(delete_post))
+        .route(
+            "/:id",
+            routing::get(get_post).put(put_post).delete(delete_post),
+        )
+        .route("/:id/edit", routing::get(get_post_edits))
+        .route(
+            "/:id/context",
+            routing::get(get_post_context.layer(middleware::from_fn(limit_fetch_remote))),
+        )
+        .route("/:id/vote", routing::post(post_post_vote))
+        .route(
+            "/:id/pin",
+            routing::post(post_post_pin).delete(delete_post_pin),
+        )
+        .route(
+            "/:id/bookmark",
+            routing::post(post_post_bookmark).delete(delete_post_bookmark),
+        )
         .route(
             "/:id/reaction",
             routing::get(get_post_reactions)
@@ -46,13 +98,16 @@ pub(super) fn create_router() -> Router {
         ("access_key" = []),
     ),
 )]
-#[tracing::instrument(skip(data, _access))]
+#[tracing::instrument(skip(data, access))]
 async fn get_posts(
     data: Data<State>,
-    _access: Access,
+    access: Access,
     extract::Query(query): extract::Query<IdPaginationQuery>,
 ) -> Result<Json<Vec<Post>>> {
-    let pagination_query = post::Entity::find();
+    access.require(Scope::Read(Resource::Statuses))?;
+
+    let mute_filter = MuteFilter::load(&*data.db).await?;
+    let pagination_query = post::Entity::find().filter(mute_filter.post_condition());
     let pagination_query = if let Some(after) = query.after {
         pagination_query.filter(post::Column::Id.lt(uuid::Uuid::from(after)))
     } else {
@@ -84,21 +139,135 @@ async fn get_posts(
         ("access_key" = []),
     ),
 )]
-#[tracing::instrument(skip(data, _access, req))]
+#[tracing::instrument(skip(data, access, req))]
 async fn post_post(
     data: Data<State>,
-    _access: Access,
+    access: Access,
     Json(req): Json<CreatePost>,
 ) -> Result<Json<IdResponse>> {
+    access.require(Scope::Write(Resource::Statuses))?;
+
+    validate_post(&req)?;
+
+    if let Some(scheduled_at) = req.scheduled_at {
+        let id = scheduled_post::Model::schedule(scheduled_at, req, &*data.db).await?;
+        return Ok(Json(IdResponse { id }));
+    }
+
+    let id = create_post(req, &data).await?;
+    Ok(Json(IdResponse { id }))
+}
+
+/// Checks `req` for errors which do not depend on the database.
+pub(crate) fn validate_post(req: &CreatePost) -> Result<()> {
+    if let Some(scheduled_at) = req.scheduled_at {
+        if scheduled_at <= Utc::now() {
+            return Err(format_err!(
+                BAD_REQUEST,
+                "post must be scheduled in the future"
+            ));
+        }
+    }
+    if let Some(poll) = &req.poll {
+        if poll.options.len() < 2 {
+            return Err(format_err!(
+                BAD_REQUEST,
+                "poll must have at least two options"
+            ));
+        }
+        if poll.options.iter().any(|option| option.trim().is_empty()) {
+            return Err(format_err!(BAD_REQUEST, "poll option must not be empty"));
+        }
+        if poll
+            .ends_at
+            .map(|ends_at| ends_at <= Utc::now())
+            .unwrap_or(false)
+        {
+            return Err(format_err!(BAD_REQUEST, "poll must end in the future"));
+        }
+        if let (Some(ends_at), Some(scheduled_at)) = (poll.ends_at, req.scheduled_at) {
+            if ends_at <= scheduled_at {
+                return Err(format_err!(
+                    BAD_REQUEST,
+                    "poll must end after the post is published"
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Inserts our post and sends it, ignoring `req.scheduled_at`.
+#[tracing::instrument(skip(data, req))]
+pub(crate) async fn create_post(req: CreatePost, data: &Data<State>) -> Result<Ulid> {
+    let prepared = prepare_post(req, data).await?;
+
     let tx = data
         .db
         .begin()
         .await
         .context_internal_server_error("failed to begin database transaction")?;
+    let (post, mentions) = insert_post(prepared, &tx).await?;
+    tx.commit()
+        .await
+        .context_internal_server_error("failed to commmit database transaction")?;
+
+    let post_id = post.id.into();
+    send_post(post, mentions, data).await?;
+
+    Ok(post_id)
+}
+
+/// `CreatePost` with its text rendered, ready to be inserted.
+pub(crate) struct PreparedPost {
+    req: CreatePost,
+    text: PostText,
+    mentions: Vec<Mention>,
+    emojis: Vec<String>,
+    hashtags: Vec<String>,
+}
+
+/// Renders text of `req`, which may resolve mentioned users and thus is done outside transactions.
+pub(crate) async fn prepare_post(mut req: CreatePost, data: &Data<State>) -> Result<PreparedPost> {
+    let mut mentions = std::mem::take(&mut req.mentions);
+    let mut emojis = std::mem::take(&mut req.emojis);
+    let mut hashtags = std::mem::take(&mut req.hashtags);
+    let text = render_text(
+        std::mem::take(&mut req.text),
+        req.format,
+        &mut mentions,
+        &mut emojis,
+        &mut hashtags,
+        data,
+    )
+    .await?;
+
+    Ok(PreparedPost {
+        req,
+        text,
+        mentions,
+        emojis,
+        hashtags,
+    })
+}
+
+/// Inserts our post with its files, tags and poll, returning it with its mentions.
+pub(crate) async fn insert_post(
+    prepared: PreparedPost,
+    tx: &DatabaseTransaction,
+) -> Result<(post::Model, Vec<Mention>)> {
+    let PreparedPost {
+        req,
+        text,
+        mentions,
+        emojis,
+        hashtags,
+    } = prepared;
 
     if let Some(reply_id) = req.reply_id {
         let reply_post_count = post::Entity::find_by_id(reply_id)
-            .count(&tx)
+            .count(tx)
             .await
             .context_internal_server_error("failed to request database")?;
         if reply_post_count == 0 {
@@ -107,7 +276,7 @@ async fn post_post(
     }
     if let Some(repost_id) = req.repost_id {
         let repost_post_count = post::Entity::find_by_id(repost_id)
-            .count(&tx)
+            .count(tx)
             .await
             .context_internal_server_error("failed to request database")?;
         if repost_post_count == 0 {
@@ -115,21 +284,14 @@ async fn post_post(
         }
     }
 
-    let emojis = emoji::Entity::find()
-        .filter(emoji::Column::Name.is_in(req.emojis))
-        .find_also_related(local_file::Entity)
-        .all(&tx)
-        .await
-        .context_internal_server_error("failed to query database")?;
-
     let id = Ulid::new();
     let post_activemodel = post::ActiveModel {
         id: ActiveValue::Set(id.into()),
         created_at: ActiveValue::Set(Utc::now().fixed_offset()),
         reply_id: ActiveValue::Set(req.reply_id.map(Into::into)),
         repost_id: ActiveValue::Set(req.repost_id.map(Into::into)),
-        text: ActiveValue::Set(req.text),
-        title: ActiveValue::Set(req.title),
+        text: ActiveValue::Set(text.text),
+        title: ActiveValue::Set(req.title.as_deref().map(sanitize_text)),
         user_id: ActiveValue::Set(None),
         visibility: ActiveValue::Set(match req.visibility {
             Visibility::Public => sea_orm_active_enums::Visibility::Public,
@@ -139,124 +301,592 @@ async fn post_post(
         }),
         is_sensitive: ActiveValue::Set(req.is_sensitive),
         uri: ActiveValue::Set(post::Model::ap_id_from_id(id)?.to_string()),
-        source_content: ActiveValue::Set(None), // TODO: Set to MFM?
-        source_media_type: ActiveValue::Set(None),
+        source_content: ActiveValue::Set(text.source_content),
+        source_media_type: ActiveValue::Set(text.source_media_type),
+        updated_at: ActiveValue::Set(None),
     };
     let post = post_activemodel
-        .insert(&tx)
+        .insert(tx)
         .await
         .context_internal_server_error("failed to insert to database")?;
 
     for (idx, local_file_id) in req.files.into_iter().enumerate() {
         let file = local_file::Entity::find_by_id(local_file_id)
-            .one(&tx)
+            .one(tx)
             .await
             .context_internal_server_error("failed to query database")?
             .context_not_found("file not found")?;
-        file.attach_to_post(post.id.into(), idx as u8, &tx).await?;
+        file.attach_to_post(post.id.into(), idx as u8, tx).await?;
     }
 
-    let emojis = emojis
-        .into_iter()
-        .filter_map(|(emoji, file)| file.map(|file| (emoji, file)))
-        .filter_map(|(emoji, file)| {
-            Some(post_emoji::ActiveModel {
+    insert_post_tags(post.id, emojis, &mentions, hashtags, tx).await?;
+
+    if let Some(poll) = req.poll {
+        let poll_activemodel = poll::ActiveModel {
+            post_id: ActiveValue::Set(post.id),
+            ends_at: ActiveValue::Set(poll.ends_at),
+            is_multiple: ActiveValue::Set(poll.is_multiple),
+            voters_count: ActiveValue::Set(Some(0)),
+            is_ended: ActiveValue::Set(false),
+        };
+        poll_activemodel
+            .insert(tx)
+            .await
+            .context_internal_server_error("failed to insert to database")?;
+        let options = poll
+            .options
+            .into_iter()
+            .enumerate()
+            .map(|(idx, name)| poll_option::ActiveModel {
                 post_id: ActiveValue::Set(post.id),
-                uri: ActiveValue::Set(emoji.ap_id().ok()?.to_string()),
-                name: ActiveValue::Set(emoji.name),
-                media_type: ActiveValue::Set(file.media_type),
-                image_url: ActiveValue::Set(file.url),
+                order: ActiveValue::Set(idx as i16),
+                name: ActiveValue::Set(sanitize_text(&name)),
+                votes_count: ActiveValue::Set(0),
             })
-        })
-        .collect::<Vec<_>>();
-    if !emojis.is_empty() {
-        post_emoji::Entity::insert_many(emojis)
-            .exec(&tx)
+            .collect::<Vec<_>>();
+        poll_option::Entity::insert_many(options)
+            .exec(tx)
             .await
             .context_internal_server_error("failed to insert to database")?;
     }
 
-    let mentions = req
-        .mentions
-        .iter()
-        .map(|mention| mention::ActiveModel {
-            post_id: ActiveValue::Set(post.id),
-            user_uri: ActiveValue::Set(mention.user_uri.to_string()),
-            name: ActiveValue::Set(mention.name.clone()),
-        })
-        .collect::<Vec<_>>();
-    if !mentions.is_empty() {
-        mention::Entity::insert_many(mentions)
-            .exec(&tx)
-            .await
-            .context_internal_server_error("failed to insert to database")?;
+    Ok((post, mentions))
+}
+
+/// Sends our newly inserted post to followers, or to mentioned users if it is a direct message.
+pub(crate) async fn send_post(
+    post: post::Model,
+    mentions: Vec<Mention>,
+    data: &Data<State>,
+) -> Result<()> {
+    let visibility = post.visibility.clone();
+
+    let post = post.into_json(data).await?;
+
+    let inboxes = match visibility {
+        sea_orm_active_enums::Visibility::Public
+        | sea_orm_active_enums::Visibility::Home
+        | sea_orm_active_enums::Visibility::Followers => get_follower_inboxes(&*data.db).await?,
+        sea_orm_active_enums::Visibility::DirectMessage => mentions
+            .into_iter()
+            .map(|mention| mention.user_uri)
+            .collect::<Vec<_>>(),
+    };
+
+    post.send(data, inboxes).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/post/{id}",
+    params(
+        ("id" = String, format = "ulid"),
+    ),
+    responses(
+        (status = 200, body = Post),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn get_post(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<Ulid>,
+) -> Result<Json<Post>> {
+    access.require(Scope::Read(Resource::Statuses))?;
+
+    let post = post::Entity::find_by_id(id)
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("post not found")?;
+    Ok(Json(Post::from_model(post, &*data.db).await?))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/post/{id}",
+    params(
+        ("id" = String, format = "ulid"),
+    ),
+    request_body = UpdatePost,
+    responses(
+        (status = 200),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access, req))]
+async fn put_post(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<Ulid>,
+    Json(req): Json<UpdatePost>,
+) -> Result<()> {
+    access.require(Scope::Write(Resource::Statuses))?;
+
+    let mut mentions = req.mentions;
+    let mut emojis = req.emojis;
+    let mut hashtags = req.hashtags;
+    let text = render_text(
+        req.text,
+        req.format,
+        &mut mentions,
+        &mut emojis,
+        &mut hashtags,
+        &data,
+    )
+    .await?;
+
+    let tx = data
+        .db
+        .begin()
+        .await
+        .context_internal_server_error("failed to begin database transaction")?;
+
+    let existing = post::Entity::find_by_id(id)
+        .one(&tx)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("post not found")?;
+    if existing.user_id.is_some() {
+        return Err(format_err!(FORBIDDEN, "cannot edit others' post"));
+    }
+    if existing.repost_id.is_some() && existing.text.is_empty() {
+        return Err(format_err!(BAD_REQUEST, "cannot edit repost"));
     }
 
-    let hashtags = req
-        .hashtags
-        .into_iter()
-        .map(|hashtag| hashtag::ActiveModel {
-            post_id: ActiveValue::Set(post.id),
-            name: ActiveValue::Set(hashtag),
-        })
-        .collect::<Vec<_>>();
-    if !hashtags.is_empty() {
-        hashtag::Entity::insert_many(hashtags)
-            .exec(&tx)
+    existing.save_edit(&tx).await?;
+
+    let post_activemodel = post::ActiveModel {
+        id: ActiveValue::Unchanged(existing.id),
+        text: ActiveValue::Set(text.text),
+        title: ActiveValue::Set(req.title.as_deref().map(sanitize_text)),
+        is_sensitive: ActiveValue::Set(req.is_sensitive),
+        source_content: ActiveValue::Set(text.source_content),
+        source_media_type: ActiveValue::Set(text.source_media_type),
+        updated_at: ActiveValue::Set(Some(Utc::now().fixed_offset())),
+        ..Default::default()
+    };
+    let post = post_activemodel
+        .update(&tx)
+        .await
+        .context_internal_server_error("failed to update database")?;
+
+    let existing_files = post
+        .find_related(local_file::Entity)
+        .all(&tx)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    for file in existing_files {
+        if !req.files.contains(&file.id.into()) {
+            let file_activemodel = local_file::ActiveModel {
+                id: ActiveValue::Unchanged(file.id),
+                post_id: ActiveValue::Set(None),
+                order: ActiveValue::Set(None),
+                ..Default::default()
+            };
+            file_activemodel
+                .update(&tx)
+                .await
+                .context_internal_server_error("failed to update database")?;
+        }
+    }
+    for (idx, local_file_id) in req.files.into_iter().enumerate() {
+        let file = local_file::Entity::find_by_id(local_file_id)
+            .one(&tx)
             .await
-            .context_internal_server_error("failed to insert to database")?;
+            .context_internal_server_error("failed to query database")?
+            .context_not_found("file not found")?;
+        file.attach_to_post(post.id.into(), idx as u8, &tx).await?;
     }
 
+    post_emoji::Entity::delete_many()
+        .filter(post_emoji::Column::PostId.eq(post.id))
+        .exec(&tx)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+    mention::Entity::delete_many()
+        .filter(mention::Column::PostId.eq(post.id))
+        .exec(&tx)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+    hashtag::Entity::delete_many()
+        .filter(hashtag::Column::PostId.eq(post.id))
+        .exec(&tx)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+    insert_post_tags(post.id, emojis, &mentions, hashtags, &tx).await?;
+
     tx.commit()
         .await
-        .context_internal_server_error("failed to commmit database transaction")?;
+        .context_internal_server_error("failed to commit database transaction")?;
 
-    let post_id = post.id.into();
     let visibility = post.visibility.clone();
 
-    let post = post.into_json(&data).await?;
+    let NoteOrAnnounce::Note(note) = post.into_json(&data).await? else {
+        return Err(format_err!(
+            INTERNAL_SERVER_ERROR,
+            "edited post is not a note"
+        ));
+    };
 
     let inboxes = match visibility {
         sea_orm_active_enums::Visibility::Public
         | sea_orm_active_enums::Visibility::Home
         | sea_orm_active_enums::Visibility::Followers => get_follower_inboxes(&*data.db).await?,
-        sea_orm_active_enums::Visibility::DirectMessage => req
-            .mentions
+        sea_orm_active_enums::Visibility::DirectMessage => mentions
             .into_iter()
             .map(|mention| mention.user_uri)
             .collect::<Vec<_>>(),
     };
 
-    post.send(&data, inboxes).await?;
+    let update = UpdateNote::new(*note)?;
+    update.send(&data, inboxes).await?;
+
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/post/{id}/edit",
+    params(
+        ("id" = String, format = "ulid"),
+    ),
+    responses(
+        (status = 200, body = Vec<PostEdit>),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn get_post_edits(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<Ulid>,
+) -> Result<Json<Vec<PostEdit>>> {
+    access.require(Scope::Read(Resource::Statuses))?;
+
+    let existing_post_count = post::Entity::find_by_id(id)
+        .count(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+
+    if existing_post_count == 0 {
+        return Err(format_err!(NOT_FOUND, "post not found"));
+    }
+
+    let post_edits = post_edit::Entity::find()
+        .filter(post_edit::Column::PostId.eq(uuid::Uuid::from(id)))
+        .order_by_desc(post_edit::Column::CreatedAt)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    let post_edits = post_edits
+        .into_iter()
+        .map(PostEdit::from_model)
+        .collect::<Vec<_>>();
+
+    Ok(Json(post_edits))
+}
+
+fn default_context_depth() -> u32 {
+    16
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct GetPostContextQuery {
+    /// Maximum number of levels to follow in each direction
+    #[param(default = 16, maximum = 64)]
+    #[serde(default = "default_context_depth")]
+    pub(super) depth: u32,
+    /// Fetch the remote `replies` collection before building the context
+    #[serde(default)]
+    pub(super) fetch_remote: bool,
+}
+
+/// Applies [`rate_limit::limit_resolve`] to context requests which fetch remote replies.
+async fn limit_fetch_remote<B>(req: Request<B>, next: Next<B>) -> Response {
+    let fetch_remote = extract::Query::<GetPostContextQuery>::try_from_uri(req.uri())
+        .map(|extract::Query(query)| query.fetch_remote)
+        .unwrap_or_default();
+    if fetch_remote {
+        rate_limit::limit_resolve(req, next).await
+    } else {
+        next.run(req).await
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/post/{id}/context",
+    params(
+        GetPostContextQuery,
+        ("id" = String, format = "ulid"),
+    ),
+    responses(
+        (status = 200, body = PostContext),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+pub(super) async fn get_post_context(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<Ulid>,
+    extract::Query(query): extract::Query<GetPostContextQuery>,
+) -> Result<Json<PostContext>> {
+    access.require(Scope::Read(Resource::Statuses))?;
+
+    let post = post::Entity::find_by_id(id)
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("post not found")?;
+
+    if query.fetch_remote {
+        match tokio::time::timeout(REMOTE_REPLIES_TIMEOUT, post.fetch_remote_replies(&data)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(error)) => {
+                tracing::warn!("failed to fetch remote replies\n{:?}", error.inner);
+            }
+            Err(_) => tracing::warn!("timed out fetching remote replies"),
+        }
+    }
+
+    let depth = query.depth.min(64) as i32;
+    let ancestor_ids = query_post_ids(
+        r#"WITH RECURSIVE "ancestor" AS (
+            SELECT "reply_id" AS "id", 1 AS "depth" FROM "post"
+            WHERE "id" = $1 AND "reply_id" IS NOT NULL
+            UNION ALL
+            SELECT "post"."reply_id", "ancestor"."depth" + 1 FROM "post"
+            JOIN "ancestor" ON "post"."id" = "ancestor"."id"
+            WHERE "post"."reply_id" IS NOT NULL AND "ancestor"."depth" < $2
+        ) SELECT "id" FROM "ancestor" ORDER BY "depth" DESC"#,
+        post.id,
+        depth,
+        &*data.db,
+    )
+    .await?;
+    let descendant_ids = query_post_ids(
+        r#"WITH RECURSIVE "descendant" AS (
+            SELECT "id", ARRAY["id"] AS "path", 1 AS "depth" FROM "post"
+            WHERE "reply_id" = $1
+            UNION ALL
+            SELECT "post"."id", "descendant"."path" || "post"."id", "descendant"."depth" + 1
+            FROM "post"
+            JOIN "descendant" ON "post"."reply_id" = "descendant"."id"
+            WHERE "descendant"."depth" < $2
+        ) SELECT "id" FROM "descendant" ORDER BY "path""#,
+        post.id,
+        depth,
+        &*data.db,
+    )
+    .await?;
+
+    let mute_filter = MuteFilter::load(&*data.db).await?;
+    let mut posts = post::Entity::find()
+        .filter(
+            post::Column::Id.is_in(
+                ancestor_ids
+                    .iter()
+                    .chain(descendant_ids.iter())
+                    .copied()
+                    .collect::<Vec<_>>(),
+            ),
+        )
+        .filter(post::Model::visible_condition())
+        .filter(mute_filter.post_condition())
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .into_iter()
+        .map(|post| (post.id, post))
+        .collect::<HashMap<_, _>>();
+
+    let ancestors = ancestor_ids
+        .iter()
+        .filter_map(|id| posts.remove(id))
+        .map(|post| Post::from_model(post, &*data.db))
+        .collect::<FuturesOrdered<_>>()
+        .try_collect()
+        .await?;
+    let descendants = descendant_ids
+        .iter()
+        .filter_map(|id| posts.remove(id))
+        .map(|post| Post::from_model(post, &*data.db))
+        .collect::<FuturesOrdered<_>>()
+        .try_collect()
+        .await?;
+
+    Ok(Json(PostContext {
+        ancestors,
+        descendants,
+    }))
+}
 
-    Ok(Json(IdResponse { id: post_id }))
+async fn query_post_ids(
+    sql: &str,
+    post_id: uuid::Uuid,
+    depth: i32,
+    db: &impl ConnectionTrait,
+) -> Result<Vec<uuid::Uuid>> {
+    let statement =
+        Statement::from_sql_and_values(DbBackend::Postgres, sql, [post_id.into(), depth.into()]);
+    let rows = db
+        .query_all(statement)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    rows.into_iter()
+        .map(|row| {
+            row.try_get::<uuid::Uuid>("", "id")
+                .context_internal_server_error("failed to query database")
+        })
+        .collect()
 }
 
 #[utoipa::path(
-    get,
-    path = "/api/post/{id}",
+    post,
+    path = "/api/post/{id}/vote",
     params(
         ("id" = String, format = "ulid"),
     ),
+    request_body = CreateVote,
     responses(
-        (status = 200, body = Post),
+        (status = 200),
     ),
     security(
         ("access_key" = []),
     ),
 )]
-#[tracing::instrument(skip(data, _access))]
-async fn get_post(
+#[tracing::instrument(skip(data, access))]
+pub(super) async fn post_post_vote(
     data: Data<State>,
-    _access: Access,
+    access: Access,
     extract::Path(id): extract::Path<Ulid>,
-) -> Result<Json<Post>> {
-    let post = post::Entity::find_by_id(id)
+    Json(req): Json<CreateVote>,
+) -> Result<()> {
+    access.require(Scope::Write(Resource::Statuses))?;
+
+    let (poll, post) = poll::Entity::find_by_id(uuid::Uuid::from(id))
+        .find_also_related(post::Entity)
         .one(&*data.db)
         .await
         .context_internal_server_error("failed to query database")?
-        .context_not_found("post not found")?;
-    Ok(Json(Post::from_model(post, &*data.db).await?))
+        .context_not_found("poll not found")?;
+    let post = post.context_internal_server_error("post not found")?;
+
+    if poll.has_ended() {
+        return Err(format_err!(BAD_REQUEST, "poll has ended"));
+    }
+
+    let options = poll
+        .find_related(poll_option::Entity)
+        .order_by_asc(poll_option::Column::Order)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    let mut choices = req.choices;
+    choices.sort_unstable();
+    choices.dedup();
+    if choices.is_empty() || (!poll.is_multiple && choices.len() > 1) {
+        return Err(format_err!(BAD_REQUEST, "invalid number of choices"));
+    }
+    let chosen_options = choices
+        .iter()
+        .map(|choice| {
+            options
+                .get(*choice as usize)
+                .context_bad_request("poll option not found")
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let tx = data
+        .db
+        .begin()
+        .await
+        .context_internal_server_error("failed to begin database transaction")?;
+    // lock the poll so that concurrent votes see each other
+    poll::Entity::find_by_id(poll.post_id)
+        .lock_exclusive()
+        .one(&tx)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    let existing_vote_count = poll
+        .find_related(poll_vote::Entity)
+        .filter(poll_vote::Column::UserId.is_null())
+        .count(&tx)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    if existing_vote_count > 0 {
+        return Err(format_err!(CONFLICT, "already voted poll"));
+    }
+    let orders = chosen_options
+        .iter()
+        .map(|option| option.order)
+        .collect::<Vec<_>>();
+    poll.save_votes(None, &orders, &tx).await?;
+    tx.commit()
+        .await
+        .context_internal_server_error("failed to commit database transaction")?;
+
+    let event = Event::Update(Update::UpdatePost {
+        post_id: post.id.into(),
+    });
+    event.send(&*data.db).await?;
+
+    if let Some(user_id) = post.user_id {
+        let user = user::Entity::find_by_id(user_id)
+            .one(&*data.db)
+            .await
+            .context_internal_server_error("failed to query database")?
+            .context_internal_server_error("user not found")?;
+        let user_uri = Url::parse(&user.uri).context_internal_server_error("malformed user URI")?;
+        let inbox =
+            Url::parse(&user.inbox).context_internal_server_error("malformed user inbox")?;
+        let post_uri = Url::parse(&post.uri).context_internal_server_error("malformed post URI")?;
+
+        for option in chosen_options {
+            let note = Note {
+                ty: Default::default(),
+                id: generate_object_id()?.into(),
+                attributed_to: LocalPerson::id(),
+                quote_url: None,
+                published: Utc::now().fixed_offset(),
+                updated: None,
+                to: vec![user_uri.clone()],
+                cc: Vec::new(),
+                summary: None,
+                name: Some(option.name.clone()),
+                content: String::new(),
+                source: None,
+                in_reply_to: Some(post_uri.clone().into()),
+                attachment: Vec::new(),
+                sensitive: false,
+                tag: Vec::new(),
+                one_of: None,
+                any_of: None,
+                end_time: None,
+                closed: None,
+                voters_count: None,
+            };
+            NoteOrAnnounce::Note(Box::new(note))
+                .send(&data, vec![inbox.clone()])
+                .await?;
+        }
+    } else {
+        poll.send_update(&data).await?;
+    }
+
+    Ok(())
 }
 
 #[utoipa::path(
@@ -272,12 +902,14 @@ async fn get_post(
         ("access_key" = []),
     ),
 )]
-#[tracing::instrument(skip(data, _access))]
-async fn delete_post(
+#[tracing::instrument(skip(data, access))]
+pub(super) async fn delete_post(
     data: Data<State>,
-    _access: Access,
+    access: Access,
     extract::Path(id): extract::Path<Ulid>,
 ) -> Result<()> {
+    access.require(Scope::Write(Resource::Statuses))?;
+
     let tx = data
         .db
         .begin()
@@ -350,12 +982,14 @@ async fn delete_post(
         ("access_key" = []),
     ),
 )]
-#[tracing::instrument(skip(data, _access))]
+#[tracing::instrument(skip(data, access))]
 async fn get_post_reactions(
     data: Data<State>,
-    _access: Access,
+    access: Access,
     extract::Path(id): extract::Path<Ulid>,
 ) -> Result<Json<Vec<Reaction>>> {
+    access.require(Scope::Read(Resource::Statuses))?;
+
     let existing_post_count = post::Entity::find_by_id(id)
         .count(&*data.db)
         .await
@@ -393,13 +1027,15 @@ async fn get_post_reactions(
         ("access_key" = []),
     ),
 )]
-#[tracing::instrument(skip(data, _access))]
-async fn post_post_reaction(
+#[tracing::instrument(skip(data, access))]
+pub(super) async fn post_post_reaction(
     data: Data<State>,
-    _access: Access,
+    access: Access,
     extract::Path(id): extract::Path<Ulid>,
     Json(req): Json<CreateReaction>,
 ) -> Result<()> {
+    access.require(Scope::Write(Resource::Favourites))?;
+
     let tx = data
         .db
         .begin()
@@ -487,12 +1123,14 @@ async fn post_post_reaction(
         ("access_key" = []),
     ),
 )]
-#[tracing::instrument(skip(data, _access))]
-async fn delete_post_reaction(
+#[tracing::instrument(skip(data, access))]
+pub(super) async fn delete_post_reaction(
     data: Data<State>,
-    _access: Access,
+    access: Access,
     extract::Path(id): extract::Path<Ulid>,
 ) -> Result<()> {
+    access.require(Scope::Write(Resource::Favourites))?;
+
     let tx = data
         .db
         .begin()
@@ -537,3 +1175,312 @@ async fn delete_post_reaction(
 
     Ok(())
 }
+
+#[utoipa::path(
+    post,
+    path = "/api/post/{id}/pin",
+    params(
+        ("id" = String, format = "ulid"),
+    ),
+    responses(
+        (status = 200),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+pub(super) async fn post_post_pin(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<Ulid>,
+) -> Result<()> {
+    access.require(Scope::Write(Resource::Accounts))?;
+
+    let tx = data
+        .db
+        .begin()
+        .await
+        .context_internal_server_error("failed to begin database transaction")?;
+
+    let post = post::Entity::find_by_id(id)
+        .one(&tx)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("post not found")?;
+    if post.user_id.is_some() {
+        return Err(format_err!(BAD_REQUEST, "cannot pin remote post"));
+    }
+    if !matches!(
+        post.visibility,
+        sea_orm_active_enums::Visibility::Public | sea_orm_active_enums::Visibility::Home
+    ) {
+        return Err(format_err!(BAD_REQUEST, "cannot pin non-public post"));
+    }
+
+    let existing_pin_count = post
+        .find_related(pin::Entity)
+        .count(&tx)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    if existing_pin_count > 0 {
+        return Err(format_err!(CONFLICT, "already pinned post"));
+    }
+    let pin_count = pin::Entity::find()
+        .count(&tx)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    if pin_count >= MAX_PINNED_POSTS {
+        return Err(format_err!(BAD_REQUEST, "too many pinned posts"));
+    }
+
+    let pin_activemodel = pin::ActiveModel {
+        post_id: ActiveValue::Set(post.id),
+        created_at: ActiveValue::Set(Utc::now().fixed_offset()),
+    };
+    pin_activemodel
+        .insert(&tx)
+        .await
+        .context_internal_server_error("failed to insert to database")?;
+
+    tx.commit()
+        .await
+        .context_internal_server_error("failed to commit database transaction")?;
+
+    let update = PersonUpdate::new_self(&data).await?;
+    update.send(&data).await?;
+
+    Ok(())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/post/{id}/pin",
+    params(
+        ("id" = String, format = "ulid"),
+    ),
+    responses(
+        (status = 200),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+pub(super) async fn delete_post_pin(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<Ulid>,
+) -> Result<()> {
+    access.require(Scope::Write(Resource::Accounts))?;
+
+    let res = pin::Entity::delete_by_id(uuid::Uuid::from(id))
+        .exec(&*data.db)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+    if res.rows_affected == 0 {
+        return Err(format_err!(NOT_FOUND, "pin not found"));
+    }
+
+    let update = PersonUpdate::new_self(&data).await?;
+    update.send(&data).await?;
+
+    Ok(())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/post/{id}/bookmark",
+    params(
+        ("id" = String, format = "ulid"),
+    ),
+    responses(
+        (status = 200),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+pub(super) async fn post_post_bookmark(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<Ulid>,
+) -> Result<()> {
+    access.require(Scope::Write(Resource::Bookmarks))?;
+
+    let existing_post_count = post::Entity::find_by_id(id)
+        .count(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    if existing_post_count == 0 {
+        return Err(format_err!(NOT_FOUND, "post not found"));
+    }
+
+    let bookmark_activemodel = bookmark::ActiveModel {
+        post_id: ActiveValue::Set(id.into()),
+        created_at: ActiveValue::Set(Utc::now().fixed_offset()),
+    };
+    let res = bookmark::Entity::insert(bookmark_activemodel)
+        .on_conflict(
+            OnConflict::column(bookmark::Column::PostId)
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(&*data.db)
+        .await
+        .context_internal_server_error("failed to insert to database")?;
+    if res == 0 {
+        return Err(format_err!(CONFLICT, "already bookmarked post"));
+    }
+
+    Ok(())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/post/{id}/bookmark",
+    params(
+        ("id" = String, format = "ulid"),
+    ),
+    responses(
+        (status = 200),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+pub(super) async fn delete_post_bookmark(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<Ulid>,
+) -> Result<()> {
+    access.require(Scope::Write(Resource::Bookmarks))?;
+
+    let res = bookmark::Entity::delete_by_id(uuid::Uuid::from(id))
+        .exec(&*data.db)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+    if res.rows_affected == 0 {
+        return Err(format_err!(NOT_FOUND, "bookmark not found"));
+    }
+
+    Ok(())
+}
+
+struct PostText {
+    text: String,
+    source_content: Option<String>,
+    source_media_type: Option<String>,
+}
+
+/// Renders `text` written in `format` to HTML, adding the tags found in it to the given ones.
+/// Without `format`, `text` is used as is.
+async fn render_text(
+    text: String,
+    format: Option<PostFormat>,
+    mentions: &mut Vec<Mention>,
+    emojis: &mut Vec<String>,
+    hashtags: &mut Vec<String>,
+    data: &Data<State>,
+) -> Result<PostText> {
+    let Some(format) = format else {
+        return Ok(PostText {
+            text: sanitize_html(&text),
+            source_content: None,
+            source_media_type: None,
+        });
+    };
+
+    let rendered = markup::render_post(&text, format, data).await?;
+    for mention in rendered.mentions {
+        if !mentions
+            .iter()
+            .any(|existing| existing.user_uri == mention.user_uri)
+        {
+            mentions.push(mention);
+        }
+    }
+    for emoji in rendered.emojis {
+        if !emojis.contains(&emoji) {
+            emojis.push(emoji);
+        }
+    }
+    for hashtag in rendered.hashtags {
+        if !hashtags.contains(&hashtag) {
+            hashtags.push(hashtag);
+        }
+    }
+
+    Ok(PostText {
+        text: sanitize_html(&rendered.content),
+        source_content: Some(text),
+        source_media_type: Some(format.media_type().to_string()),
+    })
+}
+
+async fn insert_post_tags(
+    post_id: uuid::Uuid,
+    emojis: Vec<String>,
+    mentions: &[Mention],
+    hashtags: Vec<String>,
+    db: &impl ConnectionTrait,
+) -> Result<()> {
+    let emojis = emoji::Entity::find()
+        .filter(emoji::Column::Name.is_in(emojis))
+        .find_also_related(local_file::Entity)
+        .all(db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    let emojis = emojis
+        .into_iter()
+        .filter_map(|(emoji, file)| file.map(|file| (emoji, file)))
+        .filter_map(|(emoji, file)| {
+            Some(post_emoji::ActiveModel {
+                post_id: ActiveValue::Set(post_id),
+                uri: ActiveValue::Set(emoji.ap_id().ok()?.to_string()),
+                name: ActiveValue::Set(emoji.name),
+                media_type: ActiveValue::Set(file.media_type),
+                image_url: ActiveValue::Set(file.url),
+            })
+        })
+        .collect::<Vec<_>>();
+    if !emojis.is_empty() {
+        post_emoji::Entity::insert_many(emojis)
+            .exec(db)
+            .await
+            .context_internal_server_error("failed to insert to database")?;
+    }
+
+    let mentions = mentions
+        .iter()
+        .map(|mention| mention::ActiveModel {
+            post_id: ActiveValue::Set(post_id),
+            user_uri: ActiveValue::Set(mention.user_uri.to_string()),
+            name: ActiveValue::Set(mention.name.clone()),
+        })
+        .collect::<Vec<_>>();
+    if !mentions.is_empty() {
+        mention::Entity::insert_many(mentions)
+            .exec(db)
+            .await
+            .context_internal_server_error("failed to insert to database")?;
+    }
+
+    let hashtags = hashtags
+        .into_iter()
+        .map(|hashtag| hashtag::ActiveModel {
+            post_id: ActiveValue::Set(post_id),
+            name: ActiveValue::Set(hashtag),
+        })
+        .collect::<Vec<_>>();
+    if !hashtags.is_empty() {
+        hashtag::Entity::insert_many(hashtags)
+            .exec(db)
+            .await
+            .context_internal_server_error("failed to insert to database")?;
+    }
+
+    Ok(())
+}
@@ -7,6 +7,7 @@ use crate::{
     dto::Reaction,
     entity::{reaction, user},
     error::{Context, Result},
+    scope::{Resource, Scope},
     state::State,
 };
 
@@ -30,12 +31,14 @@ pub(super) fn create_router() -> Router {
         ("access_key" = []),
     ),
 )]
-#[tracing::instrument(skip(data, _access))]
+#[tracing::instrument(skip(data, access))]
 async fn get_reaction(
     data: Data<State>,
-    _access: Access,
+    access: Access,
     extract::Path(id): extract::Path<Ulid>,
 ) -> Result<Json<Reaction>> {
+    access.require(Scope::Read(Resource::Favourites))?;
+
     let reaction = reaction::Entity::find_by_id(id)
         .one(&*data.db)
         .await
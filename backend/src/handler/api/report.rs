@@ -9,6 +9,7 @@ use crate::{
     dto::{CreateReport, IdPaginationQuery, Report},
     entity::{report, user},
     error::{Context, Result},
+    scope::{Resource, Scope},
     state::State,
 };
 
@@ -31,12 +32,14 @@ pub(super) fn create_router() -> Router {
         ("access_key" = []),
     ),
 )]
-#[tracing::instrument(skip(data, _access))]
+#[tracing::instrument(skip(data, access))]
 async fn get_reports(
     data: Data<State>,
-    _access: Access,
+    access: Access,
     extract::Query(query): extract::Query<IdPaginationQuery>,
 ) -> Result<Json<Vec<Report>>> {
+    access.require(Scope::Read(Resource::Reports))?;
+
     let pagination_query = report::Entity::find().find_also_related(user::Entity);
     let pagination_query = if let Some(after) = query.after {
         pagination_query.filter(report::Column::Id.lt(uuid::Uuid::from(after)))
@@ -68,12 +71,14 @@ async fn get_reports(
         ("access_key" = []),
     ),
 )]
-#[tracing::instrument(skip(data, _access))]
+#[tracing::instrument(skip(data, access))]
 async fn post_report(
     data: Data<State>,
-    _access: Access,
+    access: Access,
     Json(req): Json<CreateReport>,
 ) -> Result<()> {
+    access.require(Scope::Write(Resource::Reports))?;
+
     let (target_user_uri, inbox) = user::Entity::find_by_id(req.user_id)
         .select_only()
         .column(user::Column::Uri)
@@ -104,12 +109,14 @@ async fn post_report(
         ("access_key" = []),
     ),
 )]
-#[tracing::instrument(skip(data, _access))]
+#[tracing::instrument(skip(data, access))]
 async fn get_report(
     data: Data<State>,
-    _access: Access,
+    access: Access,
     extract::Path(id): extract::Path<Ulid>,
 ) -> Result<Json<Report>> {
+    access.require(Scope::Read(Resource::Reports))?;
+
     let (report, user) = report::Entity::find_by_id(id)
         .find_also_related(user::Entity)
         .one(&*data.db)
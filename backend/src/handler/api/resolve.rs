@@ -1,7 +1,5 @@
-use activitypub_federation::{
-    config::Data, fetch::webfinger::Webfinger, protocol::context::WithContext, traits::Object,
-};
-use axum::{extract, routing, Json, Router};
+use activitypub_federation::{config::Data, protocol::context::WithContext, traits::Object};
+use axum::{extract, middleware, routing, Json, Router};
 use derivative::Derivative;
 use reqwest::header;
 use serde::Deserialize;
@@ -9,11 +7,12 @@ use url::Url;
 use utoipa::IntoParams;
 
 use crate::{
-    ap::{person::Person, NoteOrAnnounce, Object as ApObject},
-    config::CONFIG,
+    ap::{NoteOrAnnounce, Object as ApObject},
     dto::{self, User},
     entity::{post, user},
     error::{Context, Result},
+    rate_limit,
+    scope::{Resource, Scope},
     state::State,
 };
 
@@ -23,6 +22,7 @@ pub(super) fn create_router() -> Router {
     Router::new()
         .route("/user", routing::get(get_resolve_user))
         .route("/link", routing::get(get_resolve_link))
+        .layer(middleware::from_fn(rate_limit::limit_resolve))
 }
 
 #[derive(Debug, Deserialize, IntoParams)]
@@ -43,53 +43,15 @@ struct GetResolveUserQuery {
         ("access_key" = []),
     ),
 )]
-#[tracing::instrument(skip(data, _access))]
+#[tracing::instrument(skip(data, access))]
 async fn get_resolve_user(
     data: Data<State>,
-    _access: Access,
+    access: Access,
     extract::Query(query): extract::Query<GetResolveUserQuery>,
 ) -> Result<Json<User>> {
-    let url = if CONFIG.debug {
-        format!(
-            "http://{}/.well-known/webfinger?resource=acct:{}@{}",
-            query.host, query.handle, query.host
-        )
-    } else {
-        format!(
-            "https://{}/.well-known/webfinger?resource=acct:{}@{}",
-            query.host, query.handle, query.host
-        )
-    };
-    let resp = data
-        .http_client
-        .get(url)
-        .send()
-        .await
-        .context_internal_server_error("failed to request HTTP")?
-        .error_for_status()
-        .context_internal_server_error("target server returned error")?
-        .json::<Webfinger>()
-        .await
-        .context_internal_server_error("failed to parse webfinger response")?;
-    let activity_url = resp
-        .links
-        .into_iter()
-        .find(|link| link.kind.as_deref() == Some("application/activity+json"))
-        .and_then(|link| link.href)
-        .context_internal_server_error("failed to find webfinger link")?;
-    let person = data
-        .http_client
-        .get(activity_url)
-        .header(header::ACCEPT, "application/activity+json")
-        .send()
-        .await
-        .context_internal_server_error("failed to request HTTP")?
-        .error_for_status()
-        .context_internal_server_error("target server returned error")?
-        .json::<WithContext<Person>>()
-        .await
-        .context_internal_server_error("failed to parse ActivityPub response")?;
-    let user = user::Model::from_json(person.inner().clone(), &data).await?;
+    access.require(Scope::Read(Resource::Search))?;
+
+    let user = user::Model::resolve(&query.handle, &query.host, &data).await?;
     Ok(Json(User::from_model(user)?))
 }
 
@@ -113,12 +75,14 @@ struct GetResolveLinkQuery {
         ("access_key" = []),
     ),
 )]
-#[tracing::instrument(skip(data, _access))]
+#[tracing::instrument(skip(data, access))]
 async fn get_resolve_link(
     data: Data<State>,
-    _access: Access,
+    access: Access,
     extract::Query(query): extract::Query<GetResolveLinkQuery>,
 ) -> Result<Json<dto::Object>> {
+    access.require(Scope::Read(Resource::Search))?;
+
     let object = data
         .http_client
         .get(query.link)
@@ -134,7 +98,7 @@ async fn get_resolve_link(
     let object = object.inner().clone();
     let dto = match object {
         ApObject::Note(note) => {
-            let model = post::Model::from_json(NoteOrAnnounce::Note(*note), &data).await?;
+            let model = post::Model::from_json(NoteOrAnnounce::Note(note), &data).await?;
             dto::Object::Post(Box::new(dto::Post::from_model(model, &*data.db).await?))
         }
         ApObject::Person(person) => {
@@ -0,0 +1,179 @@
+use activitypub_federation::config::Data;
+use axum::{extract, routing, Json, Router};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, IntoActiveModel, QueryFilter,
+    QueryOrder, QuerySelect,
+};
+use ulid::Ulid;
+
+use crate::{
+    dto::{CreatePost, IdPaginationQuery, ScheduledPost},
+    entity::scheduled_post,
+    error::{Context, Result},
+    format_err,
+    scope::{Resource, Scope},
+    state::State,
+};
+
+use super::{auth::Access, post::validate_post};
+
+pub(super) fn create_router() -> Router {
+    Router::new()
+        .route("/", routing::get(get_scheduled_posts))
+        .route(
+            "/:id",
+            routing::get(get_scheduled_post)
+                .put(put_scheduled_post)
+                .delete(delete_scheduled_post),
+        )
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/schedule",
+    params(IdPaginationQuery),
+    responses(
+        (status = 200, body = Vec<ScheduledPost>),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn get_scheduled_posts(
+    data: Data<State>,
+    access: Access,
+    extract::Query(query): extract::Query<IdPaginationQuery>,
+) -> Result<Json<Vec<ScheduledPost>>> {
+    access.require(Scope::Read(Resource::Statuses))?;
+
+    let pagination_query = scheduled_post::Entity::find();
+    let pagination_query = if let Some(after) = query.after {
+        pagination_query.filter(scheduled_post::Column::Id.lt(uuid::Uuid::from(after)))
+    } else {
+        pagination_query
+    };
+    let scheduled_posts = pagination_query
+        .order_by_desc(scheduled_post::Column::Id)
+        .limit(query.size)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    let scheduled_posts = scheduled_posts
+        .into_iter()
+        .map(ScheduledPost::from_model)
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Json(scheduled_posts))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/schedule/{id}",
+    params(
+        ("id" = String, format = "ulid"),
+    ),
+    responses(
+        (status = 200, body = ScheduledPost),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn get_scheduled_post(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<Ulid>,
+) -> Result<Json<ScheduledPost>> {
+    access.require(Scope::Read(Resource::Statuses))?;
+
+    let scheduled_post = scheduled_post::Entity::find_by_id(id)
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("scheduled post not found")?;
+    Ok(Json(ScheduledPost::from_model(scheduled_post)?))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/schedule/{id}",
+    params(
+        ("id" = String, format = "ulid"),
+    ),
+    request_body = CreatePost,
+    responses(
+        (status = 200, body = ScheduledPost),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access, req))]
+async fn put_scheduled_post(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<Ulid>,
+    Json(mut req): Json<CreatePost>,
+) -> Result<Json<ScheduledPost>> {
+    access.require(Scope::Write(Resource::Statuses))?;
+
+    let scheduled_at = req
+        .scheduled_at
+        .context_bad_request("scheduled time is required")?;
+    validate_post(&req)?;
+
+    let scheduled_post = scheduled_post::Entity::find_by_id(id)
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("scheduled post not found")?;
+
+    req.scheduled_at = None;
+    let request = serde_json::to_value(&req)
+        .context_internal_server_error("failed to serialize scheduled post")?;
+
+    let mut scheduled_post_activemodel = scheduled_post.into_active_model();
+    scheduled_post_activemodel.request = ActiveValue::Set(request);
+    scheduled_post_activemodel.scheduled_at = ActiveValue::Set(scheduled_at);
+    scheduled_post_activemodel.failed_at = ActiveValue::Set(None);
+    scheduled_post_activemodel.last_error = ActiveValue::Set(None);
+    let scheduled_post = scheduled_post_activemodel
+        .update(&*data.db)
+        .await
+        .context_internal_server_error("failed to update database")?;
+
+    Ok(Json(ScheduledPost::from_model(scheduled_post)?))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/schedule/{id}",
+    params(
+        ("id" = String, format = "ulid"),
+    ),
+    responses(
+        (status = 200),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn delete_scheduled_post(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<Ulid>,
+) -> Result<()> {
+    access.require(Scope::Write(Resource::Statuses))?;
+
+    let res = scheduled_post::Entity::delete_by_id(uuid::Uuid::from(id))
+        .exec(&*data.db)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+    if res.rows_affected == 0 {
+        return Err(format_err!(NOT_FOUND, "scheduled post not found"));
+    }
+
+    Ok(())
+}
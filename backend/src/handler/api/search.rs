@@ -0,0 +1,212 @@
+use activitypub_federation::config::Data;
+use axum::{extract, routing, Json, Router};
+use chrono::{DateTime, FixedOffset};
+use futures_util::{stream::FuturesOrdered, TryStreamExt};
+use sea_orm::{
+    sea_query::{Condition, Expr, Query, SimpleExpr},
+    ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+};
+use serde::Deserialize;
+use ulid::Ulid;
+use utoipa::IntoParams;
+
+use crate::{
+    dto::{Post, SearchResult, SearchType, User, Visibility},
+    entity::{hashtag, local_file, post, remote_file, sea_orm_active_enums, user},
+    error::{Context, Result},
+    mute::MuteFilter,
+    scope::{Resource, Scope},
+    state::State,
+};
+
+use super::auth::Access;
+
+pub(super) fn create_router() -> Router {
+    Router::new().route("/", routing::get(get_search))
+}
+
+fn default_size() -> u64 {
+    10
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+struct GetSearchQuery {
+    /// Search terms in `websearch_to_tsquery` syntax, e.g. `"quoted phrase" -excluded`
+    q: String,
+    /// Kind of results to search, all kinds if omitted
+    #[param(inline)]
+    #[serde(default, rename = "type")]
+    ty: Option<SearchType>,
+    /// Only posts by this user
+    #[param(value_type = Option<String>, format = "ulid")]
+    #[serde(default)]
+    author: Option<Ulid>,
+    /// Only posts with this visibility
+    #[param(inline)]
+    #[serde(default)]
+    visibility: Option<Visibility>,
+    /// Only posts with (or without) attached files
+    #[serde(default)]
+    has_media: Option<bool>,
+    /// Only posts created at or after this time
+    #[serde(default)]
+    since: Option<DateTime<FixedOffset>>,
+    /// Only posts created before this time
+    #[serde(default)]
+    until: Option<DateTime<FixedOffset>>,
+    #[param(value_type = Option<String>, format = "ulid")]
+    #[serde(default)]
+    after: Option<Ulid>,
+    #[param(default = 10)]
+    #[serde(default = "default_size")]
+    size: u64,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/search",
+    params(GetSearchQuery),
+    responses(
+        (status = 200, body = SearchResult),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn get_search(
+    data: Data<State>,
+    access: Access,
+    extract::Query(query): extract::Query<GetSearchQuery>,
+) -> Result<Json<SearchResult>> {
+    access.require(Scope::Read(Resource::Search))?;
+
+    let includes = |ty: SearchType| query.ty.map(|query_ty| query_ty == ty).unwrap_or(true);
+
+    let posts = if includes(SearchType::Post) {
+        search_posts(&data, &query).await?
+    } else {
+        Vec::new()
+    };
+    let users = if includes(SearchType::User) {
+        search_users(&data, &query).await?
+    } else {
+        Vec::new()
+    };
+    // hashtags are ranked rather than paginated, so they are only on the first page
+    let hashtags = if includes(SearchType::Hashtag) && query.after.is_none() {
+        search_hashtags(&data, &query).await?
+    } else {
+        Vec::new()
+    };
+
+    Ok(Json(SearchResult {
+        posts,
+        users,
+        hashtags,
+    }))
+}
+
+fn matches(table: &str, q: &str) -> SimpleExpr {
+    Expr::cust_with_values(
+        format!(r#""{table}"."search_vector" @@ websearch_to_tsquery('simple', $1)"#),
+        [q],
+    )
+}
+
+async fn search_posts(data: &Data<State>, query: &GetSearchQuery) -> Result<Vec<Post>> {
+    let mut condition = Condition::all().add(matches("post", &query.q));
+    if let Some(author) = query.author {
+        condition = condition.add(post::Column::UserId.eq(uuid::Uuid::from(author)));
+    }
+    if let Some(visibility) = &query.visibility {
+        condition = condition.add(post::Column::Visibility.eq(match visibility {
+            Visibility::Public => sea_orm_active_enums::Visibility::Public,
+            Visibility::Home => sea_orm_active_enums::Visibility::Home,
+            Visibility::Followers => sea_orm_active_enums::Visibility::Followers,
+            Visibility::DirectMessage => sea_orm_active_enums::Visibility::DirectMessage,
+        }));
+    }
+    if let Some(has_media) = query.has_media {
+        let local_file_post_ids = Query::select()
+            .column(local_file::Column::PostId)
+            .from(local_file::Entity)
+            .and_where(local_file::Column::PostId.is_not_null())
+            .to_owned();
+        let remote_file_post_ids = Query::select()
+            .column(remote_file::Column::PostId)
+            .from(remote_file::Entity)
+            .to_owned();
+        condition = condition.add(if has_media {
+            Condition::any()
+                .add(post::Column::Id.in_subquery(local_file_post_ids))
+                .add(post::Column::Id.in_subquery(remote_file_post_ids))
+        } else {
+            Condition::all()
+                .add(post::Column::Id.not_in_subquery(local_file_post_ids))
+                .add(post::Column::Id.not_in_subquery(remote_file_post_ids))
+        });
+    }
+    if let Some(since) = query.since {
+        condition = condition.add(post::Column::CreatedAt.gte(since));
+    }
+    if let Some(until) = query.until {
+        condition = condition.add(post::Column::CreatedAt.lt(until));
+    }
+
+    let mute_filter = MuteFilter::load(&*data.db).await?;
+    let pagination_query = post::Entity::find()
+        .filter(post::Model::visible_condition())
+        .filter(mute_filter.post_condition())
+        .filter(condition);
+    let pagination_query = if let Some(after) = query.after {
+        pagination_query.filter(post::Column::Id.lt(uuid::Uuid::from(after)))
+    } else {
+        pagination_query
+    };
+    let posts = pagination_query
+        .order_by_desc(post::Column::Id)
+        .limit(query.size)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    posts
+        .into_iter()
+        .map(|post| Post::from_model(post, &*data.db))
+        .collect::<FuturesOrdered<_>>()
+        .try_collect()
+        .await
+}
+
+async fn search_users(data: &Data<State>, query: &GetSearchQuery) -> Result<Vec<User>> {
+    let pagination_query = user::Entity::find().filter(matches("user", &query.q));
+    let pagination_query = if let Some(after) = query.after {
+        pagination_query.filter(user::Column::Id.lt(uuid::Uuid::from(after)))
+    } else {
+        pagination_query
+    };
+    let users = pagination_query
+        .order_by_desc(user::Column::Id)
+        .limit(query.size)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    users.into_iter().map(User::from_model).collect()
+}
+
+async fn search_hashtags(data: &Data<State>, query: &GetSearchQuery) -> Result<Vec<String>> {
+    let hashtags = hashtag::Entity::find()
+        .filter(matches("hashtag", &query.q))
+        .select_only()
+        .column(hashtag::Column::Name)
+        .group_by(hashtag::Column::Name)
+        .order_by_desc(Expr::col(hashtag::Column::PostId).count())
+        .order_by_asc(hashtag::Column::Name)
+        .limit(query.size)
+        .into_tuple::<String>()
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    Ok(hashtags)
+}
@@ -1,28 +1,35 @@
-use activitypub_federation::config::Data;
+use activitypub_federation::{config::Data, fetch::object_id::ObjectId};
 use axum::{routing, Json, Router};
 use sea_orm::{ActiveModelTrait, ActiveValue, EntityTrait, PaginatorTrait, TransactionTrait};
 use serde::Deserialize;
 use ulid::Ulid;
+use url::Url;
 use utoipa::ToSchema;
 
 use crate::{
-    ap::person::PersonUpdate,
+    ap::{
+        move_account::Move,
+        person::{LocalPerson, PersonUpdate},
+    },
     dto::{ObjectStoreType, Setting},
-    entity::{local_file, setting},
+    entity::{local_file, setting, user},
     error::{Context, Result},
     format_err,
+    scope::{Resource, Scope},
     state::State,
 };
 
 use super::auth::Access;
 
 pub(super) fn create_router() -> Router {
-    Router::new().route(
-        "/",
-        routing::get(get_setting)
-            .post(post_setting)
-            .put(put_setting),
-    )
+    Router::new()
+        .route(
+            "/",
+            routing::get(get_setting)
+                .post(post_setting)
+                .put(put_setting),
+        )
+        .route("/move", routing::post(post_setting_move))
 }
 
 #[utoipa::path(
@@ -98,6 +105,14 @@ pub struct PutSettingReq {
     pub object_store_s3_public_url_base: Option<String>,
     #[serde(default)]
     pub object_store_local_file_system_base_path: Option<String>,
+    #[serde(default)]
+    pub hide_follow_collections: Option<bool>,
+    #[serde(default)]
+    pub user_manually_approves_followers: Option<bool>,
+    /// Actor URIs of our other accounts, which we can move from or to
+    #[schema(value_type = Option<Vec<String>>, format = "url")]
+    #[serde(default)]
+    pub user_also_known_as: Option<Vec<Url>>,
 }
 
 #[utoipa::path(
@@ -111,12 +126,14 @@ pub struct PutSettingReq {
         ("access_key" = []),
     ),
 )]
-#[tracing::instrument(skip(data, _access))]
+#[tracing::instrument(skip(data, access))]
 async fn put_setting(
     data: Data<State>,
-    _access: Access,
+    access: Access,
     Json(req): Json<PutSettingReq>,
 ) -> Result<Json<Setting>> {
+    access.require(Scope::Write(Resource::Accounts))?;
+
     let setting = setting::Model::get(&*data.db).await?;
 
     let mut setting_activemodel: setting::ActiveModel = setting.into();
@@ -175,6 +192,16 @@ async fn put_setting(
                 ActiveValue::Set(Some(v));
         }
     }
+    if let Some(v) = req.hide_follow_collections {
+        setting_activemodel.hide_follow_collections = ActiveValue::Set(v);
+    }
+    if let Some(v) = req.user_manually_approves_followers {
+        setting_activemodel.user_manually_approves_followers = ActiveValue::Set(v);
+    }
+    if let Some(v) = req.user_also_known_as {
+        setting_activemodel.user_also_known_as =
+            ActiveValue::Set(v.iter().map(Url::to_string).collect());
+    }
 
     let tx = data
         .db
@@ -215,3 +242,59 @@ async fn put_setting(
 
     Ok(Json(Setting::from_model(setting)))
 }
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PostSettingMoveReq {
+    /// Actor URI of the account to move to
+    #[schema(value_type = String, format = "url")]
+    pub target: Url,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/setting/move",
+    request_body = PostSettingMoveReq,
+    responses(
+        (status = 200, body = Setting),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn post_setting_move(
+    data: Data<State>,
+    access: Access,
+    Json(req): Json<PostSettingMoveReq>,
+) -> Result<Json<Setting>> {
+    access.require(Scope::Write(Resource::Accounts))?;
+
+    let target_uri: ObjectId<user::Model> = req.target.clone().into();
+    let target_user = target_uri.dereference_forced(&data).await?;
+    let id = LocalPerson::id().to_string();
+    if !target_user.also_known_as.iter().any(|alias| alias == &id) {
+        return Err(format_err!(
+            BAD_REQUEST,
+            "target does not list this account as an alias"
+        ));
+    }
+
+    let setting = setting::Model::get(&*data.db).await?;
+    let mut setting_activemodel: setting::ActiveModel = setting.into();
+    setting_activemodel.user_moved_to = ActiveValue::Set(Some(target_user.uri.clone()));
+    let setting = setting_activemodel
+        .update(&*data.db)
+        .await
+        .context_internal_server_error("failed to update database")?;
+
+    let update = PersonUpdate::new_self(&data).await?;
+    update.send(&data).await?;
+
+    let target =
+        Url::parse(&target_user.uri).context_internal_server_error("malformed user URI")?;
+    let move_activity = Move::new_self(target)?;
+    move_activity.send(&data).await?;
+
+    Ok(Json(Setting::from_model(setting)))
+}
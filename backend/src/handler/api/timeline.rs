@@ -0,0 +1,134 @@
+use activitypub_federation::config::Data;
+use axum::{extract, routing, Json, Router};
+use futures_util::{stream::FuturesOrdered, TryStreamExt};
+use sea_orm::{
+    sea_query::{Condition, Query},
+    ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+};
+
+use crate::{
+    dto::{IdPaginationQuery, Post},
+    entity::{follow, post, sea_orm_active_enums::Visibility},
+    error::{Context, Result},
+    mute::MuteFilter,
+    scope::{Resource, Scope},
+    state::State,
+};
+
+use super::auth::Access;
+
+pub(super) fn create_router() -> Router {
+    Router::new()
+        .route("/home", routing::get(get_home_timeline))
+        .route("/local", routing::get(get_local_timeline))
+        .route("/federated", routing::get(get_federated_timeline))
+}
+
+/// Queries a page of visible, unmuted posts matching `condition`.
+pub(super) async fn get_timeline(
+    data: &Data<State>,
+    condition: Condition,
+    query: IdPaginationQuery,
+) -> Result<Vec<Post>> {
+    let mute_filter = MuteFilter::load(&*data.db).await?;
+    let pagination_query = post::Entity::find()
+        .filter(post::Model::visible_condition())
+        .filter(mute_filter.post_condition())
+        .filter(condition);
+    let pagination_query = if let Some(after) = query.after {
+        pagination_query.filter(post::Column::Id.lt(uuid::Uuid::from(after)))
+    } else {
+        pagination_query
+    };
+    let posts = pagination_query
+        .order_by_desc(post::Column::Id)
+        .limit(query.size)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    posts
+        .into_iter()
+        .map(|post| Post::from_model(post, &*data.db))
+        .collect::<FuturesOrdered<_>>()
+        .try_collect()
+        .await
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/timeline/home",
+    params(IdPaginationQuery),
+    responses(
+        (status = 200, body = Vec<Post>),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn get_home_timeline(
+    data: Data<State>,
+    access: Access,
+    extract::Query(query): extract::Query<IdPaginationQuery>,
+) -> Result<Json<Vec<Post>>> {
+    access.require(Scope::Read(Resource::Statuses))?;
+
+    let condition = Condition::any().add(post::Column::UserId.is_null()).add(
+        post::Column::UserId.in_subquery(
+            Query::select()
+                .column(follow::Column::ToId)
+                .from(follow::Entity)
+                .and_where(follow::Column::Accepted.eq(true))
+                .to_owned(),
+        ),
+    );
+    Ok(Json(get_timeline(&data, condition, query).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/timeline/local",
+    params(IdPaginationQuery),
+    responses(
+        (status = 200, body = Vec<Post>),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn get_local_timeline(
+    data: Data<State>,
+    access: Access,
+    extract::Query(query): extract::Query<IdPaginationQuery>,
+) -> Result<Json<Vec<Post>>> {
+    access.require(Scope::Read(Resource::Statuses))?;
+
+    let condition = Condition::all()
+        .add(post::Column::UserId.is_null())
+        .add(post::Column::Visibility.eq(Visibility::Public));
+    Ok(Json(get_timeline(&data, condition, query).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/timeline/federated",
+    params(IdPaginationQuery),
+    responses(
+        (status = 200, body = Vec<Post>),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn get_federated_timeline(
+    data: Data<State>,
+    access: Access,
+    extract::Query(query): extract::Query<IdPaginationQuery>,
+) -> Result<Json<Vec<Post>>> {
+    access.require(Scope::Read(Resource::Statuses))?;
+
+    let condition = Condition::all().add(post::Column::Visibility.eq(Visibility::Public));
+    Ok(Json(get_timeline(&data, condition, query).await?))
+}
@@ -0,0 +1,54 @@
+use activitypub_federation::config::Data;
+use axum::{extract, routing, Json, Router};
+use sea_orm::{sea_query::Condition, ColumnTrait, EntityTrait, PaginatorTrait};
+use ulid::Ulid;
+
+use crate::{
+    dto::{IdPaginationQuery, Post},
+    entity::{post, user},
+    error::{Context, Result},
+    format_err,
+    scope::{Resource, Scope},
+    state::State,
+};
+
+use super::{auth::Access, timeline::get_timeline};
+
+pub(super) fn create_router() -> Router {
+    Router::new().route("/:id/posts", routing::get(get_user_posts))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/user/{id}/posts",
+    params(
+        IdPaginationQuery,
+        ("id" = String, format = "ulid"),
+    ),
+    responses(
+        (status = 200, body = Vec<Post>),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, access))]
+async fn get_user_posts(
+    data: Data<State>,
+    access: Access,
+    extract::Path(id): extract::Path<Ulid>,
+    extract::Query(query): extract::Query<IdPaginationQuery>,
+) -> Result<Json<Vec<Post>>> {
+    access.require(Scope::Read(Resource::Statuses))?;
+
+    let user_count = user::Entity::find_by_id(id)
+        .count(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    if user_count == 0 {
+        return Err(format_err!(NOT_FOUND, "user not found"));
+    }
+
+    let condition = Condition::all().add(post::Column::UserId.eq(uuid::Uuid::from(id)));
+    Ok(Json(get_timeline(&data, condition, query).await?))
+}
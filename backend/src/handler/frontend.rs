@@ -1,6 +1,6 @@
 use activitypub_federation::config::Data;
 use askama::Template;
-use axum::{http::StatusCode, response::IntoResponse};
+use axum::{extract, http::StatusCode, response::IntoResponse};
 use sea_orm::{ConnectionTrait, EntityTrait, QuerySelect};
 use ulid::Ulid;
 
@@ -129,6 +129,19 @@ pub async fn get_index(data: Data<State>) -> Result<RespOrFrontend<()>> {
     .await
 }
 
+pub async fn get_hashtag(
+    data: Data<State>,
+    extract::Path(name): extract::Path<String>,
+) -> Result<RespOrFrontend<()>> {
+    let title = format!("#{name}");
+    let ctx = FrontendContext {
+        title: Some(title.clone()),
+        og_title: Some(title),
+        ..FrontendContext::site_default(&*data.db).await?
+    };
+    RespOrFrontend::frontend(StatusCode::OK, &*data.db, ctx).await
+}
+
 pub async fn get_not_found(data: Data<State>) -> Result<RespOrFrontend<()>> {
     RespOrFrontend::frontend(
         StatusCode::NOT_FOUND,
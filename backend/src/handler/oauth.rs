@@ -0,0 +1,449 @@
+//! OAuth 2.0 authorization code flow, through which third-party clients receive access keys
+//! with limited scopes instead of the password.
+
+use activitypub_federation::config::Data;
+use askama::Template;
+use axum::{
+    extract,
+    handler::Handler,
+    http::StatusCode,
+    middleware,
+    response::{IntoResponse, Redirect, Response},
+    routing, Form, Json, Router,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{Duration, Utc};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, ModelTrait, PaginatorTrait,
+    QueryFilter, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use ulid::Ulid;
+use url::Url;
+
+use crate::{
+    dto::{PasskeyAssertion, SecondFactor},
+    entity::{access_key, oauth_application, oauth_authorization, passkey, setting},
+    error::{Context, Result},
+    format_err,
+    handler::api::mastodon::{app::OOB_REDIRECT_URI, JsonOrForm},
+    rate_limit::{self, ClientIp},
+    scope,
+    state::State,
+    two_factor,
+    util::{hash_token, random_token},
+};
+
+/// How long an authorization code can be exchanged for an access key.
+const CODE_LIFETIME: Duration = Duration::minutes(10);
+
+pub(super) fn create_router() -> Router {
+    Router::new()
+        .route(
+            "/authorize",
+            routing::get(get_authorize)
+                .post(post_authorize.layer(middleware::from_fn(rate_limit::limit_login))),
+        )
+        .route(
+            "/token",
+            routing::post(post_token.layer(middleware::from_fn(rate_limit::limit_login))),
+        )
+        .route("/revoke", routing::post(post_revoke))
+}
+
+#[derive(Template)]
+#[template(path = "oauth_authorize.html")]
+struct AuthorizeTemplate {
+    instance_name: String,
+    application_name: String,
+    website: Option<String>,
+    scopes: Vec<String>,
+    params: Vec<(&'static str, String)>,
+    /// Whether a TOTP is enabled, accepted along with recovery codes
+    totp: bool,
+    /// Whether a passkey is registered, accepted along with recovery codes
+    passkey: bool,
+    error: Option<String>,
+}
+
+#[derive(Template)]
+#[template(path = "oauth_code.html")]
+struct CodeTemplate {
+    instance_name: String,
+    application_name: String,
+    code: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AuthorizeQuery {
+    response_type: String,
+    client_id: String,
+    redirect_uri: String,
+    #[serde(default)]
+    scope: Option<String>,
+    #[serde(default)]
+    state: Option<String>,
+    #[serde(default)]
+    code_challenge: Option<String>,
+    #[serde(default)]
+    code_challenge_method: Option<String>,
+}
+
+impl AuthorizeQuery {
+    /// Checks the request against the registered application, returning it with the requested
+    /// scopes.
+    async fn validate(
+        &self,
+        data: &Data<State>,
+    ) -> Result<(oauth_application::Model, Vec<String>)> {
+        let application = oauth_application::Entity::find()
+            .filter(oauth_application::Column::ClientId.eq(&self.client_id))
+            .one(&*data.db)
+            .await
+            .context_internal_server_error("failed to query database")?
+            .context_bad_request("unknown client")?;
+        if self.response_type != "code" {
+            return Err(format_err!(BAD_REQUEST, "unsupported response type"));
+        }
+        if !application.redirect_uris.contains(&self.redirect_uri) {
+            return Err(format_err!(BAD_REQUEST, "redirect URI not registered"));
+        }
+
+        let scopes = scope::parse(self.scope.as_deref()).context_bad_request("unknown scope")?;
+        let is_registered = |scope: &String| {
+            application.scopes.contains(scope)
+                || scope
+                    .split_once(':')
+                    .is_some_and(|(coarse, _)| application.scopes.iter().any(|s| s == coarse))
+        };
+        if !scopes.iter().all(is_registered) {
+            return Err(format_err!(BAD_REQUEST, "scope not registered"));
+        }
+
+        match (&self.code_challenge, self.code_challenge_method.as_deref()) {
+            (None, None) | (Some(_), Some("S256")) => {}
+            (Some(_), _) => {
+                return Err(format_err!(
+                    BAD_REQUEST,
+                    "unsupported code challenge method"
+                ))
+            }
+            (None, Some(_)) => return Err(format_err!(BAD_REQUEST, "code challenge not found")),
+        }
+
+        Ok((application, scopes))
+    }
+
+    /// Parameters to carry through the consent form.
+    fn params(&self) -> Vec<(&'static str, String)> {
+        let mut params = vec![
+            ("response_type", self.response_type.clone()),
+            ("client_id", self.client_id.clone()),
+            ("redirect_uri", self.redirect_uri.clone()),
+        ];
+        let optional = [
+            ("scope", &self.scope),
+            ("state", &self.state),
+            ("code_challenge", &self.code_challenge),
+            ("code_challenge_method", &self.code_challenge_method),
+        ];
+        for (name, value) in optional {
+            if let Some(value) = value {
+                params.push((name, value.clone()));
+            }
+        }
+        params
+    }
+}
+
+async fn consent_page(
+    status: StatusCode,
+    data: &Data<State>,
+    setting: setting::Model,
+    application: oauth_application::Model,
+    scopes: Vec<String>,
+    query: &AuthorizeQuery,
+    error: Option<String>,
+) -> Result<Response> {
+    let passkey_count = passkey::Entity::find()
+        .count(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    let template = AuthorizeTemplate {
+        totp: setting.user_totp_enabled,
+        passkey: passkey_count > 0,
+        instance_name: setting.instance_name,
+        application_name: application.name,
+        website: application.website,
+        scopes,
+        params: query.params(),
+        error,
+    };
+    let mut resp = askama_axum::into_response(&template);
+    *resp.status_mut() = status;
+    Ok(resp)
+}
+
+#[tracing::instrument(skip(data))]
+async fn get_authorize(
+    data: Data<State>,
+    extract::Query(query): extract::Query<AuthorizeQuery>,
+) -> Result<Response> {
+    let (application, scopes) = query.validate(&data).await?;
+    let setting = setting::Model::get(&*data.db).await?;
+    consent_page(
+        StatusCode::OK,
+        &data,
+        setting,
+        application,
+        scopes,
+        &query,
+        None,
+    )
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+struct PostAuthorizeReq {
+    #[serde(flatten)]
+    query: AuthorizeQuery,
+    password: String,
+    /// TOTP or recovery code
+    #[serde(default)]
+    second_factor: Option<String>,
+    /// JSON encoded [`PasskeyAssertion`]
+    #[serde(default)]
+    passkey: Option<String>,
+}
+
+#[tracing::instrument(skip(data, req))]
+async fn post_authorize(
+    data: Data<State>,
+    ClientIp(client_ip): ClientIp,
+    Form(req): Form<PostAuthorizeReq>,
+) -> Result<Response> {
+    rate_limit::check_lockout(client_ip, &*data.db).await?;
+    let (application, scopes) = req.query.validate(&data).await?;
+    let setting = setting::Model::get(&*data.db).await?;
+    let mut authenticated = bcrypt::verify(&req.password, &setting.user_password_hash)
+        .context_bad_request("failed to authenticate")?;
+    if authenticated && two_factor::is_enabled(&setting, &*data.db).await? {
+        let code = req.second_factor.filter(|code| !code.trim().is_empty());
+        let passkey = req
+            .passkey
+            .filter(|assertion| !assertion.is_empty())
+            .map(|assertion| serde_json::from_str::<PasskeyAssertion>(&assertion))
+            .transpose()
+            .context_bad_request("malformed passkey assertion")?;
+        let second_factor = SecondFactor {
+            totp: code.clone(),
+            recovery_code: code,
+            passkey,
+        };
+        authenticated = two_factor::verify(&second_factor, &setting, &*data.db).await?;
+    }
+    if !authenticated {
+        rate_limit::record_login_failure(client_ip, &*data.db).await?;
+        return consent_page(
+            StatusCode::UNAUTHORIZED,
+            &data,
+            setting,
+            application,
+            scopes,
+            &req.query,
+            Some("Wrong password or code".to_string()),
+        )
+        .await;
+    }
+
+    let authorization_activemodel = oauth_authorization::ActiveModel {
+        code: ActiveValue::Set(random_token()),
+        application_id: ActiveValue::Set(application.id),
+        redirect_uri: ActiveValue::Set(req.query.redirect_uri.clone()),
+        scopes: ActiveValue::Set(scopes),
+        code_challenge: ActiveValue::Set(req.query.code_challenge.clone()),
+        expires_at: ActiveValue::Set((Utc::now() + CODE_LIFETIME).fixed_offset()),
+    };
+    let authorization = authorization_activemodel
+        .insert(&*data.db)
+        .await
+        .context_internal_server_error("failed to insert to database")?;
+
+    if authorization.redirect_uri == OOB_REDIRECT_URI {
+        let template = CodeTemplate {
+            instance_name: setting.instance_name,
+            application_name: application.name,
+            code: authorization.code,
+        };
+        return Ok(askama_axum::into_response(&template));
+    }
+
+    let mut redirect_uri =
+        Url::parse(&authorization.redirect_uri).context_bad_request("malformed redirect URI")?;
+    {
+        let mut query_pairs = redirect_uri.query_pairs_mut();
+        query_pairs.append_pair("code", &authorization.code);
+        if let Some(state) = &req.query.state {
+            query_pairs.append_pair("state", state);
+        }
+    }
+    Ok(Redirect::to(redirect_uri.as_str()).into_response())
+}
+
+#[derive(Debug, Deserialize)]
+struct PostTokenReq {
+    grant_type: String,
+    #[serde(default)]
+    code: Option<String>,
+    #[serde(default)]
+    redirect_uri: Option<String>,
+    #[serde(default)]
+    client_id: Option<String>,
+    #[serde(default)]
+    client_secret: Option<String>,
+    #[serde(default)]
+    code_verifier: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct PostTokenResp {
+    access_token: String,
+    token_type: &'static str,
+    scope: String,
+    created_at: i64,
+}
+
+/// Computes the `S256` code challenge of `code_verifier` as in RFC 7636.
+fn code_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+#[tracing::instrument(skip(data, req))]
+async fn post_token(
+    data: Data<State>,
+    JsonOrForm(req): JsonOrForm<PostTokenReq>,
+) -> Result<Json<PostTokenResp>> {
+    if req.grant_type != "authorization_code" {
+        return Err(format_err!(BAD_REQUEST, "unsupported grant type"));
+    }
+    let code = req.code.context_bad_request("code not found")?;
+    let client_id = req.client_id.context_bad_request("client_id not found")?;
+
+    let tx = data
+        .db
+        .begin()
+        .await
+        .context_internal_server_error("failed to begin database transaction")?;
+
+    let application = oauth_application::Entity::find()
+        .filter(oauth_application::Column::ClientId.eq(client_id))
+        .one(&tx)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_unauthorized("unknown client")?;
+    let authorization = oauth_authorization::Entity::find_by_id(code)
+        .one(&tx)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_bad_request("invalid authorization code")?;
+    // A code is single use even if the exchange fails
+    authorization
+        .clone()
+        .delete(&tx)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+    tx.commit()
+        .await
+        .context_internal_server_error("failed to commit database transaction")?;
+
+    if authorization.application_id != application.id
+        || authorization.expires_at < Utc::now()
+        || req.redirect_uri.as_ref() != Some(&authorization.redirect_uri)
+    {
+        return Err(format_err!(BAD_REQUEST, "invalid authorization code"));
+    }
+    if let Some(client_secret) = &req.client_secret {
+        if *client_secret != application.client_secret {
+            return Err(format_err!(UNAUTHORIZED, "invalid client secret"));
+        }
+    }
+    match (&authorization.code_challenge, &req.code_verifier) {
+        (Some(challenge), Some(verifier)) if *challenge == code_challenge(verifier) => {}
+        (Some(_), _) => return Err(format_err!(BAD_REQUEST, "invalid code verifier")),
+        (None, _) if req.client_secret.is_some() => {}
+        (None, _) => return Err(format_err!(UNAUTHORIZED, "client secret not found")),
+    }
+
+    let token = random_token();
+    let access_key_activemodel = access_key::ActiveModel {
+        id: ActiveValue::Set(Ulid::new().into()),
+        name: ActiveValue::Set(application.name),
+        last_used_at: ActiveValue::NotSet,
+        application_id: ActiveValue::Set(Some(application.id)),
+        scopes: ActiveValue::Set(authorization.scopes),
+        created_at: ActiveValue::Set(Utc::now().fixed_offset()),
+        expires_at: ActiveValue::Set(None),
+        user_agent: ActiveValue::Set(None),
+        ip: ActiveValue::Set(None),
+        personal: ActiveValue::Set(false),
+        secret_hash: ActiveValue::Set(hash_token(&token)),
+    };
+    let access_key = access_key_activemodel
+        .insert(&*data.db)
+        .await
+        .context_internal_server_error("failed to insert to database")?;
+
+    Ok(Json(PostTokenResp {
+        access_token: token,
+        token_type: "Bearer",
+        scope: access_key.scopes.join(" "),
+        created_at: access_key.created_at.timestamp(),
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+struct PostRevokeReq {
+    token: String,
+    client_id: String,
+    client_secret: String,
+}
+
+/// Revokes an access key issued to the client. Unknown tokens are not an error, as in
+/// RFC 7009.
+#[tracing::instrument(skip(data, req))]
+async fn post_revoke(
+    data: Data<State>,
+    JsonOrForm(req): JsonOrForm<PostRevokeReq>,
+) -> Result<Json<serde_json::Value>> {
+    let application = oauth_application::Entity::find()
+        .filter(oauth_application::Column::ClientId.eq(req.client_id))
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .filter(|application| application.client_secret == req.client_secret)
+        .context_unauthorized("invalid client credentials")?;
+
+    access_key::Entity::delete_many()
+        .filter(access_key::Column::SecretHash.eq(hash_token(&req.token)))
+        .filter(access_key::Column::ApplicationId.eq(application.id))
+        .exec(&*data.db)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+
+    Ok(Json(serde_json::json!({})))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn computes_code_challenge() {
+        // Example from RFC 7636 appendix B
+        assert_eq!(
+            code_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+}
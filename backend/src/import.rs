@@ -0,0 +1,313 @@
+use std::time::Duration;
+
+use activitypub_federation::{
+    config::{Data, FederationConfig},
+    fetch::object_id::ObjectId,
+    traits::Object,
+};
+use chrono::Utc;
+use sea_orm::{
+    sea_query::OnConflict, ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, TransactionTrait,
+};
+use ulid::Ulid;
+use url::Url;
+
+use crate::{
+    ap::block::Block,
+    entity::{
+        block, bookmark, follow, follow_request, follower, import, mute, post,
+        sea_orm_active_enums::ImportType, user,
+    },
+    error::{Context, Error},
+    mute::MuteFilter,
+    queue::{Event, Update},
+    state::State,
+};
+
+const IMPORT_POLL_INTERVAL: Duration = Duration::from_secs(10);
+/// Number of entries between progress events
+const IMPORT_PROGRESS_INTERVAL: u32 = 10;
+
+/// Extracts account addresses or post URLs from a Mastodon CSV export, skipping its header.
+pub fn parse_csv(body: &str) -> Vec<String> {
+    body.trim_start_matches('\u{feff}')
+        .lines()
+        .filter_map(|line| {
+            let field = line.split(',').next()?.trim().trim_matches('"').trim();
+            let field = field.strip_prefix('@').unwrap_or(field);
+            (!field.is_empty() && field != "Account address").then(|| field.to_string())
+        })
+        .collect()
+}
+
+/// Processes uploaded imports one entry at a time, resuming unfinished ones after restarts.
+pub async fn run_worker(federation_config: FederationConfig<State>) -> Result<(), Error> {
+    let data = federation_config.to_request_data();
+
+    tracing::info!("starting import worker...");
+    while !data.stopper.is_stopped() {
+        if let Err(error) = process_pending(&data).await {
+            tracing::error!("failed to process imports\n{:?}", error.inner);
+        }
+
+        data.stopper
+            .stop_future(tokio::time::sleep(IMPORT_POLL_INTERVAL))
+            .await;
+    }
+    tracing::info!("import worker stopped");
+
+    Ok(())
+}
+
+#[tracing::instrument(skip(data))]
+async fn process_pending(data: &Data<State>) -> Result<(), Error> {
+    let imports = import::Entity::find()
+        .filter(import::Column::FinishedAt.is_null())
+        .order_by_asc(import::Column::Id)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+
+    for import in imports {
+        let id = import.id;
+        if let Err(error) = process_import(import, data).await {
+            tracing::error!(%id, "failed to process import\n{:?}", error.inner);
+        }
+    }
+
+    Ok(())
+}
+
+#[tracing::instrument(skip(data, import), fields(id = %import.id))]
+async fn process_import(import: import::Model, data: &Data<State>) -> Result<(), Error> {
+    let total_count = import.entries.len() as u32;
+    let mut processed_count = import.processed_count as u32;
+    let mut failed_count = import.failed_count as u32;
+
+    for entry in import.entries.iter().skip(processed_count as usize) {
+        if data.stopper.is_stopped() {
+            return Ok(());
+        }
+
+        if let Err(error) = import_entry(&import.r#type, entry, data).await {
+            tracing::warn!(entry, "failed to import entry\n{:?}", error.inner);
+            failed_count += 1;
+        }
+        processed_count += 1;
+
+        let import_activemodel = import::ActiveModel {
+            id: ActiveValue::Unchanged(import.id),
+            processed_count: ActiveValue::Set(processed_count as i32),
+            failed_count: ActiveValue::Set(failed_count as i32),
+            ..Default::default()
+        };
+        import_activemodel
+            .update(&*data.db)
+            .await
+            .context_internal_server_error("failed to update database")?;
+
+        if processed_count.is_multiple_of(IMPORT_PROGRESS_INTERVAL)
+            && processed_count != total_count
+        {
+            let event = Event::Update(Update::UpdateImport {
+                import_id: import.id.into(),
+                total_count,
+                processed_count,
+                failed_count,
+            });
+            event.send(&*data.db).await?;
+        }
+    }
+
+    let import_activemodel = import::ActiveModel {
+        id: ActiveValue::Unchanged(import.id),
+        finished_at: ActiveValue::Set(Some(Utc::now().fixed_offset())),
+        ..Default::default()
+    };
+    import_activemodel
+        .update(&*data.db)
+        .await
+        .context_internal_server_error("failed to update database")?;
+
+    let event = Event::Update(Update::UpdateImport {
+        import_id: import.id.into(),
+        total_count,
+        processed_count,
+        failed_count,
+    });
+    event.send(&*data.db).await?;
+
+    Ok(())
+}
+
+async fn import_entry(ty: &ImportType, entry: &str, data: &Data<State>) -> Result<(), Error> {
+    match ty {
+        ImportType::Following => follow_user(&resolve_account(entry, data).await?, data).await,
+        ImportType::Blocks => block_user(&resolve_account(entry, data).await?, data).await,
+        ImportType::Mutes => mute_user(&resolve_account(entry, data).await?, data).await,
+        ImportType::Bookmarks => bookmark_post(entry, data).await,
+    }
+}
+
+async fn resolve_account(address: &str, data: &Data<State>) -> Result<user::Model, Error> {
+    let (handle, host) = address
+        .split_once('@')
+        .context_bad_request("malformed account address")?;
+    user::Model::find_or_resolve(handle, host, data).await
+}
+
+async fn follow_user(user: &user::Model, data: &Data<State>) -> Result<(), Error> {
+    let follow_activemodel = follow::ActiveModel {
+        to_id: ActiveValue::Set(user.id),
+        accepted: ActiveValue::Set(false),
+    };
+    let inserted_count = follow::Entity::insert(follow_activemodel)
+        .on_conflict(
+            OnConflict::column(follow::Column::ToId)
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(&*data.db)
+        .await
+        .context_internal_server_error("failed to insert to database")?;
+    if inserted_count == 0 {
+        return Ok(());
+    }
+
+    let follow = follow::Model {
+        to_id: user.id,
+        accepted: false,
+    }
+    .into_json(data)
+    .await?;
+    follow.send(data).await
+}
+
+async fn block_user(user: &user::Model, data: &Data<State>) -> Result<(), Error> {
+    let tx = data
+        .db
+        .begin()
+        .await
+        .context_internal_server_error("failed to begin database transaction")?;
+
+    let block_activemodel = block::ActiveModel {
+        to_id: ActiveValue::Set(user.id),
+        created_at: ActiveValue::Set(Utc::now().fixed_offset()),
+    };
+    let inserted_count = block::Entity::insert(block_activemodel)
+        .on_conflict(
+            OnConflict::column(block::Column::ToId)
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(&tx)
+        .await
+        .context_internal_server_error("failed to insert to database")?;
+    if inserted_count == 0 {
+        return Ok(());
+    }
+    follower::Entity::delete_by_id(user.id)
+        .exec(&tx)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+    follow_request::Entity::delete_by_id(user.id)
+        .exec(&tx)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+
+    tx.commit()
+        .await
+        .context_internal_server_error("failed to commit database transaction")?;
+
+    let inbox =
+        Url::parse(&user.inbox).context_internal_server_error("malformed user inbox URL")?;
+    let block = Block::new(
+        user.id.into(),
+        user.uri
+            .parse()
+            .context_internal_server_error("malformed user URI")?,
+    )?;
+    block.send(data, inbox).await
+}
+
+async fn mute_user(user: &user::Model, data: &Data<State>) -> Result<(), Error> {
+    let tx = data
+        .db
+        .begin()
+        .await
+        .context_internal_server_error("failed to begin database transaction")?;
+
+    let existing_count = mute::Entity::find()
+        .filter(mute::Column::UserId.eq(user.id))
+        .count(&tx)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    if existing_count != 0 {
+        return Ok(());
+    }
+
+    let mute_activemodel = mute::ActiveModel {
+        id: ActiveValue::Set(Ulid::new().into()),
+        user_id: ActiveValue::Set(Some(user.id)),
+        domain: ActiveValue::Set(None),
+        thread_id: ActiveValue::Set(None),
+        created_at: ActiveValue::Set(Utc::now().fixed_offset()),
+        expires_at: ActiveValue::Set(None),
+    };
+    mute_activemodel
+        .insert(&tx)
+        .await
+        .context_internal_server_error("failed to insert to database")?;
+
+    tx.commit()
+        .await
+        .context_internal_server_error("failed to commit database transaction")?;
+    MuteFilter::invalidate();
+
+    Ok(())
+}
+
+async fn bookmark_post(uri: &str, data: &Data<State>) -> Result<(), Error> {
+    let uri = Url::parse(uri).context_bad_request("malformed post URL")?;
+    let post_uri: ObjectId<post::Model> = uri.into();
+    let post = post_uri.dereference(data).await?;
+
+    let bookmark_activemodel = bookmark::ActiveModel {
+        post_id: ActiveValue::Set(post.id),
+        created_at: ActiveValue::Set(Utc::now().fixed_offset()),
+    };
+    bookmark::Entity::insert(bookmark_activemodel)
+        .on_conflict(
+            OnConflict::column(bookmark::Column::PostId)
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(&*data.db)
+        .await
+        .context_internal_server_error("failed to insert to database")?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_following_accounts() {
+        let csv = "Account address,Show boosts,Notify on new posts,Languages\n\
+                   alice@example.com,true,false,\n\
+                   @bob@example.org,false,false,en\n";
+        assert_eq!(parse_csv(csv), vec!["alice@example.com", "bob@example.org"]);
+    }
+
+    #[test]
+    fn parses_headerless_lists() {
+        let csv = "https://example.com/notes/1\r\n\r\nhttps://example.com/notes/2\r\n";
+        assert_eq!(
+            parse_csv(csv),
+            vec!["https://example.com/notes/1", "https://example.com/notes/2"]
+        );
+    }
+}
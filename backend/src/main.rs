@@ -10,15 +10,26 @@ use tracing_subscriber::{prelude::__tracing_subscriber_SubscriberExt, util::Subs
 
 mod ap;
 mod config;
+mod delivery;
 mod dto;
 mod entity;
 mod entity_impl;
 mod error;
 mod fmt;
 mod handler;
+mod import;
+mod markup;
+mod mute;
 mod object_store;
+mod poll;
 mod queue;
+mod rate_limit;
+mod sanitize;
+mod scheduled_post;
+mod scope;
 mod state;
+mod two_factor;
+mod user_agent;
 mod util;
 
 async fn shutdown_signal(stopper: Stopper) {
@@ -81,11 +92,59 @@ async fn main() -> anyhow::Result<()> {
     let federation_config = FederationConfig::builder()
         .domain(&crate::config::CONFIG.public_domain)
         .app_data(state.clone())
+        .url_verifier(Box::new(crate::ap::block::DomainBlockVerifier::new(
+            state.db.clone(),
+        )))
         .debug(crate::config::CONFIG.debug)
         .build()
         .await
         .context("failed to build federation config")?;
 
+    tokio::spawn({
+        let federation_config = federation_config.clone();
+        async move {
+            if let Err(error) = crate::delivery::run_worker(federation_config).await {
+                tracing::error!("delivery worker failed\n{:?}", error.inner);
+            }
+        }
+    });
+
+    tokio::spawn({
+        let federation_config = federation_config.clone();
+        async move {
+            if let Err(error) = crate::poll::run_worker(federation_config).await {
+                tracing::error!("poll worker failed\n{:?}", error.inner);
+            }
+        }
+    });
+
+    tokio::spawn({
+        let federation_config = federation_config.clone();
+        async move {
+            if let Err(error) = crate::scheduled_post::run_worker(federation_config).await {
+                tracing::error!("scheduled post worker failed\n{:?}", error.inner);
+            }
+        }
+    });
+
+    tokio::spawn({
+        let federation_config = federation_config.clone();
+        async move {
+            if let Err(error) = crate::import::run_worker(federation_config).await {
+                tracing::error!("import worker failed\n{:?}", error.inner);
+            }
+        }
+    });
+
+    tokio::spawn({
+        let federation_config = federation_config.clone();
+        async move {
+            if let Err(error) = crate::sanitize::run_backfill(federation_config).await {
+                tracing::error!("failed to re-sanitize stored content\n{:?}", error.inner);
+            }
+        }
+    });
+
     let router = crate::handler::create_router(federation_config)
         .await
         .context("failed to create router")?;
@@ -0,0 +1,703 @@
+use std::{collections::HashMap, fmt::Write};
+
+use activitypub_federation::config::Data;
+use url::Url;
+
+use crate::{
+    ap::person::LocalPerson,
+    config::CONFIG,
+    dto::{Mention, PostFormat},
+    entity::user,
+    error::Error,
+    state::State,
+    util::hashtag_url,
+};
+
+impl PostFormat {
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            PostFormat::Plain => "text/plain",
+            PostFormat::Markdown => "text/markdown",
+            PostFormat::Mfm => "text/x.misskeymarkdown",
+        }
+    }
+}
+
+/// Post source rendered to HTML, with the tags found in it.
+#[derive(Debug)]
+pub struct RenderedPost {
+    pub content: String,
+    pub mentions: Vec<Mention>,
+    pub hashtags: Vec<String>,
+    pub emojis: Vec<String>,
+}
+
+/// Parses `source` written in `format`, resolves its mentions and renders it to HTML.
+/// Mentions which cannot be resolved are left as plain text.
+#[tracing::instrument(skip(data))]
+pub async fn render_post(
+    source: &str,
+    format: PostFormat,
+    data: &Data<State>,
+) -> Result<RenderedPost, Error> {
+    let blocks = parse_blocks(source, format);
+    let mut tags = Tags::default();
+    tags.collect_blocks(&blocks);
+
+    let local_person = LocalPerson::get(&*data.db).await?;
+    let mut mentions = Vec::new();
+    let mut resolved = HashMap::new();
+    for (handle, host) in tags.mentions {
+        let is_local = match &host {
+            Some(host) => host.eq_ignore_ascii_case(&CONFIG.public_domain),
+            None => true,
+        };
+        let user_uri = if is_local {
+            if !handle.eq_ignore_ascii_case(&local_person.0.user_handle) {
+                continue;
+            }
+            LocalPerson::id()
+        } else {
+            let host = host.as_deref().unwrap_or_default();
+            match user::Model::find_or_resolve(&handle, host, data).await {
+                Ok(user) => match user.uri.parse::<Url>() {
+                    Ok(uri) => uri,
+                    Err(_) => continue,
+                },
+                Err(error) => {
+                    tracing::warn!("failed to resolve @{handle}@{host}\n{:?}", error.inner);
+                    continue;
+                }
+            }
+        };
+        mentions.push(Mention {
+            user_uri: user_uri.clone(),
+            name: format!(
+                "@{}@{}",
+                handle,
+                host.as_deref().unwrap_or(CONFIG.public_domain.as_str())
+            ),
+        });
+        resolved.insert(mention_key(&handle, host.as_deref()), user_uri);
+    }
+
+    let mut content = String::new();
+    render_blocks(&blocks, &resolved, &mut content)?;
+
+    Ok(RenderedPost {
+        content,
+        mentions,
+        hashtags: tags.hashtags,
+        emojis: tags.emojis,
+    })
+}
+
+fn mention_key(handle: &str, host: Option<&str>) -> (String, Option<String>) {
+    (
+        handle.to_lowercase(),
+        host.map(|host| host.to_lowercase())
+            .filter(|host| host != &CONFIG.public_domain),
+    )
+}
+
+#[derive(Debug)]
+enum Block {
+    Paragraph(Vec<Inline>),
+    Quote(Vec<Block>),
+    Code(String),
+}
+
+#[derive(Debug)]
+enum Inline {
+    Text(String),
+    LineBreak,
+    Mention {
+        handle: String,
+        host: Option<String>,
+    },
+    Hashtag(String),
+    Emoji(String),
+    Url(String),
+    Link {
+        label: String,
+        url: String,
+    },
+    Bold(Vec<Inline>),
+    Italic(Vec<Inline>),
+    Strike(Vec<Inline>),
+    /// MFM function such as `$[x2 ...]`, of which only the content is rendered
+    Function(Vec<Inline>),
+    Code(String),
+}
+
+fn parse_blocks(source: &str, format: PostFormat) -> Vec<Block> {
+    let lines = source.lines().collect::<Vec<_>>();
+    let mut blocks = Vec::new();
+    let mut paragraph = Vec::new();
+
+    let flush = |paragraph: &mut Vec<&str>, blocks: &mut Vec<Block>| {
+        if !paragraph.is_empty() {
+            blocks.push(Block::Paragraph(parse_inlines(
+                &paragraph.join("\n"),
+                format,
+            )));
+            paragraph.clear();
+        }
+    };
+
+    let mut idx = 0;
+    while idx < lines.len() {
+        let line = lines[idx];
+        if format != PostFormat::Plain && line.trim_start().starts_with("```") {
+            flush(&mut paragraph, &mut blocks);
+            let code = lines[idx + 1..]
+                .iter()
+                .take_while(|line| !line.trim_start().starts_with("```"))
+                .copied()
+                .collect::<Vec<_>>();
+            idx += code.len() + 2;
+            blocks.push(Block::Code(code.join("\n")));
+        } else if format != PostFormat::Plain && line.starts_with('>') {
+            flush(&mut paragraph, &mut blocks);
+            let quote = lines[idx..]
+                .iter()
+                .take_while(|line| line.starts_with('>'))
+                .map(|line| {
+                    let line = &line[1..];
+                    line.strip_prefix(' ').unwrap_or(line)
+                })
+                .collect::<Vec<_>>();
+            idx += quote.len();
+            blocks.push(Block::Quote(parse_blocks(&quote.join("\n"), format)));
+        } else if line.trim().is_empty() {
+            flush(&mut paragraph, &mut blocks);
+            idx += 1;
+        } else {
+            paragraph.push(line);
+            idx += 1;
+        }
+    }
+    flush(&mut paragraph, &mut blocks);
+
+    blocks
+}
+
+fn parse_inlines(source: &str, format: PostFormat) -> Vec<Inline> {
+    let mut inlines = Vec::new();
+    let mut text = String::new();
+    let mut pos = 0;
+    while pos < source.len() {
+        let prev = source[..pos].chars().next_back();
+        if let Some((inline, len)) = parse_inline(&source[pos..], prev, format) {
+            if !text.is_empty() {
+                inlines.push(Inline::Text(std::mem::take(&mut text)));
+            }
+            inlines.push(inline);
+            pos += len;
+            continue;
+        }
+
+        let c = source[pos..].chars().next().unwrap_or_default();
+        if c == '\n' {
+            if !text.is_empty() {
+                inlines.push(Inline::Text(std::mem::take(&mut text)));
+            }
+            inlines.push(Inline::LineBreak);
+        } else {
+            text.push(c);
+        }
+        pos += c.len_utf8();
+    }
+    if !text.is_empty() {
+        inlines.push(Inline::Text(text));
+    }
+    inlines
+}
+
+/// Parses an inline node at the start of `rest`, returning it with its length in bytes.
+fn parse_inline(rest: &str, prev: Option<char>, format: PostFormat) -> Option<(Inline, usize)> {
+    let after_word = prev
+        .map(|c| c.is_alphanumeric() || c == '_')
+        .unwrap_or(false);
+
+    if format != PostFormat::Plain {
+        if let Some(inner) = rest.strip_prefix('`') {
+            let end = inner.find(['`', '\n'])?;
+            if inner[end..].starts_with('`') && end > 0 {
+                return Some((Inline::Code(inner[..end].to_string()), end + 2));
+            }
+        }
+        for (delimiter, ctor) in [
+            ("**", Inline::Bold as fn(Vec<Inline>) -> Inline),
+            ("~~", Inline::Strike),
+            ("*", Inline::Italic),
+            ("_", Inline::Italic),
+        ] {
+            if delimiter == "_" && after_word {
+                continue;
+            }
+            if let Some((inner, len)) = delimited(rest, delimiter) {
+                return Some((ctor(parse_inlines(inner, format)), len));
+            }
+        }
+        if format == PostFormat::Mfm {
+            if let Some((inner, len)) = mfm_function(rest) {
+                return Some((Inline::Function(parse_inlines(inner, format)), len));
+            }
+        }
+        if let Some(inner) = rest.strip_prefix('[') {
+            let label_end = inner.find("](")?;
+            let label = &inner[..label_end];
+            let url_start = label_end + 2;
+            let url_end = url_start + inner[url_start..].find(')')?;
+            let url = &inner[url_start..url_end];
+            if !label.contains('\n') && is_http_url(url) {
+                return Some((
+                    Inline::Link {
+                        label: label.to_string(),
+                        url: url.to_string(),
+                    },
+                    url_end + 2,
+                ));
+            }
+        }
+    }
+
+    if after_word {
+        return None;
+    }
+
+    if rest.starts_with("https://") || rest.starts_with("http://") {
+        let end = rest
+            .find(|c: char| c.is_whitespace() || matches!(c, '<' | '>' | '"'))
+            .unwrap_or(rest.len());
+        let mut url = &rest[..end];
+        loop {
+            let trimmed = url.trim_end_matches(['.', ',', ':', ';', '!', '?', '\'']);
+            let trimmed = if trimmed.ends_with(')') && !trimmed.contains('(') {
+                &trimmed[..trimmed.len() - 1]
+            } else {
+                trimmed
+            };
+            if trimmed == url {
+                break;
+            }
+            url = trimmed;
+        }
+        if is_http_url(url) {
+            return Some((Inline::Url(url.to_string()), url.len()));
+        }
+    }
+
+    if let Some(inner) = rest.strip_prefix('@') {
+        let handle_len = handle_len(inner);
+        if handle_len > 0 {
+            let handle = inner[..handle_len].to_string();
+            let host_len = inner[handle_len..]
+                .strip_prefix('@')
+                .map(host_len)
+                .unwrap_or(0);
+            let host = (host_len > 0)
+                .then(|| inner[handle_len + 1..handle_len + 1 + host_len].to_string());
+            let len = 1 + handle_len + if host_len > 0 { 1 + host_len } else { 0 };
+            return Some((Inline::Mention { handle, host }, len));
+        }
+    }
+
+    if let Some(inner) = rest.strip_prefix('#') {
+        if prev != Some('&') {
+            let end = inner
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(inner.len());
+            let name = &inner[..end];
+            if !name.is_empty() && !name.chars().all(|c| c.is_ascii_digit()) {
+                return Some((Inline::Hashtag(name.to_string()), 1 + end));
+            }
+        }
+    }
+
+    if let Some(inner) = rest.strip_prefix(':') {
+        let end = inner
+            .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '+' | '-')))
+            .unwrap_or(inner.len());
+        if end > 0 && inner[end..].starts_with(':') {
+            return Some((Inline::Emoji(inner[..end].to_string()), end + 2));
+        }
+    }
+
+    None
+}
+
+/// Finds `delimiter`-enclosed content at the start of `rest` on a single line.
+fn delimited<'a>(rest: &'a str, delimiter: &str) -> Option<(&'a str, usize)> {
+    let inner = rest.strip_prefix(delimiter)?;
+    let end = inner.find(delimiter)?;
+    let content = &inner[..end];
+    if content.is_empty()
+        || content.contains('\n')
+        || content.starts_with(char::is_whitespace)
+        || content.ends_with(char::is_whitespace)
+    {
+        return None;
+    }
+    Some((content, delimiter.len() * 2 + end))
+}
+
+/// Finds an MFM function `$[name.args content]` at the start of `rest`, returning its content
+/// and length. Nested brackets in the content are matched.
+fn mfm_function(rest: &str) -> Option<(&str, usize)> {
+    let inner = rest.strip_prefix("$[")?;
+    let name_end = inner
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | ',' | '=' | '-')))?;
+    if name_end == 0 || !inner[name_end..].starts_with(' ') {
+        return None;
+    }
+    let content_start = name_end + 1;
+    let mut depth = 0usize;
+    for (idx, c) in inner[content_start..].char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' if depth == 0 => {
+                let content_end = content_start + idx;
+                return Some((&inner[content_start..content_end], 2 + content_end + 1));
+            }
+            ']' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn handle_len(s: &str) -> usize {
+    let mut len = 0;
+    for (idx, c) in s.char_indices() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            len = idx + 1;
+        } else if matches!(c, '.' | '-') {
+            // dots and hyphens only inside of handles
+            let next = s[idx + 1..].chars().next();
+            if !next
+                .map(|c| c.is_ascii_alphanumeric() || c == '_')
+                .unwrap_or(false)
+            {
+                break;
+            }
+        } else {
+            break;
+        }
+    }
+    len
+}
+
+fn host_len(s: &str) -> usize {
+    let len = handle_len(s);
+    if s[..len].contains('.') {
+        len
+    } else {
+        0
+    }
+}
+
+fn is_http_url(url: &str) -> bool {
+    Url::parse(url)
+        .map(|url| matches!(url.scheme(), "http" | "https") && url.host().is_some())
+        .unwrap_or(false)
+}
+
+#[derive(Default)]
+struct Tags {
+    mentions: Vec<(String, Option<String>)>,
+    hashtags: Vec<String>,
+    emojis: Vec<String>,
+}
+
+impl Tags {
+    fn collect_blocks(&mut self, blocks: &[Block]) {
+        for block in blocks {
+            match block {
+                Block::Paragraph(inlines) => self.collect_inlines(inlines),
+                Block::Quote(blocks) => self.collect_blocks(blocks),
+                Block::Code(_) => {}
+            }
+        }
+    }
+
+    fn collect_inlines(&mut self, inlines: &[Inline]) {
+        for inline in inlines {
+            match inline {
+                Inline::Mention { handle, host } => {
+                    let key = mention_key(handle, host.as_deref());
+                    if !self
+                        .mentions
+                        .iter()
+                        .any(|(handle, host)| mention_key(handle, host.as_deref()) == key)
+                    {
+                        self.mentions.push((handle.clone(), host.clone()));
+                    }
+                }
+                Inline::Hashtag(name) => {
+                    if !self.hashtags.contains(name) {
+                        self.hashtags.push(name.clone());
+                    }
+                }
+                Inline::Emoji(name) => {
+                    if !self.emojis.contains(name) {
+                        self.emojis.push(name.clone());
+                    }
+                }
+                Inline::Bold(inlines)
+                | Inline::Italic(inlines)
+                | Inline::Strike(inlines)
+                | Inline::Function(inlines) => self.collect_inlines(inlines),
+                Inline::Text(_)
+                | Inline::LineBreak
+                | Inline::Url(_)
+                | Inline::Link { .. }
+                | Inline::Code(_) => {}
+            }
+        }
+    }
+}
+
+type ResolvedMentions = HashMap<(String, Option<String>), Url>;
+
+fn render_blocks(
+    blocks: &[Block],
+    mentions: &ResolvedMentions,
+    out: &mut String,
+) -> Result<(), Error> {
+    for block in blocks {
+        match block {
+            Block::Paragraph(inlines) => {
+                out.push_str("<p>");
+                render_inlines(inlines, mentions, out)?;
+                out.push_str("</p>");
+            }
+            Block::Quote(blocks) => {
+                out.push_str("<blockquote>");
+                render_blocks(blocks, mentions, out)?;
+                out.push_str("</blockquote>");
+            }
+            Block::Code(code) => {
+                let _ = write!(out, "<pre><code>{}</code></pre>", escape(code));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn render_inlines(
+    inlines: &[Inline],
+    mentions: &ResolvedMentions,
+    out: &mut String,
+) -> Result<(), Error> {
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) => out.push_str(&escape(text)),
+            Inline::LineBreak => out.push_str("<br>"),
+            Inline::Mention { handle, host } => {
+                match mentions.get(&mention_key(handle, host.as_deref())) {
+                    Some(uri) => {
+                        let _ = write!(
+                            out,
+                            r#"<span class="h-card"><a href="{}" class="u-url mention">@<span>{}</span></a></span>"#,
+                            escape(uri.as_str()),
+                            escape(handle)
+                        );
+                    }
+                    None => {
+                        out.push('@');
+                        out.push_str(&escape(handle));
+                        if let Some(host) = host {
+                            out.push('@');
+                            out.push_str(&escape(host));
+                        }
+                    }
+                }
+            }
+            Inline::Hashtag(name) => {
+                let url = hashtag_url(name)?;
+                let _ = write!(
+                    out,
+                    r#"<a href="{}" class="mention hashtag">#<span>{}</span></a>"#,
+                    escape(url.as_str()),
+                    escape(name)
+                );
+            }
+            Inline::Emoji(name) => {
+                let _ = write!(out, ":{}:", escape(name));
+            }
+            Inline::Url(url) => {
+                let _ = write!(out, r#"<a href="{0}">{0}</a>"#, escape(url));
+            }
+            Inline::Link { label, url } => {
+                let _ = write!(out, r#"<a href="{}">{}</a>"#, escape(url), escape(label));
+            }
+            Inline::Bold(inlines) => {
+                out.push_str("<strong>");
+                render_inlines(inlines, mentions, out)?;
+                out.push_str("</strong>");
+            }
+            Inline::Italic(inlines) => {
+                out.push_str("<em>");
+                render_inlines(inlines, mentions, out)?;
+                out.push_str("</em>");
+            }
+            Inline::Strike(inlines) => {
+                out.push_str("<del>");
+                render_inlines(inlines, mentions, out)?;
+                out.push_str("</del>");
+            }
+            Inline::Function(inlines) => render_inlines(inlines, mentions, out)?,
+            Inline::Code(code) => {
+                let _ = write!(out, "<code>{}</code>", escape(code));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(source: &str, format: PostFormat) -> String {
+        let mut out = String::new();
+        render_blocks(&parse_blocks(source, format), &HashMap::new(), &mut out).unwrap();
+        out
+    }
+
+    fn tags(source: &str, format: PostFormat) -> Tags {
+        let mut tags = Tags::default();
+        tags.collect_blocks(&parse_blocks(source, format));
+        tags
+    }
+
+    #[test]
+    fn renders_nested_emphasis() {
+        assert_eq!(
+            render("**bold _italic_** and ~~*gone*~~", PostFormat::Markdown),
+            "<p><strong>bold <em>italic</em></strong> and <del><em>gone</em></del></p>"
+        );
+        assert_eq!(
+            render("snake_case_name and ** spaced **", PostFormat::Markdown),
+            "<p>snake_case_name and ** spaced **</p>"
+        );
+        assert_eq!(render("**bold**", PostFormat::Plain), "<p>**bold**</p>");
+    }
+
+    #[test]
+    fn renders_code() {
+        assert_eq!(
+            render("`**not bold** <b>` and ``", PostFormat::Markdown),
+            "<p><code>**not bold** &lt;b&gt;</code> and ``</p>"
+        );
+        assert_eq!(
+            render("```\n#tag @alice\n```\nafter", PostFormat::Markdown),
+            "<pre><code>#tag @alice</code></pre><p>after</p>"
+        );
+        assert!(tags("```\n#tag @alice\n```", PostFormat::Markdown)
+            .hashtags
+            .is_empty());
+    }
+
+    #[test]
+    fn renders_links() {
+        assert_eq!(
+            render(
+                "[label](https://example.com/a) and https://example.com/b).",
+                PostFormat::Markdown
+            ),
+            r#"<p><a href="https://example.com/a">label</a> and <a href="https://example.com/b">https://example.com/b</a>).</p>"#
+        );
+        assert_eq!(
+            render("[label](javascript:alert(1))", PostFormat::Markdown),
+            "<p>[label](javascript:alert(1))</p>"
+        );
+    }
+
+    #[test]
+    fn collects_mentions() {
+        let tags = tags(
+            "@alice @Alice@Example.com @alice@example.com, user@example.com @bob.",
+            PostFormat::Plain,
+        );
+        assert_eq!(
+            tags.mentions,
+            vec![
+                ("alice".to_string(), None),
+                ("Alice".to_string(), Some("Example.com".to_string())),
+                ("bob".to_string(), None),
+            ]
+        );
+        assert_eq!(
+            render("@alice@example.com", PostFormat::Plain),
+            "<p>@alice@example.com</p>"
+        );
+    }
+
+    #[test]
+    fn collects_hashtags_and_emojis() {
+        let tags = tags(
+            "#rust #Rust_lang #123 a#b &#39; :blob_cat: :not emoji:",
+            PostFormat::Markdown,
+        );
+        assert_eq!(tags.hashtags, vec!["rust", "Rust_lang"]);
+        assert_eq!(tags.emojis, vec!["blob_cat"]);
+        assert!(render("#rust", PostFormat::Plain)
+            .contains(r#"class="mention hashtag">#<span>rust</span></a>"#));
+    }
+
+    #[test]
+    fn renders_mfm_functions() {
+        assert_eq!(
+            render("$[x2 **big** $[spin.speed=2s #spin]]!", PostFormat::Mfm),
+            format!(
+                r#"<p><strong>big</strong> <a href="https://{}/tags/spin" class="mention hashtag">#<span>spin</span></a>!</p>"#,
+                CONFIG.public_domain
+            )
+        );
+        assert_eq!(
+            render("$[x2 unclosed", PostFormat::Mfm),
+            "<p>$[x2 unclosed</p>"
+        );
+        assert_eq!(
+            render("$[x2 text]", PostFormat::Markdown),
+            "<p>$[x2 text]</p>"
+        );
+    }
+
+    #[test]
+    fn handles_non_ascii_input() {
+        assert_eq!(
+            render("**日本語** _é_ `ß` ~~🦀~~", PostFormat::Markdown),
+            "<p><strong>日本語</strong> <em>é</em> <code>ß</code> <del>🦀</del></p>"
+        );
+        let tags = tags(
+            "@ünïcode @alice.日本 #日本語 :ä: https://例え.jp/パス。 > 引用 $[x2 ü]",
+            PostFormat::Mfm,
+        );
+        assert_eq!(tags.mentions, vec![("alice".to_string(), None)]);
+        assert_eq!(tags.hashtags, vec!["日本語"]);
+        for source in [
+            "é*", "*é", "`é", "[é](", "@a.é", "$[é", "> é", "```é", "#", ":é",
+        ] {
+            render(source, PostFormat::Mfm);
+        }
+    }
+}
@@ -0,0 +1,277 @@
+use std::{
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use sea_orm::{
+    sea_query::{Condition, Expr},
+    ColumnTrait, ConnectionTrait, EntityTrait, PaginatorTrait, QueryFilter, Value,
+};
+
+use crate::{
+    entity::{mute, notification, post},
+    error::{Context, Result},
+    queue::{Event, Notification, Update},
+};
+
+/// How long [`MuteFilter::cached`] reuses loaded mutes, bounding the delay for mutes to expire
+const CACHE_TTL: Duration = Duration::from_secs(10);
+
+/// Last loaded mutes and when they were loaded
+type CachedFilter = Option<(Instant, Arc<MuteFilter>)>;
+
+static CACHE: Lazy<RwLock<CachedFilter>> = Lazy::new(|| RwLock::new(None));
+
+/// Currently active mutes, loaded once and applied to posts, notifications and events as
+/// subqueries.
+#[derive(Debug, Default)]
+pub struct MuteFilter {
+    user_ids: Vec<uuid::Uuid>,
+    domains: Vec<String>,
+    /// Muted thread roots, whose descendants are muted as well
+    thread_ids: Vec<uuid::Uuid>,
+}
+
+impl MuteFilter {
+    #[tracing::instrument(skip(db))]
+    pub async fn load(db: &impl ConnectionTrait) -> Result<Self> {
+        let mutes = mute::Entity::find()
+            .filter(
+                Condition::any()
+                    .add(mute::Column::ExpiresAt.is_null())
+                    .add(mute::Column::ExpiresAt.gt(Utc::now())),
+            )
+            .all(db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+
+        let mut this = Self::default();
+        for mute in mutes {
+            if let Some(user_id) = mute.user_id {
+                this.user_ids.push(user_id);
+            }
+            if let Some(domain) = mute.domain {
+                this.domains.push(domain);
+            }
+            if let Some(thread_id) = mute.thread_id {
+                this.thread_ids.push(thread_id);
+            }
+        }
+        Ok(this)
+    }
+
+    /// Loads mutes shared by all event streams, which would otherwise query them for every event.
+    /// Changes to mutes take effect once [`MuteFilter::invalidate`] is called.
+    pub async fn cached(db: &impl ConnectionTrait) -> Result<Arc<Self>> {
+        if let Some((loaded_at, filter)) = &*CACHE.read().unwrap_or_else(|error| error.into_inner())
+        {
+            if loaded_at.elapsed() < CACHE_TTL {
+                return Ok(filter.clone());
+            }
+        }
+
+        let filter = Arc::new(Self::load(db).await?);
+        *CACHE.write().unwrap_or_else(|error| error.into_inner()) =
+            Some((Instant::now(), filter.clone()));
+        Ok(filter)
+    }
+
+    /// Makes [`MuteFilter::cached`] load mutes again after they are changed.
+    pub fn invalidate() {
+        *CACHE.write().unwrap_or_else(|error| error.into_inner()) = None;
+    }
+
+    fn is_empty(&self) -> bool {
+        self.user_ids.is_empty() && self.domains.is_empty() && self.thread_ids.is_empty()
+    }
+
+    /// Subquery of the IDs of muted users, either muted directly or by domain.
+    fn muted_users_query(&self, values: &mut Vec<Value>) -> Option<String> {
+        let mut conditions = Vec::new();
+        if !self.user_ids.is_empty() {
+            let placeholders = bind(values, self.user_ids.iter().copied());
+            conditions.push(format!(r#""id" IN ({placeholders})"#));
+        }
+        if !self.domains.is_empty() {
+            let placeholders = bind(values, self.domains.iter().cloned());
+            conditions.push(format!(r#""host" IN ({placeholders})"#));
+        }
+        if conditions.is_empty() {
+            return None;
+        }
+        Some(format!(
+            r#"SELECT "id" FROM "user" WHERE {}"#,
+            conditions.join(" OR ")
+        ))
+    }
+
+    /// Subquery of the IDs of muted thread roots and all of their descendants.
+    fn muted_posts_query(&self, values: &mut Vec<Value>) -> Option<String> {
+        if self.thread_ids.is_empty() {
+            return None;
+        }
+        let placeholders = bind(values, self.thread_ids.iter().copied());
+        Some(format!(
+            r#"WITH RECURSIVE "muted_thread" AS (
+                SELECT "id" FROM "post" WHERE "id" IN ({placeholders})
+                UNION
+                SELECT "reply"."id" FROM "post" AS "reply"
+                JOIN "muted_thread" ON "reply"."reply_id" = "muted_thread"."id"
+            ) SELECT "id" FROM "muted_thread""#
+        ))
+    }
+
+    /// Condition excluding muted posts, to be applied to queries over `post`.
+    pub fn post_condition(&self) -> Condition {
+        let mut condition = Condition::all();
+
+        let mut values = Vec::new();
+        if let Some(users) = self.muted_users_query(&mut values) {
+            condition = condition.add(Expr::cust_with_values(
+                format!(r#"("post"."user_id" IS NULL OR "post"."user_id" NOT IN ({users}))"#),
+                values,
+            ));
+        }
+
+        let mut values = Vec::new();
+        if let Some(posts) = self.muted_posts_query(&mut values) {
+            condition = condition.add(Expr::cust_with_values(
+                format!(r#""post"."id" NOT IN ({posts})"#),
+                values,
+            ));
+        }
+
+        condition
+    }
+
+    /// Condition excluding notifications about muted users, muted threads and posts of muted
+    /// users, to be applied to queries over `notification`.
+    pub fn notification_condition(&self) -> Condition {
+        let mut condition = Condition::all();
+
+        let mut values = Vec::new();
+        if let Some(users) = self.muted_users_query(&mut values) {
+            condition = condition.add(Expr::cust_with_values(
+                format!(
+                    r#"(NOT ("notification"."user_ids" && ARRAY({users}))
+                    AND ("notification"."post_id" IS NULL OR "notification"."post_id" NOT IN (
+                        SELECT "id" FROM "post" WHERE "user_id" IN ({users})
+                    )))"#
+                ),
+                values,
+            ));
+        }
+
+        let mut values = Vec::new();
+        if let Some(posts) = self.muted_posts_query(&mut values) {
+            condition = condition.add(Expr::cust_with_values(
+                format!(
+                    r#"("notification"."post_id" IS NULL OR "notification"."post_id" NOT IN ({posts}))"#
+                ),
+                values,
+            ));
+        }
+
+        condition
+    }
+
+    async fn is_post_id_muted(
+        &self,
+        post_id: uuid::Uuid,
+        db: &impl ConnectionTrait,
+    ) -> Result<bool> {
+        if self.is_empty() {
+            return Ok(false);
+        }
+        let unmuted_count = post::Entity::find_by_id(post_id)
+            .filter(self.post_condition())
+            .count(db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        Ok(unmuted_count == 0)
+    }
+
+    /// Checks a notification stored by [`Event::send`].
+    pub async fn is_notification_muted(
+        &self,
+        notification: &Notification,
+        db: &impl ConnectionTrait,
+    ) -> Result<bool> {
+        if self.is_empty() {
+            return Ok(false);
+        }
+        let unmuted_count = notification::Entity::find_by_id(notification.id)
+            .filter(self.notification_condition())
+            .count(db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        Ok(unmuted_count == 0)
+    }
+
+    pub async fn is_event_muted(&self, event: &Event, db: &impl ConnectionTrait) -> Result<bool> {
+        match event {
+            Event::Update(Update::CreatePost { post_id })
+            | Event::Update(Update::UpdatePost { post_id }) => {
+                self.is_post_id_muted((*post_id).into(), db).await
+            }
+            Event::Update(_) => Ok(false),
+            Event::Notification(notification) => self.is_notification_muted(notification, db).await,
+        }
+    }
+}
+
+/// Appends `items` to `values`, returning their numbered placeholders for custom SQL.
+fn bind<T>(values: &mut Vec<Value>, items: impl Iterator<Item = T>) -> String
+where
+    T: Into<Value>,
+{
+    items
+        .map(|item| {
+            values.push(item.into());
+            format!("${}", values.len())
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{DbBackend, QueryTrait};
+
+    use super::*;
+
+    #[test]
+    fn binds_mutes_as_values() {
+        let user_id = uuid::Uuid::from_u128(1);
+        let thread_id = uuid::Uuid::from_u128(2);
+        let filter = MuteFilter {
+            user_ids: vec![user_id],
+            domains: vec!["example.com".to_string()],
+            thread_ids: vec![thread_id],
+        };
+
+        let statement = post::Entity::find()
+            .filter(filter.post_condition())
+            .build(DbBackend::Postgres);
+        assert!(statement.sql.contains(r#""id" IN ($1) OR "host" IN ($2)"#));
+        assert!(statement.sql.contains(r#"WHERE "id" IN ($3)"#));
+        assert_eq!(
+            statement.values.unwrap().0,
+            vec![
+                Value::from(user_id),
+                Value::from("example.com"),
+                Value::from(thread_id)
+            ]
+        );
+
+        let statement = notification::Entity::find()
+            .filter(filter.notification_condition())
+            .build(DbBackend::Postgres);
+        assert!(statement
+            .sql
+            .contains(r#""notification"."user_ids" && ARRAY(SELECT"#));
+        assert_eq!(statement.values.unwrap().0.len(), 5);
+    }
+}
@@ -0,0 +1,86 @@
+use std::time::Duration;
+
+use activitypub_federation::config::{Data, FederationConfig};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, IntoActiveModel, ModelTrait,
+    PaginatorTrait, QueryFilter,
+};
+
+use crate::{
+    entity::{poll, poll_vote, post},
+    error::{Context, Error},
+    queue::{Event, Notification, NotificationType, Update},
+    state::State,
+};
+
+const POLL_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Closes polls past their end time, notifying us of polls we have created or voted on.
+pub async fn run_worker(federation_config: FederationConfig<State>) -> Result<(), Error> {
+    let data = federation_config.to_request_data();
+
+    tracing::info!("starting poll worker...");
+    while !data.stopper.is_stopped() {
+        if let Err(error) = close_ended(&data).await {
+            tracing::error!("failed to close ended polls\n{:?}", error.inner);
+        }
+
+        data.stopper
+            .stop_future(tokio::time::sleep(POLL_CHECK_INTERVAL))
+            .await;
+    }
+    tracing::info!("poll worker stopped");
+
+    Ok(())
+}
+
+#[tracing::instrument(skip(data))]
+async fn close_ended(data: &Data<State>) -> Result<(), Error> {
+    let polls = poll::Entity::find()
+        .filter(poll::Column::IsEnded.eq(false))
+        .filter(poll::Column::EndsAt.lte(Utc::now()))
+        .find_also_related(post::Entity)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+
+    for (poll, post) in polls {
+        let Some(post) = post else {
+            continue;
+        };
+
+        let local_vote_count = poll
+            .find_related(poll_vote::Entity)
+            .filter(poll_vote::Column::UserId.is_null())
+            .count(&*data.db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+
+        let mut poll_activemodel = poll.into_active_model();
+        poll_activemodel.is_ended = ActiveValue::Set(true);
+        let poll = poll_activemodel
+            .update(&*data.db)
+            .await
+            .context_internal_server_error("failed to update database")?;
+
+        let event = Event::Update(Update::UpdatePost {
+            post_id: post.id.into(),
+        });
+        event.send(&*data.db).await?;
+
+        if post.user_id.is_none() {
+            if let Err(error) = poll.send_update(data).await {
+                tracing::error!("failed to send ended poll\n{:?}", error.inner);
+            }
+        }
+        if post.user_id.is_none() || local_vote_count > 0 {
+            let event = Event::Notification(Notification::new(NotificationType::PollEnded {
+                post_id: post.id.into(),
+            }));
+            event.send(&*data.db).await?;
+        }
+    }
+
+    Ok(())
+}
@@ -1,16 +1,21 @@
-use std::convert::Infallible;
+use std::{convert::Infallible, sync::Arc};
 
 use axum::response::sse::Event as SseEvent;
 use futures_util::{Stream, StreamExt};
 use sea_orm::{
-    ActiveModelTrait, ActiveValue, ConnectionTrait, DbBackend, Statement, TransactionTrait,
+    ActiveModelTrait, ActiveValue, ConnectionTrait, DatabaseConnection, DbBackend, EntityTrait,
+    QuerySelect, Statement, TransactionTrait,
 };
 use serde::{Deserialize, Serialize};
 use sqlx_postgres::{PgListener, PgNotification};
 use ulid::Ulid;
 use utoipa::ToSchema;
 
-use crate::{entity::notification, error::Error};
+use crate::{
+    entity::{notification, reaction},
+    error::Error,
+    mute::MuteFilter,
+};
 
 const EVENT_CHANNEL_NAME: &str = "event";
 
@@ -24,6 +29,11 @@ pub enum Update {
         post_id: Ulid,
     },
     #[serde(rename_all = "camelCase")]
+    UpdatePost {
+        #[schema(value_type = String, format = "ulid")]
+        post_id: Ulid,
+    },
+    #[serde(rename_all = "camelCase")]
     DeletePost {
         #[schema(value_type = String, format = "ulid")]
         post_id: Ulid,
@@ -48,6 +58,14 @@ pub enum Update {
         #[schema(value_type = String, format = "ulid")]
         user_id: Ulid,
     },
+    #[serde(rename_all = "camelCase")]
+    UpdateImport {
+        #[schema(value_type = String, format = "ulid")]
+        import_id: Ulid,
+        total_count: u32,
+        processed_count: u32,
+        failed_count: u32,
+    },
 }
 
 #[derive(Debug, Deserialize, Serialize, ToSchema)]
@@ -74,6 +92,11 @@ pub enum NotificationType {
         user_id: Ulid,
     },
     #[serde(rename_all = "camelCase")]
+    FollowRequested {
+        #[schema(value_type = String, format = "ulid")]
+        user_id: Ulid,
+    },
+    #[serde(rename_all = "camelCase")]
     CreateReport {
         #[schema(value_type = String, format = "ulid")]
         report_id: Ulid,
@@ -84,6 +107,11 @@ pub enum NotificationType {
         post_id: Ulid,
     },
     #[serde(rename_all = "camelCase")]
+    Replied {
+        #[schema(value_type = String, format = "ulid")]
+        post_id: Ulid,
+    },
+    #[serde(rename_all = "camelCase")]
     Reposted {
         #[schema(value_type = String, format = "ulid")]
         user_id: Ulid,
@@ -102,6 +130,23 @@ pub enum NotificationType {
         #[schema(value_type = String, format = "ulid")]
         reaction_id: Ulid,
     },
+    #[serde(rename_all = "camelCase")]
+    Moved {
+        #[schema(value_type = String, format = "ulid")]
+        user_id: Ulid,
+        #[schema(value_type = String, format = "ulid")]
+        target_user_id: Ulid,
+    },
+    #[serde(rename_all = "camelCase")]
+    PollEnded {
+        #[schema(value_type = String, format = "ulid")]
+        post_id: Ulid,
+    },
+    #[serde(rename_all = "camelCase")]
+    LoginLockedOut {
+        #[schema(value_type = String, format = "ulid")]
+        lockout_id: Ulid,
+    },
 }
 
 #[derive(Debug, Deserialize, Serialize, ToSchema)]
@@ -112,6 +157,53 @@ pub struct Notification {
     pub ty: NotificationType,
 }
 
+impl NotificationType {
+    /// Users and post the notification is about, stored along the payload so that mutes can be
+    /// applied in queries.
+    async fn subjects(
+        &self,
+        db: &impl ConnectionTrait,
+    ) -> crate::error::Result<(Vec<uuid::Uuid>, Option<uuid::Uuid>)> {
+        use crate::error::Context;
+
+        Ok(match self {
+            NotificationType::AcceptFollow { user_id }
+            | NotificationType::RejectFollow { user_id }
+            | NotificationType::CreateFollower { user_id }
+            | NotificationType::DeleteFollower { user_id }
+            | NotificationType::FollowRequested { user_id } => (vec![(*user_id).into()], None),
+            NotificationType::Moved {
+                user_id,
+                target_user_id,
+            } => (vec![(*user_id).into(), (*target_user_id).into()], None),
+            NotificationType::Reposted { user_id, post_id } => {
+                (vec![(*user_id).into()], Some((*post_id).into()))
+            }
+            NotificationType::Mentioned { post_id }
+            | NotificationType::Replied { post_id }
+            | NotificationType::Quoted { post_id }
+            | NotificationType::PollEnded { post_id } => (Vec::new(), Some((*post_id).into())),
+            NotificationType::Reacted {
+                post_id,
+                reaction_id,
+            } => {
+                let user_id = reaction::Entity::find_by_id(*reaction_id)
+                    .select_only()
+                    .column(reaction::Column::UserId)
+                    .into_tuple::<Option<uuid::Uuid>>()
+                    .one(db)
+                    .await
+                    .context_internal_server_error("failed to query database")?
+                    .flatten();
+                (user_id.into_iter().collect(), Some((*post_id).into()))
+            }
+            NotificationType::CreateReport { .. } | NotificationType::LoginLockedOut { .. } => {
+                (Vec::new(), None)
+            }
+        })
+    }
+}
+
 impl Notification {
     pub fn new(ty: NotificationType) -> Self {
         Self {
@@ -141,10 +233,13 @@ impl Event {
         if let Event::Notification(notification) = &self {
             let payload = serde_json::to_value(&notification.ty)
                 .context_internal_server_error("failed to serialize notification payload")?;
+            let (user_ids, post_id) = notification.ty.subjects(&tx).await?;
 
             let notification_activemodel = notification::ActiveModel {
                 id: ActiveValue::Set(notification.id.into()),
                 payload: ActiveValue::Set(payload),
+                user_ids: ActiveValue::Set(user_ids),
+                post_id: ActiveValue::Set(post_id),
             };
             notification_activemodel
                 .insert(&tx)
@@ -176,42 +271,75 @@ impl Event {
     }
 }
 
-fn make_sse_event(msg: PgNotification) -> anyhow::Result<SseEvent> {
+/// Returns `None` if the event is muted.
+async fn parse_event(
+    msg: PgNotification,
+    db: &DatabaseConnection,
+) -> anyhow::Result<Option<Event>> {
     use anyhow::Context;
 
     let payload = msg.payload();
     let payload: Event =
         serde_json::from_str(payload).context("failed to deserialize Redis channel payload")?;
-    let event = SseEvent::default()
-        .json_data(payload)
-        .context("failed to construct SSE event")?;
-    Ok(event)
+
+    let mute_filter = MuteFilter::cached(db).await.map_err(|error| error.inner)?;
+    if mute_filter
+        .is_event_muted(&payload, db)
+        .await
+        .map_err(|error| error.inner)?
+    {
+        return Ok(None);
+    }
+
+    Ok(Some(payload))
 }
 
-pub async fn event_stream(
+/// Listens to unmuted events sent by [`Event::send`].
+pub async fn listen_events(
     mut pg_listener: PgListener,
-) -> Result<impl Stream<Item = Result<SseEvent, Infallible>>, Error> {
+    db: Arc<DatabaseConnection>,
+) -> Result<impl Stream<Item = Event>, Error> {
     use crate::error::Context;
 
     pg_listener
         .listen(EVENT_CHANNEL_NAME)
         .await
         .context_internal_server_error("failed to listen Postgres channel")?;
-    let stream = pg_listener.into_stream().filter_map(|msg| {
-        let opt = match msg {
-            Ok(msg) => match make_sse_event(msg) {
-                Ok(event) => Some(Result::<_, Infallible>::Ok(event)),
+    let stream = pg_listener.into_stream().filter_map(move |msg| {
+        let db = db.clone();
+        async move {
+            match msg {
+                Ok(msg) => match parse_event(msg, &db).await {
+                    Ok(event) => event,
+                    Err(error) => {
+                        tracing::error!("failed to parse event\n{:?}", error);
+                        None
+                    }
+                },
                 Err(error) => {
-                    tracing::error!("failed to make SSE event\n{:?}", error);
+                    tracing::error!("failed to listen from Postgres channel\n{:?}", error);
                     None
                 }
-            },
-            Err(error) => {
-                tracing::error!("failed to listen from Postgres channel\n{:?}", error);
-                None
             }
-        };
-        async move { opt }
+        }
     });
     Ok(stream)
 }
+
+pub async fn event_stream(
+    pg_listener: PgListener,
+    db: Arc<DatabaseConnection>,
+) -> Result<impl Stream<Item = Result<SseEvent, Infallible>>, Error> {
+    let stream = listen_events(pg_listener, db)
+        .await?
+        .filter_map(|event| async move {
+            match SseEvent::default().json_data(event) {
+                Ok(event) => Some(Ok(event)),
+                Err(error) => {
+                    tracing::error!("failed to construct SSE event\n{:?}", error);
+                    None
+                }
+            }
+        });
+    Ok(stream)
+}
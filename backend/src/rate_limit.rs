@@ -0,0 +1,296 @@
+//! In-memory rate limiting, and lockouts of client IPs after repeated failed logins.
+
+use std::{net::IpAddr, num::NonZeroU32, time::Duration};
+
+use activitypub_federation::config::FederationConfig;
+use async_trait::async_trait;
+use axum::{
+    extract::FromRequestParts,
+    headers::{authorization::Bearer, Authorization, HeaderMapExt},
+    http::{request::Parts, Extensions, HeaderMap, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use axum_client_ip::SecureClientIp;
+use chrono::Utc;
+use governor::{
+    clock::{Clock, DefaultClock},
+    state::keyed::DefaultKeyedStateStore,
+    Quota, RateLimiter,
+};
+use once_cell::sync::Lazy;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
+    QueryOrder, QuerySelect, TransactionTrait,
+};
+use ulid::Ulid;
+
+use crate::{
+    config::CONFIG,
+    entity::{access_key, login_lockout},
+    error::{Context, Error, Result},
+    format_err,
+    queue::{Event, Notification, NotificationType},
+    state::State,
+    util::hash_token,
+};
+
+type KeyedRateLimiter = RateLimiter<String, DefaultKeyedStateStore<String>, DefaultClock>;
+
+/// Number of keys above which the ones with a fully replenished budget are forgotten
+const MAX_KEYS: usize = 10_000;
+
+/// IP of lockouts applying to every client, after too many failed logins across all IPs
+const ACCOUNT_LOCKOUT_IP: &str = "*";
+
+static LIMITERS: Lazy<Limiters> = Lazy::new(|| {
+    let minute = Duration::from_secs(60);
+    Limiters::new(
+        quota(CONFIG.rate_limit_per_minute, minute),
+        quota(CONFIG.login_rate_limit_per_minute, minute),
+        quota(CONFIG.resolve_rate_limit_per_minute, minute),
+        quota(
+            CONFIG.login_lockout_failures,
+            minute * CONFIG.login_lockout_minutes,
+        ),
+        quota(
+            CONFIG.account_lockout_failures,
+            minute * CONFIG.login_lockout_minutes,
+        ),
+    )
+});
+
+struct Limiters {
+    clock: DefaultClock,
+    api: KeyedRateLimiter,
+    login: KeyedRateLimiter,
+    resolve: KeyedRateLimiter,
+    login_failure: KeyedRateLimiter,
+    account_login_failure: KeyedRateLimiter,
+}
+
+impl Limiters {
+    fn new(
+        api: Quota,
+        login: Quota,
+        resolve: Quota,
+        login_failure: Quota,
+        account_login_failure: Quota,
+    ) -> Self {
+        let clock = DefaultClock::default();
+        let limiter = |quota| RateLimiter::new(quota, DefaultKeyedStateStore::default(), &clock);
+        Self {
+            api: limiter(api),
+            login: limiter(login),
+            resolve: limiter(resolve),
+            login_failure: limiter(login_failure),
+            account_login_failure: limiter(account_login_failure),
+            clock,
+        }
+    }
+
+    /// Spends one from the budget of each key. Returns the seconds to wait on the first key
+    /// without budget left.
+    fn check(&self, limiter: &KeyedRateLimiter, keys: &[String]) -> std::result::Result<(), u64> {
+        if limiter.len() > MAX_KEYS {
+            limiter.retain_recent();
+        }
+        for key in keys {
+            if let Err(not_until) = limiter.check_key(key) {
+                return Err(retry_after_secs(not_until.wait_time_from(self.clock.now())));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Allows `count` requests at once, replenished evenly over `period`.
+fn quota(count: u32, period: Duration) -> Quota {
+    let count = NonZeroU32::new(count).unwrap_or(NonZeroU32::MIN);
+    Quota::with_period((period / count.get()).max(Duration::from_nanos(1)))
+        .unwrap()
+        .allow_burst(count)
+}
+
+/// Rounds up to whole seconds for the `Retry-After` header, which is at least 1.
+fn retry_after_secs(wait: Duration) -> u64 {
+    (wait.as_secs() + u64::from(wait.subsec_nanos() > 0)).max(1)
+}
+
+/// Client IP taken from `CONFIG.client_ip_source`, which unlike `InsecureClientIp` can't be
+/// spoofed by clients.
+pub struct ClientIp(pub IpAddr);
+
+impl ClientIp {
+    fn from(headers: &HeaderMap, extensions: &Extensions) -> Result<Self> {
+        SecureClientIp::from(&CONFIG.client_ip_source, headers, extensions)
+            .map(|SecureClientIp(ip)| ClientIp(ip))
+            .ok()
+            .context_bad_request("failed to determine client IP")
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ClientIp
+where
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self> {
+        Self::from(&parts.headers, &parts.extensions)
+    }
+}
+
+/// Limits requests by client IP, and by access key if a valid one is given.
+pub async fn limit_api<B>(req: Request<B>, next: Next<B>) -> Response {
+    limit(&LIMITERS.api, req, next).await
+}
+
+/// Limits login attempts, more strictly than [`limit_api`].
+pub async fn limit_login<B>(req: Request<B>, next: Next<B>) -> Response {
+    limit(&LIMITERS.login, req, next).await
+}
+
+/// Limits requests fetching remote objects, more strictly than [`limit_api`].
+pub async fn limit_resolve<B>(req: Request<B>, next: Next<B>) -> Response {
+    limit(&LIMITERS.resolve, req, next).await
+}
+
+async fn limit<B>(limiter: &KeyedRateLimiter, req: Request<B>, next: Next<B>) -> Response {
+    let ClientIp(ip) = match ClientIp::from(req.headers(), req.extensions()) {
+        Ok(client_ip) => client_ip,
+        Err(error) => return error.into_response(),
+    };
+    let mut keys = vec![format!("ip:{}", ip)];
+    match access_key_id(&req).await {
+        Ok(Some(access_key_id)) => keys.push(format!("key:{}", access_key_id)),
+        Ok(None) => {}
+        Err(error) => return error.into_response(),
+    }
+
+    if let Err(retry_after) = LIMITERS.check(limiter, &keys) {
+        return format_err!(TOO_MANY_REQUESTS, "too many requests")
+            .with_retry_after(retry_after)
+            .into_response();
+    }
+    next.run(req).await
+}
+
+/// Resolves the bearer token of `req` to the ID of its access key, so that neither invalid
+/// tokens nor secrets end up as keys of the limiters.
+async fn access_key_id<B>(req: &Request<B>) -> Result<Option<uuid::Uuid>> {
+    let Some(Authorization(bearer)) = req.headers().typed_get::<Authorization<Bearer>>() else {
+        return Ok(None);
+    };
+    let data = req
+        .extensions()
+        .get::<FederationConfig<State>>()
+        .context_internal_server_error("federation config not found")?
+        .to_request_data();
+    access_key::Entity::find()
+        .filter(access_key::Column::SecretHash.eq(hash_token(bearer.token())))
+        .select_only()
+        .column(access_key::Column::Id)
+        .into_tuple::<uuid::Uuid>()
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")
+}
+
+/// Fails if `ip`, or every client, is locked out after too many failed logins.
+pub async fn check_lockout(ip: IpAddr, db: &impl ConnectionTrait) -> Result<()> {
+    let now = Utc::now();
+    let lockout = login_lockout::Entity::find()
+        .filter(login_lockout::Column::Ip.is_in([ip.to_string(), ACCOUNT_LOCKOUT_IP.to_string()]))
+        .filter(login_lockout::Column::LockedUntil.gt(now))
+        .order_by_desc(login_lockout::Column::LockedUntil)
+        .one(db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+
+    match lockout {
+        Some(lockout) => {
+            let wait = (lockout.locked_until.to_utc() - now)
+                .to_std()
+                .unwrap_or_default();
+            Err(format_err!(TOO_MANY_REQUESTS, "too many failed logins")
+                .with_retry_after(retry_after_secs(wait)))
+        }
+        None => Ok(()),
+    }
+}
+
+/// Counts a failed login from `ip`, and locks it out once `CONFIG.login_lockout_failures` is
+/// exceeded, or every client once `CONFIG.account_lockout_failures` is. Lockouts are notified to
+/// the user.
+pub async fn record_login_failure(
+    ip: IpAddr,
+    db: &(impl ConnectionTrait + TransactionTrait),
+) -> Result<()> {
+    let account_key = ACCOUNT_LOCKOUT_IP.to_string();
+    let locked_ip = if LIMITERS
+        .account_login_failure
+        .check_key(&account_key)
+        .is_err()
+    {
+        account_key
+    } else if LIMITERS.login_failure.check_key(&ip.to_string()).is_err() {
+        ip.to_string()
+    } else {
+        return Ok(());
+    };
+
+    let now = Utc::now();
+    let lockout_activemodel = login_lockout::ActiveModel {
+        id: ActiveValue::Set(Ulid::new().into()),
+        ip: ActiveValue::Set(locked_ip.clone()),
+        created_at: ActiveValue::Set(now.fixed_offset()),
+        locked_until: ActiveValue::Set(
+            (now + chrono::Duration::minutes(CONFIG.login_lockout_minutes.into())).fixed_offset(),
+        ),
+    };
+    let lockout = lockout_activemodel
+        .insert(db)
+        .await
+        .context_internal_server_error("failed to insert to database")?;
+    tracing::warn!(ip = locked_ip, "locked out after repeated failed logins");
+
+    let event = Event::Notification(Notification::new(NotificationType::LoginLockedOut {
+        lockout_id: lockout.id.into(),
+    }));
+    event.send(db).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rounds_retry_after_up() {
+        assert_eq!(retry_after_secs(Duration::ZERO), 1);
+        assert_eq!(retry_after_secs(Duration::from_millis(1500)), 2);
+        assert_eq!(retry_after_secs(Duration::from_secs(30)), 30);
+    }
+
+    #[test]
+    fn limits_each_key() {
+        let minute = Duration::from_secs(60);
+        let limiters = Limiters::new(
+            quota(2, minute),
+            quota(1, minute),
+            quota(1, minute),
+            quota(1, minute),
+            quota(1, minute),
+        );
+        let keys = ["ip:192.0.2.1".to_string(), "key:a".to_string()];
+
+        assert_eq!(limiters.check(&limiters.api, &keys), Ok(()));
+        assert_eq!(limiters.check(&limiters.api, &keys[..1]), Ok(()));
+        let retry_after = limiters.check(&limiters.api, &keys).unwrap_err();
+        assert!((1..=30).contains(&retry_after));
+        assert_eq!(limiters.check(&limiters.api, &keys[1..]), Ok(()));
+    }
+}
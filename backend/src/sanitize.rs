@@ -0,0 +1,294 @@
+use std::collections::{HashMap, HashSet};
+
+use activitypub_federation::config::{Data, FederationConfig};
+use ammonia::{Builder, UrlRelative};
+use once_cell::sync::Lazy;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, IntoActiveModel, QueryFilter,
+    QueryOrder, QuerySelect,
+};
+use ulid::Ulid;
+
+use crate::{
+    entity::{post, setting, user},
+    error::{Context, Error},
+    state::State,
+};
+
+/// Number of rows re-sanitized at once by [`run_backfill`]
+const BACKFILL_BATCH_SIZE: u64 = 100;
+
+static HTML_SANITIZER: Lazy<Builder<'static>> = Lazy::new(|| {
+    let mut builder = Builder::empty();
+    builder
+        .tags(HashSet::from([
+            "a",
+            "b",
+            "blockquote",
+            "br",
+            "code",
+            "del",
+            "em",
+            "i",
+            "li",
+            "ol",
+            "p",
+            "pre",
+            "s",
+            "span",
+            "strong",
+            "u",
+            "ul",
+        ]))
+        .tag_attributes(HashMap::from([
+            ("a", HashSet::from(["href"])),
+            ("ol", HashSet::from(["start", "reversed"])),
+            ("li", HashSet::from(["value"])),
+        ]))
+        .allowed_classes(HashMap::from([
+            ("a", HashSet::from(["hashtag", "mention", "u-url"])),
+            ("span", HashSet::from(["ellipsis", "h-card", "invisible"])),
+        ]))
+        .clean_content_tags(HashSet::from(["script", "style"]))
+        .url_schemes(HashSet::from(["http", "https", "mailto"]))
+        .url_relative(UrlRelative::Deny)
+        .link_rel(Some("nofollow noopener"))
+        .strip_comments(true);
+    builder
+});
+
+static TEXT_SANITIZER: Lazy<Builder<'static>> = Lazy::new(|| {
+    let mut builder = Builder::empty();
+    builder
+        .clean_content_tags(HashSet::from(["script", "style"]))
+        .strip_comments(true);
+    builder
+});
+
+/// Sanitizes rich HTML content such as `post.text` and `user.description` with an allowlist
+/// of tags, attributes and classes. Links are given `rel="nofollow noopener"`.
+pub fn sanitize_html(html: &str) -> String {
+    HTML_SANITIZER.clean(html).to_string()
+}
+
+/// Strips all markup from plain text such as `post.title` and `user.name`.
+pub fn sanitize_text(text: &str) -> String {
+    TEXT_SANITIZER
+        .clean(text)
+        .to_string()
+        .replace("&nbsp;", "\u{a0}")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&")
+}
+
+/// Re-sanitizes `post.text` and `user.description` stored before they were sanitized on ingest.
+/// Runs once, recorded by `setting.html_sanitized`.
+pub async fn run_backfill(federation_config: FederationConfig<State>) -> Result<(), Error> {
+    let data = federation_config.to_request_data();
+    let Some(setting) = setting::Entity::find_by_id(Ulid::nil())
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+    else {
+        return Ok(());
+    };
+    if setting.html_sanitized {
+        return Ok(());
+    }
+
+    tracing::info!("re-sanitizing stored posts and users...");
+    backfill_posts(&data).await?;
+    backfill_users(&data).await?;
+    if data.stopper.is_stopped() {
+        return Ok(());
+    }
+
+    let mut setting_activemodel = setting.into_active_model();
+    setting_activemodel.html_sanitized = ActiveValue::Set(true);
+    setting_activemodel
+        .update(&*data.db)
+        .await
+        .context_internal_server_error("failed to update database")?;
+    tracing::info!("re-sanitized stored posts and users");
+
+    Ok(())
+}
+
+async fn backfill_posts(data: &Data<State>) -> Result<(), Error> {
+    let mut last_id = None;
+    while !data.stopper.is_stopped() {
+        let mut query = post::Entity::find()
+            .order_by_asc(post::Column::Id)
+            .limit(BACKFILL_BATCH_SIZE);
+        if let Some(last_id) = last_id {
+            query = query.filter(post::Column::Id.gt(last_id));
+        }
+        let posts = query
+            .all(&*data.db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        let Some(last) = posts.last() else {
+            break;
+        };
+        last_id = Some(last.id);
+
+        for post in posts {
+            let text = sanitize_html(&post.text);
+            if text == post.text {
+                continue;
+            }
+            let mut post_activemodel = post.into_active_model();
+            post_activemodel.text = ActiveValue::Set(text);
+            post_activemodel
+                .update(&*data.db)
+                .await
+                .context_internal_server_error("failed to update database")?;
+        }
+    }
+    Ok(())
+}
+
+async fn backfill_users(data: &Data<State>) -> Result<(), Error> {
+    let mut last_id = None;
+    while !data.stopper.is_stopped() {
+        let mut query = user::Entity::find()
+            .order_by_asc(user::Column::Id)
+            .limit(BACKFILL_BATCH_SIZE);
+        if let Some(last_id) = last_id {
+            query = query.filter(user::Column::Id.gt(last_id));
+        }
+        let users = query
+            .all(&*data.db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        let Some(last) = users.last() else {
+            break;
+        };
+        last_id = Some(last.id);
+
+        for user in users {
+            let description = user.description.as_deref().map(sanitize_html);
+            if description == user.description {
+                continue;
+            }
+            let mut user_activemodel = user.into_active_model();
+            user_activemodel.description = ActiveValue::Set(description);
+            user_activemodel
+                .update(&*data.db)
+                .await
+                .context_internal_server_error("failed to update database")?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_allowed_markup() {
+        let html = r#"<p>Hello <span class="h-card"><a href="https://example.com/@alice" class="u-url mention">@<span>alice</span></a></span><br><strong>bold</strong></p>"#;
+        assert_eq!(
+            sanitize_html(html),
+            r#"<p>Hello <span class="h-card"><a href="https://example.com/@alice" class="u-url mention" rel="nofollow noopener">@<span>alice</span></a></span><br><strong>bold</strong></p>"#
+        );
+    }
+
+    #[test]
+    fn removes_script_tags() {
+        assert_eq!(
+            sanitize_html("<p>hi<script>alert(1)</script></p>"),
+            "<p>hi</p>"
+        );
+        assert_eq!(
+            sanitize_html("<p>hi<style>body { display: none }</style></p>"),
+            "<p>hi</p>"
+        );
+    }
+
+    #[test]
+    fn removes_event_handlers() {
+        assert_eq!(
+            sanitize_html(r#"<p onclick="alert(1)">hi</p><img src="x" onerror="alert(1)">"#),
+            "<p>hi</p>"
+        );
+        assert_eq!(
+            sanitize_html(r#"<a href="https://example.com/" onmouseover="alert(1)">x</a>"#),
+            r#"<a href="https://example.com/" rel="nofollow noopener">x</a>"#
+        );
+    }
+
+    #[test]
+    fn removes_dangerous_urls() {
+        assert_eq!(
+            sanitize_html(r#"<a href="javascript:alert(1)">x</a>"#),
+            r#"<a rel="nofollow noopener">x</a>"#
+        );
+        assert_eq!(
+            sanitize_html(r#"<a href="JaVaScRiPt:alert(1)">x</a>"#),
+            r#"<a rel="nofollow noopener">x</a>"#
+        );
+        assert_eq!(
+            sanitize_html(r#"<a href="data:text/html;base64,PHNjcmlwdD4=">x</a>"#),
+            r#"<a rel="nofollow noopener">x</a>"#
+        );
+        assert_eq!(
+            sanitize_html(r#"<a href="/relative">x</a>"#),
+            r#"<a rel="nofollow noopener">x</a>"#
+        );
+    }
+
+    #[test]
+    fn normalizes_link_rel() {
+        assert_eq!(
+            sanitize_html(r#"<a href="https://example.com/" rel="opener" target="_blank">x</a>"#),
+            r#"<a href="https://example.com/" rel="nofollow noopener">x</a>"#
+        );
+    }
+
+    #[test]
+    fn removes_disallowed_tags_and_attributes() {
+        assert_eq!(
+            sanitize_html(
+                r#"<iframe src="https://example.com/"></iframe><p style="position: fixed" class="evil">x</p>"#
+            ),
+            "<p>x</p>"
+        );
+        assert_eq!(
+            sanitize_html(r#"<svg><a href="https://example.com/">x</a></svg>"#),
+            ""
+        );
+        assert_eq!(
+            sanitize_html(r#"<form action="https://example.com/"><input name="password"></form>"#),
+            ""
+        );
+    }
+
+    #[test]
+    fn removes_comments_and_broken_markup() {
+        assert_eq!(
+            sanitize_html("<p>a<!-- <script>alert(1)</script> -->b</p>"),
+            "<p>ab</p>"
+        );
+        assert_eq!(
+            sanitize_html(r#"<p>"><script>alert(1)</script></p>"#),
+            "<p>\"&gt;</p>"
+        );
+        assert_eq!(
+            sanitize_html("<scr<script>ipt>alert(1)</script>"),
+            "ipt&gt;alert(1)"
+        );
+    }
+
+    #[test]
+    fn strips_markup_from_text() {
+        assert_eq!(sanitize_text("Alice <b>Bob</b>"), "Alice Bob");
+        assert_eq!(sanitize_text("<script>alert(1)</script>Alice"), "Alice");
+        assert_eq!(sanitize_text(r#"<img src=x onerror="alert(1)">"#), "");
+        assert_eq!(sanitize_text("Tom & Jerry"), "Tom & Jerry");
+        assert_eq!(sanitize_text(":blob: 1 < 2"), ":blob: 1 < 2");
+    }
+}
@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use activitypub_federation::config::{Data, FederationConfig};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, IntoActiveModel, QueryFilter,
+    QueryOrder, TransactionTrait,
+};
+
+use crate::{
+    dto::{CreatePost, Mention},
+    entity::{post, scheduled_post},
+    error::{Context, Error},
+    handler::api::post::{insert_post, prepare_post, send_post},
+    state::State,
+};
+
+const SCHEDULED_POST_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Publishes scheduled posts once they are due.
+pub async fn run_worker(federation_config: FederationConfig<State>) -> Result<(), Error> {
+    let data = federation_config.to_request_data();
+
+    tracing::info!("starting scheduled post worker...");
+    while !data.stopper.is_stopped() {
+        if let Err(error) = publish_due(&data).await {
+            tracing::error!("failed to publish scheduled posts\n{:?}", error.inner);
+        }
+
+        data.stopper
+            .stop_future(tokio::time::sleep(SCHEDULED_POST_POLL_INTERVAL))
+            .await;
+    }
+    tracing::info!("scheduled post worker stopped");
+
+    Ok(())
+}
+
+#[tracing::instrument(skip(data))]
+async fn publish_due(data: &Data<State>) -> Result<(), Error> {
+    let scheduled_posts = scheduled_post::Entity::find()
+        .filter(scheduled_post::Column::FailedAt.is_null())
+        .filter(scheduled_post::Column::ScheduledAt.lte(Utc::now()))
+        .order_by_asc(scheduled_post::Column::ScheduledAt)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+
+    for scheduled_post in scheduled_posts {
+        match publish(&scheduled_post, data).await {
+            Ok(Some((post, mentions))) => {
+                if let Err(error) = send_post(post, mentions, data).await {
+                    tracing::warn!(
+                        id = %scheduled_post.id,
+                        "failed to send scheduled post\n{:?}",
+                        error.inner
+                    );
+                }
+            }
+            Ok(None) => {}
+            Err(error) => {
+                tracing::warn!(
+                    id = %scheduled_post.id,
+                    "failed to publish scheduled post\n{:?}",
+                    error.inner
+                );
+                let mut scheduled_post_activemodel = scheduled_post.into_active_model();
+                scheduled_post_activemodel.failed_at =
+                    ActiveValue::Set(Some(Utc::now().fixed_offset()));
+                scheduled_post_activemodel.last_error =
+                    ActiveValue::Set(Some(error.inner.to_string()));
+                scheduled_post_activemodel
+                    .update(&*data.db)
+                    .await
+                    .context_internal_server_error("failed to update database")?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Inserts the post of `scheduled_post` and removes it in one transaction, returning the post
+/// to send. Returns `None` if it has already been published.
+async fn publish(
+    scheduled_post: &scheduled_post::Model,
+    data: &Data<State>,
+) -> Result<Option<(post::Model, Vec<Mention>)>, Error> {
+    let req = serde_json::from_value::<CreatePost>(scheduled_post.request.clone())
+        .context_internal_server_error("malformed scheduled post")?;
+    let prepared = prepare_post(req, data).await?;
+
+    let tx = data
+        .db
+        .begin()
+        .await
+        .context_internal_server_error("failed to begin database transaction")?;
+    let res = scheduled_post::Entity::delete_by_id(scheduled_post.id)
+        .exec(&tx)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+    if res.rows_affected == 0 {
+        return Ok(None);
+    }
+    let (post, mentions) = insert_post(prepared, &tx).await?;
+    tx.commit()
+        .await
+        .context_internal_server_error("failed to commit database transaction")?;
+
+    Ok(Some((post, mentions)))
+}
@@ -0,0 +1,146 @@
+//! OAuth scopes granted to access keys, named like those of Mastodon.
+
+use std::fmt;
+
+/// Scopes granted to keys issued by password login.
+pub const FULL_SCOPES: [&str; 4] = ["read", "write", "follow", "push"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Resource {
+    Accounts,
+    Blocks,
+    Bookmarks,
+    Favourites,
+    Follows,
+    Media,
+    Mutes,
+    Notifications,
+    Reports,
+    Search,
+    Statuses,
+}
+
+impl Resource {
+    const ALL: [Self; 11] = [
+        Self::Accounts,
+        Self::Blocks,
+        Self::Bookmarks,
+        Self::Favourites,
+        Self::Follows,
+        Self::Media,
+        Self::Mutes,
+        Self::Notifications,
+        Self::Reports,
+        Self::Search,
+        Self::Statuses,
+    ];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Accounts => "accounts",
+            Self::Blocks => "blocks",
+            Self::Bookmarks => "bookmarks",
+            Self::Favourites => "favourites",
+            Self::Follows => "follows",
+            Self::Media => "media",
+            Self::Mutes => "mutes",
+            Self::Notifications => "notifications",
+            Self::Reports => "reports",
+            Self::Search => "search",
+            Self::Statuses => "statuses",
+        }
+    }
+}
+
+/// Scope required by a handler.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scope {
+    Read(Resource),
+    Write(Resource),
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read(resource) => write!(f, "read:{}", resource.as_str()),
+            Self::Write(resource) => write!(f, "write:{}", resource.as_str()),
+        }
+    }
+}
+
+impl Scope {
+    /// Returns whether any of `granted` covers this scope. `read` and `write` cover every
+    /// resource, and `follow` covers blocks, follows and mutes.
+    pub fn is_granted<S: AsRef<str>>(self, granted: &[S]) -> bool {
+        granted
+            .iter()
+            .any(|granted| match (granted.as_ref(), self) {
+                ("read", Self::Read(_)) | ("write", Self::Write(_)) => true,
+                (
+                    "follow",
+                    Self::Read(Resource::Blocks | Resource::Follows | Resource::Mutes)
+                    | Self::Write(Resource::Blocks | Resource::Follows | Resource::Mutes),
+                ) => true,
+                (granted, scope) => granted == scope.to_string(),
+            })
+    }
+}
+
+/// Returns whether `scope` is a scope we know of, so that it can be granted.
+pub fn is_valid(scope: &str) -> bool {
+    if FULL_SCOPES.contains(&scope) {
+        return true;
+    }
+    match scope.split_once(':') {
+        Some(("read" | "write", resource)) => {
+            Resource::ALL.iter().any(|known| known.as_str() == resource)
+        }
+        _ => false,
+    }
+}
+
+/// Parses space separated `scopes`, defaulting to `read` like Mastodon.
+pub fn parse(scopes: Option<&str>) -> Option<Vec<String>> {
+    let mut parsed = Vec::new();
+    for scope in scopes.unwrap_or("read").split_whitespace() {
+        if !is_valid(scope) {
+            return None;
+        }
+        if !parsed.iter().any(|existing| existing == scope) {
+            parsed.push(scope.to_string());
+        }
+    }
+    if parsed.is_empty() {
+        parsed.push("read".to_string());
+    }
+    Some(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grants_by_coarse_scopes() {
+        assert!(Scope::Read(Resource::Statuses).is_granted(&["read"]));
+        assert!(!Scope::Write(Resource::Statuses).is_granted(&["read"]));
+        assert!(Scope::Write(Resource::Follows).is_granted(&["follow"]));
+        assert!(!Scope::Write(Resource::Statuses).is_granted(&["follow"]));
+    }
+
+    #[test]
+    fn grants_by_fine_scopes() {
+        assert!(Scope::Write(Resource::Media).is_granted(&["read", "write:media"]));
+        assert!(!Scope::Write(Resource::Statuses).is_granted(&["write:media"]));
+    }
+
+    #[test]
+    fn parses_scopes() {
+        assert_eq!(parse(None), Some(vec!["read".to_string()]));
+        assert_eq!(
+            parse(Some("read write:media read")),
+            Some(vec!["read".to_string(), "write:media".to_string()])
+        );
+        assert_eq!(parse(Some("read admin:write")), None);
+    }
+}
@@ -0,0 +1,544 @@
+//! Second factors required at login once enabled: TOTP, recovery codes and WebAuthn passkeys.
+//!
+//! Passkeys are verified without attestation, from the public key that browsers expose through
+//! `AuthenticatorAttestationResponse.getPublicKey()`, so no CBOR decoding is needed.
+
+use std::net::IpAddr;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{Duration, Utc};
+use rand::RngCore;
+use ring::signature::{UnparsedPublicKey, ECDSA_P256_SHA256_ASN1, ED25519};
+use sea_orm::{
+    sea_query::{Condition, Expr},
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, PaginatorTrait,
+    QueryFilter, TransactionTrait,
+};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use totp_rs::{Algorithm, Secret, TOTP};
+use url::Url;
+
+use crate::{
+    config::CONFIG,
+    dto::{PasskeyAssertion, Reauthentication, SecondFactor},
+    entity::{passkey, passkey_challenge, setting},
+    error::{Context, Result},
+    format_err, rate_limit,
+};
+
+/// Number of recovery codes generated at once.
+const RECOVERY_CODE_COUNT: usize = 10;
+
+/// How long a passkey challenge can be answered.
+pub const CHALLENGE_LIFETIME: Duration = Duration::minutes(5);
+
+/// COSE algorithm identifier of ECDSA with P-256 and SHA-256.
+pub const ES256: i32 = -7;
+/// COSE algorithm identifier of Ed25519.
+pub const EDDSA: i32 = -8;
+
+/// DER prefix of a P-256 `SubjectPublicKeyInfo`, followed by the uncompressed point.
+const ES256_SPKI_PREFIX: [u8; 26] = [
+    0x30, 0x59, 0x30, 0x13, 0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01, 0x06, 0x08, 0x2a,
+    0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07, 0x03, 0x42, 0x00,
+];
+/// DER prefix of an Ed25519 `SubjectPublicKeyInfo`, followed by the key.
+const EDDSA_SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+/// Generates a base32 encoded TOTP secret.
+pub fn generate_totp_secret() -> String {
+    let mut secret = vec![0; 20];
+    rand::thread_rng().fill_bytes(&mut secret);
+    Secret::Raw(secret).to_encoded().to_string()
+}
+
+fn totp(secret: &str, user_handle: &str) -> Result<TOTP> {
+    let secret = Secret::Encoded(secret.to_string())
+        .to_bytes()
+        .context_internal_server_error("malformed TOTP secret")?;
+    TOTP::new(
+        Algorithm::SHA1,
+        6,
+        1,
+        30,
+        secret,
+        Some(CONFIG.public_domain.replace(':', "")),
+        format!("{}@{}", user_handle, CONFIG.public_domain).replace(':', ""),
+    )
+    .context_internal_server_error("failed to create TOTP")
+}
+
+/// Returns the `otpauth://` URL for authenticator apps to import the secret from.
+pub fn totp_url(secret: &str, user_handle: &str) -> Result<String> {
+    Ok(totp(secret, user_handle)?.get_url())
+}
+
+/// Checks `code` against the current time, allowing one step of clock skew. Steps at or before
+/// `last_step` are rejected so that codes cannot be replayed. Returns the matching step.
+pub fn verify_totp(
+    secret: &str,
+    user_handle: &str,
+    code: &str,
+    last_step: Option<i64>,
+) -> Result<Option<i64>> {
+    verify_totp_at(secret, user_handle, code, last_step, Utc::now().timestamp())
+}
+
+fn verify_totp_at(
+    secret: &str,
+    user_handle: &str,
+    code: &str,
+    last_step: Option<i64>,
+    time: i64,
+) -> Result<Option<i64>> {
+    let totp = totp(secret, user_handle)?;
+    let step_secs = totp.step as i64;
+    let current_step = time / step_secs;
+    let skew = i64::from(totp.skew);
+    for step in current_step - skew..=current_step + skew {
+        if step < 0 || last_step.is_some_and(|last_step| step <= last_step) {
+            continue;
+        }
+        let expected = totp.generate((step * step_secs) as u64);
+        if ring::constant_time::verify_slices_are_equal(expected.as_bytes(), code.trim().as_bytes())
+            .is_ok()
+        {
+            return Ok(Some(step));
+        }
+    }
+    Ok(None)
+}
+
+/// Records `step` as used, failing if it already was by a concurrent request.
+pub async fn accept_totp_step(step: i64, db: &impl ConnectionTrait) -> Result<bool> {
+    let res = setting::Entity::update_many()
+        .col_expr(setting::Column::UserTotpLastStep, Expr::value(step))
+        .filter(setting::Column::Id.eq(uuid::Uuid::nil()))
+        .filter(
+            Condition::any()
+                .add(setting::Column::UserTotpLastStep.is_null())
+                .add(setting::Column::UserTotpLastStep.lt(step)),
+        )
+        .exec(db)
+        .await
+        .context_internal_server_error("failed to update database")?;
+    Ok(res.rows_affected > 0)
+}
+
+/// Removes the recovery code hashed as `hash`, returning whether it was still unused.
+/// Done as a single conditional update so that a code cannot be spent twice concurrently.
+pub async fn consume_recovery_code(hash: &str, db: &impl ConnectionTrait) -> Result<bool> {
+    let res = setting::Entity::update_many()
+        .col_expr(
+            setting::Column::UserRecoveryCodeHashes,
+            Expr::cust_with_values(r#"array_remove("user_recovery_code_hashes", $1)"#, [hash]),
+        )
+        .filter(setting::Column::Id.eq(uuid::Uuid::nil()))
+        .filter(Expr::cust_with_values(
+            r#"$1 = ANY("user_recovery_code_hashes")"#,
+            [hash],
+        ))
+        .exec(db)
+        .await
+        .context_internal_server_error("failed to update database")?;
+    Ok(res.rows_affected > 0)
+}
+
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Hashes a recovery code. Codes are random enough that a fast hash suffices.
+pub fn hash_recovery_code(code: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(normalize_recovery_code(code).as_bytes()))
+}
+
+/// Generates recovery codes like `1a2b3-c4d5e`, returning them with their hashes.
+pub fn generate_recovery_codes() -> (Vec<String>, Vec<String>) {
+    let codes = (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let mut bytes = [0; 5];
+            rand::thread_rng().fill_bytes(&mut bytes);
+            let code = bytes
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect::<String>();
+            format!("{}-{}", &code[..5], &code[5..])
+        })
+        .collect::<Vec<_>>();
+    let hashes = codes.iter().map(|code| hash_recovery_code(code)).collect();
+    (codes, hashes)
+}
+
+/// Generates a WebAuthn challenge, encoded in base64url as in `clientDataJSON`.
+pub fn generate_challenge() -> String {
+    let mut challenge = [0; 32];
+    rand::thread_rng().fill_bytes(&mut challenge);
+    URL_SAFE_NO_PAD.encode(challenge)
+}
+
+pub fn decode_base64url(value: &str) -> Result<Vec<u8>> {
+    URL_SAFE_NO_PAD
+        .decode(value.trim_end_matches('='))
+        .context_bad_request("malformed base64url")
+}
+
+#[derive(Debug, Deserialize)]
+struct ClientData {
+    #[serde(rename = "type")]
+    ty: String,
+    challenge: String,
+    origin: String,
+}
+
+/// Checks `clientDataJSON` of the ceremony `ty` (`webauthn.create` or `webauthn.get`),
+/// returning the challenge it signs.
+pub fn verify_client_data(client_data_json: &[u8], ty: &str) -> Result<String> {
+    let client_data: ClientData =
+        serde_json::from_slice(client_data_json).context_bad_request("malformed client data")?;
+    if client_data.ty != ty {
+        return Err(format_err!(BAD_REQUEST, "unexpected client data type"));
+    }
+    let origin = Url::parse(&client_data.origin).context_bad_request("malformed origin")?;
+    if origin.host_str() != Some(&CONFIG.public_domain)
+        || (origin.scheme() != "https" && !CONFIG.debug)
+    {
+        return Err(format_err!(BAD_REQUEST, "unexpected origin"));
+    }
+    Ok(client_data.challenge)
+}
+
+#[derive(Debug)]
+pub struct AuthenticatorData {
+    pub sign_count: u32,
+    /// Only present at registration.
+    pub credential_id: Option<Vec<u8>>,
+}
+
+/// Parses `authenticatorData` of `rp_id`, requiring the user to have been present.
+pub fn parse_authenticator_data(data: &[u8], rp_id: &str) -> Result<AuthenticatorData> {
+    const USER_PRESENT: u8 = 0x01;
+    const ATTESTED_CREDENTIAL_DATA: u8 = 0x40;
+
+    if data.len() < 37 {
+        return Err(format_err!(BAD_REQUEST, "malformed authenticator data"));
+    }
+    if data[..32] != Sha256::digest(rp_id.as_bytes())[..] {
+        return Err(format_err!(BAD_REQUEST, "unexpected relying party"));
+    }
+    let flags = data[32];
+    if flags & USER_PRESENT == 0 {
+        return Err(format_err!(BAD_REQUEST, "user not present"));
+    }
+    let sign_count = u32::from_be_bytes([data[33], data[34], data[35], data[36]]);
+
+    let credential_id = if flags & ATTESTED_CREDENTIAL_DATA != 0 {
+        // AAGUID, then the big-endian length of the credential ID
+        let length = data
+            .get(53..55)
+            .context_bad_request("malformed authenticator data")?;
+        let length = u16::from_be_bytes([length[0], length[1]]) as usize;
+        let credential_id = data
+            .get(55..55 + length)
+            .context_bad_request("malformed authenticator data")?;
+        Some(credential_id.to_vec())
+    } else {
+        None
+    };
+
+    Ok(AuthenticatorData {
+        sign_count,
+        credential_id,
+    })
+}
+
+/// Extracts the raw public key from a DER `SubjectPublicKeyInfo` of `algorithm`.
+pub fn raw_public_key(algorithm: i32, spki: &[u8]) -> Result<&[u8]> {
+    let prefix: &[u8] = match algorithm {
+        ES256 => &ES256_SPKI_PREFIX,
+        EDDSA => &EDDSA_SPKI_PREFIX,
+        _ => return Err(format_err!(BAD_REQUEST, "unsupported passkey algorithm")),
+    };
+    spki.strip_prefix(prefix)
+        .context_bad_request("malformed public key")
+}
+
+/// Verifies an assertion signature over `authenticator_data` and the hash of
+/// `client_data_json`.
+pub fn verify_assertion(
+    algorithm: i32,
+    spki: &[u8],
+    authenticator_data: &[u8],
+    client_data_json: &[u8],
+    signature: &[u8],
+) -> Result<()> {
+    let public_key = raw_public_key(algorithm, spki)?;
+    let mut message = authenticator_data.to_vec();
+    message.extend_from_slice(&Sha256::digest(client_data_json));
+    let verification = match algorithm {
+        ES256 => {
+            UnparsedPublicKey::new(&ECDSA_P256_SHA256_ASN1, public_key).verify(&message, signature)
+        }
+        _ => UnparsedPublicKey::new(&ED25519, public_key).verify(&message, signature),
+    };
+    verification.map_err(|_| format_err!(BAD_REQUEST, "invalid passkey signature"))
+}
+
+/// Returns whether any second factor is enabled, so that login requires one.
+pub async fn is_enabled(setting: &setting::Model, db: &impl ConnectionTrait) -> Result<bool> {
+    if setting.user_totp_enabled {
+        return Ok(true);
+    }
+    let passkey_count = passkey::Entity::find()
+        .count(db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    Ok(passkey_count > 0)
+}
+
+/// Stores a new passkey challenge, pruning expired ones.
+pub async fn create_challenge(db: &impl ConnectionTrait) -> Result<String> {
+    passkey_challenge::Entity::delete_many()
+        .filter(passkey_challenge::Column::ExpiresAt.lt(Utc::now()))
+        .exec(db)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+
+    let challenge_activemodel = passkey_challenge::ActiveModel {
+        challenge: ActiveValue::Set(generate_challenge()),
+        expires_at: ActiveValue::Set((Utc::now() + CHALLENGE_LIFETIME).fixed_offset()),
+    };
+    let challenge = challenge_activemodel
+        .insert(db)
+        .await
+        .context_internal_server_error("failed to insert to database")?;
+    Ok(challenge.challenge)
+}
+
+/// Consumes `challenge` so that it cannot be answered twice.
+pub async fn consume_challenge(challenge: &str, db: &impl ConnectionTrait) -> Result<()> {
+    let result = passkey_challenge::Entity::delete_many()
+        .filter(passkey_challenge::Column::Challenge.eq(challenge))
+        .filter(passkey_challenge::Column::ExpiresAt.gt(Utc::now()))
+        .exec(db)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+    if result.rows_affected == 0 {
+        return Err(format_err!(BAD_REQUEST, "unknown or expired challenge"));
+    }
+    Ok(())
+}
+
+async fn verify_passkey(assertion: &PasskeyAssertion, db: &impl ConnectionTrait) -> Result<bool> {
+    let Some(passkey) = passkey::Entity::find()
+        .filter(passkey::Column::CredentialId.eq(&assertion.credential_id))
+        .one(db)
+        .await
+        .context_internal_server_error("failed to query database")?
+    else {
+        return Ok(false);
+    };
+
+    let client_data_json = decode_base64url(&assertion.client_data_json)?;
+    let authenticator_data = decode_base64url(&assertion.authenticator_data)?;
+    let challenge = verify_client_data(&client_data_json, "webauthn.get")?;
+    consume_challenge(&challenge, db).await?;
+    let parsed = parse_authenticator_data(&authenticator_data, &CONFIG.public_domain)?;
+    if verify_assertion(
+        passkey.algorithm,
+        &decode_base64url(&passkey.public_key)?,
+        &authenticator_data,
+        &client_data_json,
+        &decode_base64url(&assertion.signature)?,
+    )
+    .is_err()
+    {
+        return Ok(false);
+    }
+    // Authenticators without counters always report zero
+    let sign_count = i64::from(parsed.sign_count);
+    if sign_count != 0 && sign_count <= passkey.sign_count {
+        return Err(format_err!(BAD_REQUEST, "passkey may be cloned"));
+    }
+
+    let mut passkey_activemodel: passkey::ActiveModel = passkey.into();
+    passkey_activemodel.sign_count = ActiveValue::Set(sign_count);
+    passkey_activemodel.last_used_at = ActiveValue::Set(Some(Utc::now().fixed_offset()));
+    passkey_activemodel
+        .update(db)
+        .await
+        .context_internal_server_error("failed to update database")?;
+    Ok(true)
+}
+
+/// Verifies `factor`, consuming the recovery code if one was used.
+pub async fn verify(
+    factor: &SecondFactor,
+    setting: &setting::Model,
+    db: &impl ConnectionTrait,
+) -> Result<bool> {
+    if let (Some(code), Some(secret), true) = (
+        &factor.totp,
+        &setting.user_totp_secret,
+        setting.user_totp_enabled,
+    ) {
+        if let Some(step) = verify_totp(
+            secret,
+            &setting.user_handle,
+            code,
+            setting.user_totp_last_step,
+        )? {
+            if accept_totp_step(step, db).await? {
+                return Ok(true);
+            }
+        }
+    }
+
+    if let Some(code) = &factor.recovery_code {
+        if consume_recovery_code(&hash_recovery_code(code), db).await? {
+            return Ok(true);
+        }
+    }
+
+    if let Some(assertion) = &factor.passkey {
+        if verify_passkey(assertion, db).await? {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Checks the password, and a second factor if enabled, before changes weakening the account.
+/// Failures count towards the lockout of `ip` like failed logins.
+pub async fn reauthenticate(
+    req: &Reauthentication,
+    setting: &setting::Model,
+    ip: IpAddr,
+    db: &(impl ConnectionTrait + TransactionTrait),
+) -> Result<()> {
+    rate_limit::check_lockout(ip, db).await?;
+
+    if !bcrypt::verify(&req.password, &setting.user_password_hash)
+        .context_bad_request("failed to authenticate")?
+    {
+        rate_limit::record_login_failure(ip, db).await?;
+        return Err(format_err!(BAD_REQUEST, "failed to authenticate"));
+    }
+    if is_enabled(setting, db).await? {
+        if req.second_factor.is_empty() {
+            return Err(format_err!(UNAUTHORIZED, "second factor required"));
+        }
+        if !verify(&req.second_factor, setting, db).await? {
+            rate_limit::record_login_failure(ip, db).await?;
+            return Err(format_err!(BAD_REQUEST, "failed to authenticate"));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ring::{
+        rand::SystemRandom,
+        signature::{Ed25519KeyPair, KeyPair},
+    };
+
+    use super::*;
+
+    fn authenticator_data(rp_id: &str, flags: u8, sign_count: u32) -> Vec<u8> {
+        let mut data = Sha256::digest(rp_id.as_bytes()).to_vec();
+        data.push(flags);
+        data.extend_from_slice(&sign_count.to_be_bytes());
+        data
+    }
+
+    #[test]
+    fn rejects_replayed_totp_codes() {
+        let secret = generate_totp_secret();
+        let time = 1_700_000_000;
+        let code = totp(&secret, "alice").unwrap().generate(time as u64);
+        let step = time / 30;
+
+        assert_eq!(
+            verify_totp_at(&secret, "alice", &code, None, time).unwrap(),
+            Some(step)
+        );
+        assert_eq!(
+            verify_totp_at(&secret, "alice", &code, Some(step - 1), time + 30).unwrap(),
+            Some(step)
+        );
+        assert_eq!(
+            verify_totp_at(&secret, "alice", &code, Some(step), time).unwrap(),
+            None
+        );
+        assert_eq!(
+            verify_totp_at(&secret, "alice", "000000x", None, time).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn hashes_recovery_codes_normalized() {
+        assert_eq!(
+            hash_recovery_code("1A2B3-C4D5E"),
+            hash_recovery_code(" 1a2b3c4d5e")
+        );
+        let (codes, hashes) = generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        assert_eq!(hash_recovery_code(&codes[0]), hashes[0]);
+    }
+
+    #[test]
+    fn parses_authenticator_data() {
+        let mut data = authenticator_data("example.com", 0x41, 7);
+        data.extend_from_slice(&[0; 16]);
+        data.extend_from_slice(&[0, 3, 1, 2, 3]);
+        let parsed = parse_authenticator_data(&data, "example.com")
+            .map_err(|error| error.inner)
+            .unwrap();
+        assert_eq!(parsed.sign_count, 7);
+        assert_eq!(parsed.credential_id, Some(vec![1, 2, 3]));
+
+        assert!(parse_authenticator_data(&data, "example.org").is_err());
+        let data = authenticator_data("example.com", 0x00, 0);
+        assert!(parse_authenticator_data(&data, "example.com").is_err());
+    }
+
+    #[test]
+    fn verifies_ed25519_assertions() {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let mut spki = EDDSA_SPKI_PREFIX.to_vec();
+        spki.extend_from_slice(key_pair.public_key().as_ref());
+
+        let authenticator_data = authenticator_data("example.com", 0x01, 1);
+        let client_data_json = br#"{"type":"webauthn.get"}"#;
+        let mut message = authenticator_data.clone();
+        message.extend_from_slice(&Sha256::digest(client_data_json));
+        let signature = key_pair.sign(&message);
+
+        assert!(verify_assertion(
+            EDDSA,
+            &spki,
+            &authenticator_data,
+            client_data_json,
+            signature.as_ref()
+        )
+        .is_ok());
+        assert!(verify_assertion(
+            EDDSA,
+            &spki,
+            &authenticator_data,
+            br#"{"type":"webauthn.create"}"#,
+            signature.as_ref()
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,76 @@
+//! Rough `User-Agent` parsing, to tell sessions apart.
+
+const BROWSERS: [(&str, &str); 6] = [
+    ("Edg/", "Edge"),
+    ("OPR/", "Opera"),
+    ("Firefox/", "Firefox"),
+    ("Chrome/", "Chrome"),
+    ("CriOS/", "Chrome"),
+    ("Safari/", "Safari"),
+];
+
+const OPERATING_SYSTEMS: [(&str, &str); 7] = [
+    ("Windows", "Windows"),
+    ("Android", "Android"),
+    ("iPhone", "iOS"),
+    ("iPad", "iPadOS"),
+    ("Mac OS X", "macOS"),
+    ("CrOS", "ChromeOS"),
+    ("Linux", "Linux"),
+];
+
+/// Describes the device of `user_agent` like `Firefox on Linux`. Clients other than browsers are
+/// named after their first product token, like `curl`.
+pub fn describe(user_agent: &str) -> String {
+    let browser = BROWSERS
+        .iter()
+        .find(|(token, _)| user_agent.contains(token))
+        .map(|(_, name)| name.to_string())
+        .or_else(|| {
+            user_agent
+                .split_whitespace()
+                .next()
+                .map(|product| product.split('/').next().unwrap_or(product).to_string())
+        })
+        .unwrap_or_else(|| "Unknown client".to_string());
+    match OPERATING_SYSTEMS
+        .iter()
+        .find(|(token, _)| user_agent.contains(token))
+    {
+        Some((_, os)) => format!("{} on {}", browser, os),
+        None => browser,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_browsers() {
+        assert_eq!(
+            describe("Mozilla/5.0 (X11; Linux x86_64; rv:131.0) Gecko/20100101 Firefox/131.0"),
+            "Firefox on Linux"
+        );
+        assert_eq!(
+            describe(
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) \
+                 Chrome/129.0.0.0 Safari/537.36 Edg/129.0.0.0"
+            ),
+            "Edge on Windows"
+        );
+        assert_eq!(
+            describe(
+                "Mozilla/5.0 (iPhone; CPU iPhone OS 18_0 like Mac OS X) AppleWebKit/605.1.15 \
+                 (KHTML, like Gecko) Version/18.0 Mobile/15E148 Safari/604.1"
+            ),
+            "Safari on iOS"
+        );
+    }
+
+    #[test]
+    fn describes_other_clients() {
+        assert_eq!(describe("curl/8.10.1"), "curl");
+        assert_eq!(describe(""), "Unknown client");
+    }
+}
@@ -1,10 +1,14 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rand::RngCore;
 use sea_orm::{
     sea_query::{Expr, Func},
     ConnectionTrait, EntityTrait, QuerySelect,
 };
+use sha2::{Digest, Sha256};
 use url::Url;
 
 use crate::{
+    config::CONFIG,
     entity::{follower, user},
     error::{Context, Result},
 };
@@ -28,3 +32,26 @@ pub async fn get_follower_inboxes(db: &impl ConnectionTrait) -> Result<Vec<Url>>
         .collect::<Vec<_>>();
     Ok(inboxes)
 }
+
+/// Generates a random URL-safe string, for secrets which must not be guessed.
+pub fn random_token() -> String {
+    let mut bytes = [0; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Hashes a [`random_token`] for storage, so that it can be looked up but not recovered.
+pub fn hash_token(token: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Builds the URL of the page listing posts with the hashtag `name`.
+pub fn hashtag_url(name: &str) -> Result<Url> {
+    let mut url = Url::parse(&format!("https://{}", CONFIG.public_domain))
+        .context_internal_server_error("failed to construct hashtag URL")?;
+    url.path_segments_mut()
+        .ok()
+        .context_internal_server_error("failed to construct hashtag URL")?
+        .extend(["tags", name]);
+    Ok(url)
+}
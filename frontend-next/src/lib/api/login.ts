@@ -7,7 +7,7 @@ import { apiUrl } from "@/lib/api";
 import { Id, throwError } from "@/lib/dto";
 
 const PostLoginResp = z.object({
-  token: Id,
+  token: z.string(),
 });
 
 export default async function login(password: string) {
@@ -3,6 +3,7 @@ import { BrowserRouter, Route, Routes } from "react-router-dom";
 
 import Layout from "./components/Layout";
 import { AccessKeyContextProvider } from "./contexts/auth";
+import HashtagPage from "./pages/Hashtag";
 import IndexPage from "./pages/Index";
 import NotFoundPage from "./pages/NotFound";
 import NotePage from "./pages/Note";
@@ -21,6 +22,7 @@ export default function App() {
                 <Route index element={<IndexPage />} />
                 <Route path="note/:id" element={<NotePage />} />
                 <Route path="person/" element={<PersonPage />} />
+                <Route path="tags/:name" element={<HashtagPage />} />
               </Route>
             </Route>
           </Routes>
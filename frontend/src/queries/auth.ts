@@ -4,7 +4,6 @@ import z from "zod";
 
 import { JsonMutationRet, useJsonMutation } from ".";
 import { AccessKeyContext } from "../contexts/auth";
-import { Id } from "../dto";
 
 export function useIsAuthed(): boolean | undefined {
   const [accessKey] = useContext(AccessKeyContext);
@@ -35,7 +34,7 @@ export interface LoginReq {
 }
 
 const LoginResp = z.object({
-  token: Id,
+  token: z.string(),
 });
 
 export function useLoginMutation(
@@ -20,6 +20,28 @@ mod m20230814_150734_repost;
 mod m20230815_033104_notification;
 mod m20230824_155814_post_source;
 mod m20240728_175258_settings_object_store;
+mod m20261017_090000_hide_follow_collections;
+mod m20261017_100000_follow_request;
+mod m20261017_110000_post_edit;
+mod m20261017_120000_delivery;
+mod m20261017_130000_block;
+mod m20261017_140000_mute;
+mod m20261017_150000_search;
+mod m20261017_160000_poll;
+mod m20261017_170000_pin;
+mod m20261017_180000_bookmark;
+mod m20261017_190000_scheduled_post;
+mod m20261017_200000_draft;
+mod m20261017_210000_account_migration;
+mod m20261017_220000_import;
+mod m20261017_230000_oauth;
+mod m20261017_231000_session;
+mod m20261017_232000_two_factor;
+mod m20261017_233000_login_lockout;
+mod m20261017_234000_notification_subject;
+mod m20261017_235000_html_sanitized;
+mod m20261017_236000_access_key_secret;
+mod m20261017_237000_totp_last_step;
 
 pub struct Migrator;
 
@@ -47,6 +69,28 @@ impl MigratorTrait for Migrator {
             Box::new(m20230815_033104_notification::Migration),
             Box::new(m20230824_155814_post_source::Migration),
             Box::new(m20240728_175258_settings_object_store::Migration),
+            Box::new(m20261017_090000_hide_follow_collections::Migration),
+            Box::new(m20261017_100000_follow_request::Migration),
+            Box::new(m20261017_110000_post_edit::Migration),
+            Box::new(m20261017_120000_delivery::Migration),
+            Box::new(m20261017_130000_block::Migration),
+            Box::new(m20261017_140000_mute::Migration),
+            Box::new(m20261017_150000_search::Migration),
+            Box::new(m20261017_160000_poll::Migration),
+            Box::new(m20261017_170000_pin::Migration),
+            Box::new(m20261017_180000_bookmark::Migration),
+            Box::new(m20261017_190000_scheduled_post::Migration),
+            Box::new(m20261017_200000_draft::Migration),
+            Box::new(m20261017_210000_account_migration::Migration),
+            Box::new(m20261017_220000_import::Migration),
+            Box::new(m20261017_230000_oauth::Migration),
+            Box::new(m20261017_231000_session::Migration),
+            Box::new(m20261017_232000_two_factor::Migration),
+            Box::new(m20261017_233000_login_lockout::Migration),
+            Box::new(m20261017_234000_notification_subject::Migration),
+            Box::new(m20261017_235000_html_sanitized::Migration),
+            Box::new(m20261017_236000_access_key_secret::Migration),
+            Box::new(m20261017_237000_totp_last_step::Migration),
         ]
     }
 }
@@ -222,6 +222,8 @@ pub enum User {
     ManuallyApprovesFollowers,
     IsBot,
     Description,
+    AlsoKnownAs,
+    MovedTo,
 }
 
 #[derive(Iden)]
@@ -239,6 +241,7 @@ pub enum Post {
     RepostId,
     SourceContent,
     SourceMediaType,
+    UpdatedAt,
 }
 
 #[derive(Iden)]
@@ -251,11 +254,19 @@ enum Visibility {
 }
 
 #[derive(Iden)]
-enum AccessKey {
+pub enum AccessKey {
     Table,
     Id,
     Name,
     LastUsedAt,
+    ApplicationId,
+    Scopes,
+    CreatedAt,
+    ExpiresAt,
+    UserAgent,
+    Ip,
+    Personal,
+    SecretHash,
 }
 
 #[derive(Iden)]
@@ -83,4 +83,13 @@ pub enum Setting {
     ObjectStoreS3Bucket,
     ObjectStoreS3PublicUrlBase,
     ObjectStoreLocalFileSystemBasePath,
+    HideFollowCollections,
+    UserManuallyApprovesFollowers,
+    UserAlsoKnownAs,
+    UserMovedTo,
+    UserTotpSecret,
+    UserTotpEnabled,
+    UserRecoveryCodeHashes,
+    HtmlSanitized,
+    UserTotpLastStep,
 }
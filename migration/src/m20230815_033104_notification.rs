@@ -39,8 +39,10 @@ impl MigrationTrait for Migration {
 }
 
 #[derive(Iden)]
-enum Notification {
+pub enum Notification {
     Table,
     Id,
     Payload,
+    UserIds,
+    PostId,
 }
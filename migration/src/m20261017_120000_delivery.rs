@@ -0,0 +1,97 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Delivery::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Delivery::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(Delivery::ActivityId).string().not_null())
+                    .col(ColumnDef::new(Delivery::Activity).json_binary().not_null())
+                    .col(ColumnDef::new(Delivery::Inbox).string().not_null())
+                    .col(
+                        ColumnDef::new(Delivery::Attempts)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(Delivery::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Delivery::NextAttemptAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Delivery::FailedAt).timestamp_with_time_zone())
+                    .col(ColumnDef::new(Delivery::LastError).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(DeliveryInbox::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(DeliveryInbox::Inbox)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(DeliveryInbox::FirstFailedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(DeliveryInbox::SuspendedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(DeliveryInbox::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Delivery::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Delivery {
+    Table,
+    Id,
+    ActivityId,
+    Activity,
+    Inbox,
+    Attempts,
+    CreatedAt,
+    NextAttemptAt,
+    FailedAt,
+    LastError,
+}
+
+#[derive(Iden)]
+enum DeliveryInbox {
+    Table,
+    Inbox,
+    FirstFailedAt,
+    SuspendedAt,
+}
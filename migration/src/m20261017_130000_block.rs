@@ -0,0 +1,80 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20230806_104639_initial::User;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Block::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Block::ToId).uuid().not_null().primary_key())
+                    .col(
+                        ColumnDef::new(Block::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Block::Table, Block::ToId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(DomainBlock::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(DomainBlock::Domain)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(DomainBlock::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(DomainBlock::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Block::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Block {
+    Table,
+    ToId,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum DomainBlock {
+    Table,
+    Domain,
+    CreatedAt,
+}
@@ -0,0 +1,63 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20230806_104639_initial::{Post, User};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Mute::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Mute::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(Mute::UserId).uuid().unique_key())
+                    .col(ColumnDef::new(Mute::Domain).string().unique_key())
+                    .col(ColumnDef::new(Mute::ThreadId).uuid().unique_key())
+                    .col(
+                        ColumnDef::new(Mute::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Mute::ExpiresAt).timestamp_with_time_zone())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Mute::Table, Mute::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Mute::Table, Mute::ThreadId)
+                            .to(Post::Table, Post::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Mute::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Mute {
+    Table,
+    Id,
+    UserId,
+    Domain,
+    ThreadId,
+    CreatedAt,
+    ExpiresAt,
+}
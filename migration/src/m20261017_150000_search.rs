@@ -0,0 +1,109 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20230806_104639_initial::{Post, User};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // `text` is HTML, so index the source of local posts and the text without tags otherwise
+        add_search_vector(
+            manager,
+            Post::Table.into_iden(),
+            r#"to_tsvector('simple', coalesce("title", '') || ' ' || coalesce("source_content", regexp_replace("text", '<[^>]*>', ' ', 'g')))"#,
+        )
+        .await?;
+        add_search_vector(
+            manager,
+            User::Table.into_iden(),
+            r#"to_tsvector('simple', "handle" || ' ' || coalesce("name", ''))"#,
+        )
+        .await?;
+        add_search_vector(
+            manager,
+            Hashtag::Table.into_iden(),
+            r#"to_tsvector('simple', "name")"#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        drop_search_vector(manager, Hashtag::Table.into_iden()).await?;
+        drop_search_vector(manager, User::Table.into_iden()).await?;
+        drop_search_vector(manager, Post::Table.into_iden()).await?;
+
+        Ok(())
+    }
+}
+
+/// Adds a generated `search_vector` column with a GIN index to `table`.
+async fn add_search_vector(
+    manager: &SchemaManager<'_>,
+    table: DynIden,
+    expression: &str,
+) -> Result<(), DbErr> {
+    manager
+        .alter_table(
+            Table::alter()
+                .table(table.clone())
+                .add_column(
+                    ColumnDef::new(SearchVector)
+                        .custom(Alias::new("tsvector"))
+                        .not_null()
+                        .extra(format!("GENERATED ALWAYS AS ({expression}) STORED")),
+                )
+                .to_owned(),
+        )
+        .await?;
+
+    manager
+        .create_index(
+            Index::create()
+                .name(index_name(&table))
+                .table(table)
+                .col(SearchVector)
+                .full_text()
+                .to_owned(),
+        )
+        .await?;
+
+    Ok(())
+}
+
+async fn drop_search_vector(manager: &SchemaManager<'_>, table: DynIden) -> Result<(), DbErr> {
+    manager
+        .drop_index(
+            Index::drop()
+                .name(index_name(&table))
+                .table(table.clone())
+                .to_owned(),
+        )
+        .await?;
+
+    manager
+        .alter_table(
+            Table::alter()
+                .table(table)
+                .drop_column(SearchVector)
+                .to_owned(),
+        )
+        .await?;
+
+    Ok(())
+}
+
+fn index_name(table: &DynIden) -> String {
+    format!("idx_{}_search_vector", table.to_string())
+}
+
+#[derive(Iden)]
+enum Hashtag {
+    Table,
+}
+
+#[derive(Iden)]
+struct SearchVector;
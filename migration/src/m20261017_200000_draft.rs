@@ -0,0 +1,79 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20230811_163629_local_file::LocalFile;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Draft::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Draft::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(Draft::Request).json_binary().not_null())
+                    .col(
+                        ColumnDef::new(Draft::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Draft::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(LocalFile::Table)
+                    .add_column(ColumnDef::new(DraftId).uuid())
+                    .add_foreign_key(
+                        ForeignKey::create()
+                            .from(LocalFile::Table, DraftId)
+                            .to(Draft::Table, Draft::Id)
+                            .on_delete(ForeignKeyAction::SetNull)
+                            .get_foreign_key(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(LocalFile::Table)
+                    .drop_column(DraftId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Draft::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Draft {
+    Table,
+    Id,
+    Request,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Iden)]
+struct DraftId;
@@ -0,0 +1,67 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{m20230806_104639_initial::User, m20230812_135017_setting::Setting};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Setting::Table)
+                    .add_column(
+                        ColumnDef::new(Setting::UserAlsoKnownAs)
+                            .array(ColumnType::String(None))
+                            .not_null()
+                            .default(Expr::cust("'{}'")),
+                    )
+                    .add_column(ColumnDef::new(Setting::UserMovedTo).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .add_column(
+                        ColumnDef::new(User::AlsoKnownAs)
+                            .array(ColumnType::String(None))
+                            .not_null()
+                            .default(Expr::cust("'{}'")),
+                    )
+                    .add_column(ColumnDef::new(User::MovedTo).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .drop_column(User::MovedTo)
+                    .drop_column(User::AlsoKnownAs)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Setting::Table)
+                    .drop_column(Setting::UserMovedTo)
+                    .drop_column(Setting::UserAlsoKnownAs)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
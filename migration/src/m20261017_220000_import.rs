@@ -0,0 +1,104 @@
+use sea_orm_migration::{prelude::*, sea_query::extension::postgres::Type};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(ImportType::Table)
+                    .values([
+                        ImportType::Following,
+                        ImportType::Blocks,
+                        ImportType::Mutes,
+                        ImportType::Bookmarks,
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Import::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Import::Id).uuid().not_null().primary_key())
+                    .col(
+                        ColumnDef::new(Import::Type)
+                            .enumeration(
+                                ImportType::Table,
+                                [
+                                    ImportType::Following,
+                                    ImportType::Blocks,
+                                    ImportType::Mutes,
+                                    ImportType::Bookmarks,
+                                ],
+                            )
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Import::Entries)
+                            .array(ColumnType::String(None))
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Import::ProcessedCount)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(Import::FailedCount)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(Import::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Import::FinishedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Import::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_type(Type::drop().name(ImportType::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Import {
+    Table,
+    Id,
+    Type,
+    Entries,
+    ProcessedCount,
+    FailedCount,
+    CreatedAt,
+    FinishedAt,
+}
+
+#[derive(Iden)]
+enum ImportType {
+    Table,
+    Following,
+    Blocks,
+    Mutes,
+    Bookmarks,
+}
@@ -0,0 +1,167 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20230806_104639_initial::AccessKey;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(OauthApplication::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(OauthApplication::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(OauthApplication::Name).string().not_null())
+                    .col(ColumnDef::new(OauthApplication::Website).string())
+                    .col(
+                        ColumnDef::new(OauthApplication::RedirectUris)
+                            .array(ColumnType::String(None))
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(OauthApplication::Scopes)
+                            .array(ColumnType::String(None))
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(OauthApplication::ClientId)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(OauthApplication::ClientSecret)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(OauthApplication::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(OauthAuthorization::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(OauthAuthorization::Code)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(OauthAuthorization::ApplicationId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(OauthAuthorization::RedirectUri)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(OauthAuthorization::Scopes)
+                            .array(ColumnType::String(None))
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(OauthAuthorization::CodeChallenge).string())
+                    .col(
+                        ColumnDef::new(OauthAuthorization::ExpiresAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(OauthAuthorization::Table, OauthAuthorization::ApplicationId)
+                            .to(OauthApplication::Table, OauthApplication::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AccessKey::Table)
+                    .add_column(ColumnDef::new(AccessKey::ApplicationId).uuid())
+                    // Keys issued before scopes existed came from password login
+                    .add_column(
+                        ColumnDef::new(AccessKey::Scopes)
+                            .array(ColumnType::String(None))
+                            .not_null()
+                            .default(Expr::cust("'{read,write,follow,push}'")),
+                    )
+                    .add_foreign_key(
+                        ForeignKey::create()
+                            .from(AccessKey::Table, AccessKey::ApplicationId)
+                            .to(OauthApplication::Table, OauthApplication::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .get_foreign_key(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AccessKey::Table)
+                    .drop_column(AccessKey::Scopes)
+                    .drop_column(AccessKey::ApplicationId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(OauthAuthorization::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(OauthApplication::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum OauthApplication {
+    Table,
+    Id,
+    Name,
+    Website,
+    RedirectUris,
+    Scopes,
+    ClientId,
+    ClientSecret,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum OauthAuthorization {
+    Table,
+    Code,
+    ApplicationId,
+    RedirectUri,
+    Scopes,
+    CodeChallenge,
+    ExpiresAt,
+}
@@ -0,0 +1,53 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20230806_104639_initial::AccessKey;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AccessKey::Table)
+                    .add_column(
+                        ColumnDef::new(AccessKey::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .add_column(ColumnDef::new(AccessKey::ExpiresAt).timestamp_with_time_zone())
+                    .add_column(ColumnDef::new(AccessKey::UserAgent).string())
+                    .add_column(ColumnDef::new(AccessKey::Ip).string())
+                    .add_column(
+                        ColumnDef::new(AccessKey::Personal)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AccessKey::Table)
+                    .drop_column(AccessKey::Personal)
+                    .drop_column(AccessKey::Ip)
+                    .drop_column(AccessKey::UserAgent)
+                    .drop_column(AccessKey::ExpiresAt)
+                    .drop_column(AccessKey::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20230812_135017_setting::Setting;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Setting::Table)
+                    .add_column(ColumnDef::new(Setting::UserTotpSecret).string())
+                    .add_column(
+                        ColumnDef::new(Setting::UserTotpEnabled)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .add_column(
+                        ColumnDef::new(Setting::UserRecoveryCodeHashes)
+                            .array(ColumnType::String(None))
+                            .not_null()
+                            .default(Expr::cust("'{}'")),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Passkey::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Passkey::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(Passkey::Name).string().not_null())
+                    .col(
+                        ColumnDef::new(Passkey::CredentialId)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Passkey::PublicKey).string().not_null())
+                    .col(ColumnDef::new(Passkey::Algorithm).integer().not_null())
+                    .col(ColumnDef::new(Passkey::SignCount).big_integer().not_null())
+                    .col(
+                        ColumnDef::new(Passkey::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Passkey::LastUsedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(PasskeyChallenge::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PasskeyChallenge::Challenge)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(PasskeyChallenge::ExpiresAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PasskeyChallenge::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Passkey::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Setting::Table)
+                    .drop_column(Setting::UserRecoveryCodeHashes)
+                    .drop_column(Setting::UserTotpEnabled)
+                    .drop_column(Setting::UserTotpSecret)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Passkey {
+    Table,
+    Id,
+    Name,
+    CredentialId,
+    PublicKey,
+    Algorithm,
+    SignCount,
+    CreatedAt,
+    LastUsedAt,
+}
+
+#[derive(Iden)]
+enum PasskeyChallenge {
+    Table,
+    Challenge,
+    ExpiresAt,
+}
@@ -0,0 +1,92 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20230815_033104_notification::Notification;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Fills the new columns from the payloads, whose IDs are ULID strings.
+const BACKFILL: &str = r#"
+CREATE FUNCTION "ulid_to_uuid"("ulid" text) RETURNS uuid AS $$
+DECLARE
+    "alphabet" CONSTANT text := '0123456789ABCDEFGHJKMNPQRSTVWXYZ';
+    "bits" text := '';
+    "hex" text := '';
+BEGIN
+    FOR "i" IN 1..26 LOOP
+        "bits" := "bits" || (position(upper(substr("ulid", "i", 1)) IN "alphabet") - 1)::bit(5)::text;
+    END LOOP;
+    "bits" := substr("bits", 3);
+    FOR "i" IN 0..31 LOOP
+        "hex" := "hex" || to_hex(substr("bits", "i" * 4 + 1, 4)::bit(4)::int);
+    END LOOP;
+    RETURN "hex"::uuid;
+END
+$$ LANGUAGE plpgsql IMMUTABLE;
+
+UPDATE "notification" SET
+    "user_ids" = ARRAY(
+        SELECT "ulid_to_uuid"("value") FROM jsonb_each_text("payload")
+        WHERE "key" IN ('userId', 'targetUserId')
+    ),
+    "post_id" = CASE WHEN "payload" ? 'postId' THEN "ulid_to_uuid"("payload" ->> 'postId') END;
+
+UPDATE "notification" SET "user_ids" = "notification"."user_ids" || "reaction"."user_id"
+FROM "reaction"
+WHERE "notification"."payload" ->> 'type' = 'reacted'
+    AND "reaction"."id" = "ulid_to_uuid"("notification"."payload" ->> 'reactionId')
+    AND "reaction"."user_id" IS NOT NULL;
+
+DROP FUNCTION "ulid_to_uuid"(text);
+"#;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Notification::Table)
+                    .add_column(
+                        ColumnDef::new(Notification::UserIds)
+                            .array(ColumnType::Uuid)
+                            .not_null()
+                            .default(Expr::cust("'{}'")),
+                    )
+                    .add_column(ColumnDef::new(Notification::PostId).uuid())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared(BACKFILL)
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_notification_post_id")
+                    .table(Notification::Table)
+                    .col(Notification::PostId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Notification::Table)
+                    .drop_column(Notification::PostId)
+                    .drop_column(Notification::UserIds)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20230806_104639_initial::AccessKey;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Existing tokens are the public IDs of their access keys, so they are revoked
+        manager
+            .exec_stmt(Query::delete().from_table(AccessKey::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AccessKey::Table)
+                    .add_column(
+                        ColumnDef::new(AccessKey::SecretHash)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AccessKey::Table)
+                    .drop_column(AccessKey::SecretHash)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}